@@ -0,0 +1,63 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use manifest_parser_rs::{Backend, BorrowedManifest, Manifest};
+use std::fmt::Write;
+use std::hint::black_box;
+
+const SMALL: &str = include_str!("../tests/corpus/full.toml");
+
+/// Builds a manifest with `count` dependencies, targets, features and profiles.
+fn large_manifest(count: usize) -> String {
+    let mut manifest = String::from("[package]\nname = \"large\"\nversion = \"1.0.0\"\n");
+    manifest.push_str("\n[dependencies]\n");
+    for index in 0..count {
+        writeln!(
+            manifest,
+            "crate-{index} = {{ version = \"^{index}.2\", features = [\"std\", \"derive\"], optional = true }}"
+        )
+        .unwrap();
+    }
+    for index in 0..count / 10 {
+        writeln!(
+            manifest,
+            "\n[target.'cfg(target_os = \"os{index}\")'.dev-dependencies]\nlocal-{index} = {{ path = \"../local-{index}\" }}"
+        )
+        .unwrap();
+    }
+    manifest.push_str("\n[features]\n");
+    for index in 0..count {
+        writeln!(manifest, "feature-{index} = [\"dep:crate-{index}\"]").unwrap();
+    }
+    for index in 0..count / 10 {
+        writeln!(manifest, "\n[profile.custom-{index}]\nopt-level = 3 # fast").unwrap();
+    }
+    manifest
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let large = large_manifest(2000);
+    let inputs = [("small", SMALL), ("large", large.as_str())];
+
+    for (name, input) in inputs {
+        let mut group = c.benchmark_group(format!("parse/{name}"));
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        if name == "large" {
+            group.sample_size(10);
+        }
+        for backend in [Backend::Pest, Backend::Handwritten] {
+            group.bench_with_input(
+                BenchmarkId::new(format!("{backend:?}"), input.len()),
+                input,
+                |b, input| b.iter(|| Manifest::parse_with(black_box(input), backend).unwrap()),
+            );
+        }
+        group.bench_with_input(
+            BenchmarkId::new("Borrowed", input.len()),
+            input,
+            |b, input| b.iter(|| BorrowedManifest::parse(black_box(input)).unwrap()),
+        );
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_parse);
+criterion_main!(benches);
@@ -0,0 +1,102 @@
+/* C API of manifest_parser_rs. Generated by cbindgen from src/ffi.rs, do not edit. */
+
+#ifndef MANIFEST_PARSER_H
+#define MANIFEST_PARSER_H
+
+#include <stddef.h>
+
+// A parsed manifest owned by the caller of the C API.
+typedef struct MpManifest MpManifest;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Parses a manifest.
+//
+// # Arguments
+//
+// * `input` - The manifest content as UTF-8, which does not need to be NUL-terminated
+// * `len` - The length of the content in bytes
+//
+// # Returns
+//
+// The parsed manifest, to be released with `mp_manifest_free`, or `NULL` if
+// the content is not valid UTF-8 or is not a valid manifest
+//
+// # Safety
+//
+// `input` must point to `len` readable bytes.
+struct MpManifest *mp_manifest_parse(const char *input,
+                                     size_t len);
+
+// Retrieves a value from a manifest given a section and key.
+//
+// # Arguments
+//
+// * `manifest` - The manifest
+// * `section` - The NUL-terminated section name, such as `package` or `dependencies`
+// * `key` - The NUL-terminated key within the section
+//
+// # Returns
+//
+// The value as `get_by_key` returns it, to be released with `mp_string_free`,
+// or `NULL` if the section or key does not exist
+//
+// # Safety
+//
+// `manifest` must come from `mp_manifest_parse` and not be freed, and
+// `section` and `key` must be NUL-terminated strings.
+char *mp_manifest_get(const struct MpManifest *manifest,
+                      const char *section,
+                      const char *key);
+
+// Serializes a manifest as compact JSON, the same typed tree that `parse --format json` prints.
+//
+// # Arguments
+//
+// * `manifest` - The manifest
+//
+// # Returns
+//
+// The JSON document, to be released with `mp_string_free`, or `NULL` on failure
+//
+// # Safety
+//
+// `manifest` must come from `mp_manifest_parse` and not be freed.
+char *mp_manifest_to_json(const struct MpManifest *manifest);
+
+// Releases a manifest. Passing `NULL` does nothing.
+//
+// # Safety
+//
+// `manifest` must come from `mp_manifest_parse` and not be freed already.
+void mp_manifest_free(struct MpManifest *manifest);
+
+// Releases a string returned by this library. Passing `NULL` does nothing.
+//
+// # Safety
+//
+// `string` must come from this library and not be freed already.
+void mp_string_free(char *string);
+
+// Retrieves the error message of the last parse, get or JSON call on this thread.
+//
+// # Returns
+//
+// The message, valid until the next such call on the same thread, or `NULL`
+// if the last call succeeded
+const char *mp_last_error(void);
+
+// Retrieves the version of the library, such as `0.2.0`.
+//
+// # Returns
+//
+// A static NUL-terminated string that must not be freed
+const char *mp_version(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* MANIFEST_PARSER_H */
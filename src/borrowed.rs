@@ -0,0 +1,322 @@
+//! A manifest that borrows its sections, keys and values from the input.
+//!
+//! `Manifest::parse` copies every key and value into a `String` and builds the
+//! typed dependencies, patches and lints. `BorrowedManifest::parse` walks the
+//! same parse tree but only records slices of the input, which makes it the
+//! cheaper choice for scanning many manifests for a few values. Values are kept
+//! exactly as `Manifest::get_by_key` returns them, with the surrounding quotes
+//! removed and nothing unescaped, so every value is a slice of the input.
+
+use crate::query::split_table_name;
+use crate::{
+    header_name, patch_table, Lint, Manifest, ManifestError, ManifestParser, Platform, Rule, Span,
+};
+use indexmap::IndexMap;
+use pest::iterators::{Pair, Pairs};
+use pest::Parser;
+
+/// A parsed manifest whose section names, keys and values point into its input.
+///
+/// Sections and their keys are kept in the order they first appear in the source.
+#[derive(Debug, Clone, Default)]
+pub struct BorrowedManifest<'a> {
+    /// The manifest source
+    source: &'a str,
+    /// Map of section names to their key-value pairs
+    sections: IndexMap<&'a str, IndexMap<&'a str, &'a str>>,
+    /// Location of every section in the source
+    section_spans: IndexMap<&'a str, Span>,
+    /// Location of every `key = value` entry in the source, per section
+    key_spans: IndexMap<&'a str, IndexMap<&'a str, Span>>,
+    /// The parse tree of the source, kept for `into_owned`
+    tree: Option<Pairs<'a, Rule>>,
+}
+
+impl<'a> BorrowedManifest<'a> {
+    /// Parses a manifest string without copying its keys and values.
+    ///
+    /// The same manifests are accepted as by `Manifest::parse`, including the
+    /// checks of target platforms, lints and `[replace]` package ids.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The manifest content, which the result borrows from
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing either a parsed `BorrowedManifest` or a `ManifestError`
+    pub fn parse(input: &'a str) -> Result<Self, ManifestError> {
+        let mut manifest = BorrowedManifest {
+            source: input,
+            ..BorrowedManifest::default()
+        };
+        let parsed_item = ManifestParser::parse(Rule::manifest, input)
+            .map_err(|e| ManifestError::ParseError(e.to_string()))?;
+        manifest.tree = Some(parsed_item.clone());
+        let mut current_section = None;
+
+        for item in parsed_item.flatten() {
+            match item.as_rule() {
+                Rule::section => {
+                    let span = Span::from_pest(item.as_span());
+                    // A repeated generic section such as a second `[[bin]]` replaces the first
+                    let section = section_name(item);
+                    manifest.sections.insert(section, IndexMap::new());
+                    manifest.key_spans.insert(section, IndexMap::new());
+                    manifest.section_spans.insert(section, span);
+                    current_section = Some(section);
+                }
+                Rule::key_value => {
+                    if let Some(section) = current_section {
+                        manifest.insert_pair(section, item);
+                    }
+                }
+                Rule::package_section => manifest.parse_package_section(item),
+                Rule::dependencies_section => manifest.parse_dependencies_section(item)?,
+                Rule::patch_section => manifest.parse_table_section(item),
+                Rule::replace_section => manifest.parse_replace_section(item)?,
+                Rule::lints_section => manifest.parse_lints_section(item)?,
+                _ => {}
+            }
+        }
+
+        for (section, spans) in &manifest.key_spans {
+            if let [patch, _, name] = split_table_name(section).as_slice() {
+                if patch == "patch" {
+                    let fields = spans
+                        .values()
+                        .map(|span| input[span.start..span.end].to_string());
+                    patch_table(section, name, fields)?;
+                }
+            }
+        }
+
+        Ok(manifest)
+    }
+
+    /// Retrieves the source the manifest was parsed from.
+    pub fn source(&self) -> &'a str {
+        self.source
+    }
+
+    /// Retrieves an iterator over the section names in the manifest.
+    ///
+    /// # Returns
+    ///
+    /// An iterator yielding the section names as slices of the input, in the order
+    /// the sections first appear in the source.
+    pub fn sections(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.sections.keys().copied()
+    }
+
+    /// Retrieves an iterator over the sections of the manifest along with their key-value pairs.
+    ///
+    /// # Returns
+    ///
+    /// An iterator yielding each section name and its map of key-value pairs, with
+    /// sections and keys in the order they first appear in the source.
+    pub fn iter(&self) -> impl Iterator<Item = (&'a str, &IndexMap<&'a str, &'a str>)> + '_ {
+        self.sections
+            .iter()
+            .map(|(section, entries)| (*section, entries))
+    }
+
+    /// Retrieves a value from the manifest given a section and key.
+    ///
+    /// # Arguments
+    ///
+    /// * `section` - The section name
+    /// * `key` - The key within the section
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing either the value as a slice of the input or a `ManifestError`
+    pub fn get_by_key(&self, section: &str, key: &str) -> Result<&'a str, ManifestError> {
+        self.get_by_section(section)?
+            .get(key)
+            .copied()
+            .ok_or_else(|| ManifestError::MissingKey(section.to_string(), key.to_string()))
+    }
+
+    /// Retrieves a section from the manifest as a map of key-value pairs.
+    ///
+    /// # Arguments
+    ///
+    /// * `section` - The section name
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing either the section map or a `ManifestError`
+    pub fn get_by_section(
+        &self,
+        section: &str,
+    ) -> Result<&IndexMap<&'a str, &'a str>, ManifestError> {
+        self.sections
+            .get(section)
+            .ok_or_else(|| ManifestError::MissingSection(section.to_string()))
+    }
+
+    /// Retrieves the location of a section in the source.
+    ///
+    /// # Arguments
+    ///
+    /// * `section` - The section name
+    ///
+    /// # Returns
+    ///
+    /// The span of the whole section starting at its header, or `None` if the section does not exist
+    pub fn section_span(&self, section: &str) -> Option<Span> {
+        self.section_spans.get(section).copied()
+    }
+
+    /// Retrieves the location of a `key = value` entry in the source.
+    ///
+    /// # Arguments
+    ///
+    /// * `section` - The section name
+    /// * `key` - The key within the section
+    ///
+    /// # Returns
+    ///
+    /// The span of the entry starting at its key, or `None` if the key does not exist
+    pub fn key_span(&self, section: &str, key: &str) -> Option<Span> {
+        self.key_spans.get(section)?.get(key).copied()
+    }
+
+    /// Retrieves the name of the package described by the manifest.
+    pub fn package_name(&self) -> &'a str {
+        self.get_by_key("package", "name").unwrap_or_default()
+    }
+
+    /// Converts the manifest into an owned `Manifest`.
+    ///
+    /// The owned manifest is built from the parse tree kept by `parse`, so the
+    /// source is not parsed again; the typed dependencies, patches and lints are
+    /// built at this point.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing either the owned `Manifest` of the same source or a `ManifestError`
+    pub fn into_owned(self) -> Result<Manifest, ManifestError> {
+        match self.tree {
+            Some(tree) => Manifest::from_pairs(tree),
+            None => Ok(Manifest::default()),
+        }
+    }
+
+    /// Creates a section if it does not exist yet and records its location.
+    fn open_section(&mut self, section: &'a str, span: Span) {
+        self.sections.entry(section).or_default();
+        self.key_spans.entry(section).or_default();
+        self.section_spans.entry(section).or_insert(span);
+    }
+
+    /// Stores a `key = value` entry of a section along with its location.
+    fn insert_entry(&mut self, section: &'a str, key: &'a str, value: &'a str, span: Span) {
+        if let Some(section_map) = self.sections.get_mut(section) {
+            section_map.insert(key, value.trim_matches('"'));
+            self.key_spans.entry(section).or_default().insert(key, span);
+        }
+    }
+
+    /// Stores a `key = value` pair whose first inner pair is the key.
+    fn insert_pair(&mut self, section: &'a str, item: Pair<'a, Rule>) {
+        let span = Span::from_pest(item.as_span());
+        let mut inner = item.into_inner();
+        let key = inner.next().unwrap().as_str().trim();
+        let value = inner.next().unwrap().as_str().trim();
+        self.insert_entry(section, key, value, span);
+    }
+
+    /// Records the `[package]` section.
+    fn parse_package_section(&mut self, item: Pair<'a, Rule>) {
+        self.open_section("package", Span::from_pest(item.as_span()));
+
+        for field in item.into_inner() {
+            let span = Span::from_pest(field.as_span());
+            match field.as_rule() {
+                Rule::package_name | Rule::package_version => {
+                    let key = if field.as_rule() == Rule::package_name {
+                        "name"
+                    } else {
+                        "version"
+                    };
+                    let value = field.into_inner().next().unwrap().as_str().trim();
+                    self.insert_entry("package", key, value, span);
+                }
+                Rule::section_inside => {
+                    for entry in field.into_inner() {
+                        self.insert_pair("package", entry);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Records a dependencies section, checking the platform of a `[target.*]` table.
+    fn parse_dependencies_section(&mut self, item: Pair<'a, Rule>) -> Result<(), ManifestError> {
+        let header = item.clone().into_inner().next().unwrap();
+        let table = header.into_inner().next().unwrap();
+        for part in table.into_inner() {
+            if part.as_rule() == Rule::table_key {
+                Platform::parse(part.as_str())?;
+            }
+        }
+        self.parse_table_section(item);
+        Ok(())
+    }
+
+    /// Records a section made of a header and `key = value` pairs, such as a
+    /// dependencies or `[patch.<source>]` table.
+    fn parse_table_section(&mut self, item: Pair<'a, Rule>) {
+        let section_span = Span::from_pest(item.as_span());
+        let mut inner = item.into_inner();
+        let table = inner.next().unwrap().into_inner().next().unwrap();
+        let section = table.as_str();
+        self.open_section(section, section_span);
+
+        for entry in inner {
+            self.insert_pair(section, entry);
+        }
+    }
+
+    /// Records the `[replace]` section, checking that every key is a `name:version` id.
+    fn parse_replace_section(&mut self, item: Pair<'a, Rule>) -> Result<(), ManifestError> {
+        self.open_section("replace", Span::from_pest(item.as_span()));
+
+        for entry in item.into_inner() {
+            let span = Span::from_pest(entry.as_span());
+            let mut inner = entry.into_inner();
+            let key = inner.next().unwrap().into_inner().next().unwrap().as_str();
+            if !key.contains(':') {
+                return Err(ManifestError::ParseError(format!(
+                    "Invalid package id {}",
+                    key
+                )));
+            }
+            let spec = inner.next().unwrap().as_str().trim();
+            self.insert_entry("replace", key, spec, span);
+        }
+
+        Ok(())
+    }
+
+    /// Records a `[lints.<tool>]` or `[workspace.lints.<tool>]` section, checking every lint.
+    fn parse_lints_section(&mut self, item: Pair<'a, Rule>) -> Result<(), ManifestError> {
+        let mut inner = item.clone().into_inner();
+        let table = inner.next().unwrap().into_inner().next().unwrap();
+        let section = table.as_str();
+        let tool = table.into_inner().next().unwrap().as_str();
+        for entry in inner {
+            Lint::from_pair(entry, tool, section)?;
+        }
+        self.parse_table_section(item);
+        Ok(())
+    }
+}
+
+/// Returns the name of a generic section without its brackets.
+fn section_name(item: Pair<'_, Rule>) -> &str {
+    header_name(item.into_inner().next().unwrap().as_str())
+}
@@ -0,0 +1,404 @@
+//! On-disk cache of parsed manifests, keyed by content hash.
+//!
+//! Every entry is a JSON file named after the SHA-256 of this crate's version and
+//! the manifest content, so an edited manifest or an upgrade of the parser simply
+//! misses the cache and nothing has to be invalidated by hand. Entries hold the
+//! whole parsed `Manifest`, spans and typed tables included. When the entries
+//! outgrow the size limit, the least recently used ones are removed. Files of
+//! the directory that are not named like an entry are never touched.
+
+use crate::{Dependency, Lint, Manifest, ManifestError, PatchEntry, ReplaceEntry, Span};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::SystemTime;
+
+/// Version of the crate that wrote an entry; other versions may parse differently.
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Extension of entry files.
+const ENTRY_EXTENSION: &str = "json";
+
+/// Extension of entries that are still being written.
+const TEMP_EXTENSION: &str = "tmp";
+
+/// Default size limit of a cache, 64 MiB.
+pub const DEFAULT_MAX_SIZE: u64 = 64 * 1024 * 1024;
+
+/// A cached parse result: every field of the `Manifest` and the version that wrote it.
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    /// Version of the crate that wrote the entry; the other fields are those of `Manifest`
+    version: String,
+    sections: IndexMap<String, IndexMap<String, String>>,
+    raw_values: IndexMap<String, IndexMap<String, String>>,
+    array_tables: IndexMap<String, Vec<IndexMap<String, String>>>,
+    dependencies: Vec<Dependency>,
+    patches: Vec<PatchEntry>,
+    replacements: Vec<ReplaceEntry>,
+    lints: Vec<Lint>,
+    workspace_lints: Vec<Lint>,
+    section_spans: IndexMap<String, Span>,
+    key_spans: IndexMap<String, IndexMap<String, Span>>,
+}
+
+impl Entry {
+    fn new(manifest: &Manifest) -> Self {
+        Entry {
+            version: VERSION.to_string(),
+            sections: manifest.sections.clone(),
+            raw_values: manifest.raw_values.clone(),
+            array_tables: manifest.array_tables.clone(),
+            dependencies: manifest.dependencies.clone(),
+            patches: manifest.patches.clone(),
+            replacements: manifest.replacements.clone(),
+            lints: manifest.lints.clone(),
+            workspace_lints: manifest.workspace_lints.clone(),
+            section_spans: manifest.section_spans.clone(),
+            key_spans: manifest.key_spans.clone(),
+        }
+    }
+
+    fn into_manifest(self) -> Manifest {
+        Manifest {
+            sections: self.sections,
+            raw_values: self.raw_values,
+            array_tables: self.array_tables,
+            dependencies: self.dependencies,
+            patches: self.patches,
+            replacements: self.replacements,
+            lints: self.lints,
+            workspace_lints: self.workspace_lints,
+            section_spans: self.section_spans,
+            key_spans: self.key_spans,
+        }
+    }
+}
+
+/// A directory of parsed manifests that can be shared between threads and processes.
+#[derive(Debug)]
+pub struct ManifestCache {
+    /// Directory holding the entries
+    dir: PathBuf,
+    /// Size limit of the entries in bytes
+    max_size: u64,
+    /// Size of the entries in bytes, as far as this cache knows
+    size: AtomicU64,
+    /// Counter making the names of entries being written unique
+    writes: AtomicUsize,
+}
+
+impl ManifestCache {
+    /// Opens a cache directory, creating it if needed, with the default 64 MiB size limit.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The directory holding the entries
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing either the `ManifestCache` or a `ManifestError` if the
+    /// directory cannot be created or read
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self, ManifestError> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir).map_err(|e| cache_error(&dir, e))?;
+        let cache = ManifestCache {
+            dir,
+            max_size: DEFAULT_MAX_SIZE,
+            size: AtomicU64::new(0),
+            writes: AtomicUsize::new(0),
+        };
+        let size = cache.entries()?.iter().map(|entry| entry.len).sum();
+        cache.size.store(size, Ordering::Relaxed);
+        Ok(cache)
+    }
+
+    /// Sets the size limit of the entries in bytes.
+    pub fn with_max_size(mut self, max_size: u64) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Retrieves the directory holding the entries.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Retrieves the size of the entries in bytes, including those written by
+    /// other processes when the cache was opened or last pruned.
+    pub fn size(&self) -> u64 {
+        self.size.load(Ordering::Relaxed)
+    }
+
+    /// Computes the key of a manifest's entry.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - The manifest content
+    ///
+    /// # Returns
+    ///
+    /// The hex SHA-256 of the crate version and the content
+    pub fn key(content: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(VERSION.as_bytes());
+        hasher.update([0]);
+        hasher.update(content.as_bytes());
+        hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    /// Parses a manifest, reusing the cached result for the same content.
+    ///
+    /// Manifests that do not parse are not cached. The cache only saves work, so
+    /// an entry that cannot be written does not fail the parse.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - The manifest content
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing either the parsed `Manifest` or the `ManifestError` of the parse
+    pub fn parse(&self, content: &str) -> Result<Manifest, ManifestError> {
+        if let Some(manifest) = self.get(content) {
+            return Ok(manifest);
+        }
+        let manifest = Manifest::parse(content)?;
+        let _ = self.insert(content, &manifest);
+        Ok(manifest)
+    }
+
+    /// Retrieves the cached result for a manifest's content.
+    ///
+    /// An entry that cannot be read, e.g. because it was cut short or written by
+    /// another version, is removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - The manifest content
+    ///
+    /// # Returns
+    ///
+    /// The cached `Manifest`, or `None` if there is no usable entry
+    pub fn get(&self, content: &str) -> Option<Manifest> {
+        let path = self.entry_path(&ManifestCache::key(content));
+        let data = fs::read(&path).ok()?;
+        match serde_json::from_slice::<Entry>(&data) {
+            Ok(entry) if entry.version == VERSION => {
+                // Mark the entry as recently used, which keeps it when the cache is pruned
+                let _ = File::options()
+                    .append(true)
+                    .open(&path)
+                    .and_then(|file| file.set_modified(SystemTime::now()));
+                Some(entry.into_manifest())
+            }
+            _ => {
+                if fs::remove_file(&path).is_ok() {
+                    self.shrink(data.len() as u64);
+                }
+                None
+            }
+        }
+    }
+
+    /// Stores the parse result of a manifest's content, pruning the cache if it
+    /// grows over its size limit.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - The manifest content
+    /// * `manifest` - The manifest parsed from it
+    ///
+    /// # Returns
+    ///
+    /// A `Result` that is a `ManifestError` if the entry cannot be written
+    pub fn insert(&self, content: &str, manifest: &Manifest) -> Result<(), ManifestError> {
+        let key = ManifestCache::key(content);
+        let path = self.entry_path(&key);
+        let data = serde_json::to_vec(&Entry::new(manifest))
+            .map_err(|e| ManifestError::Cache(format!("{}: {}", path.display(), e)))?;
+
+        // Entries are written aside and renamed, so readers never see half an entry
+        let temp = self.dir.join(format!(
+            "{}.{}.{}.{}",
+            key,
+            std::process::id(),
+            self.writes.fetch_add(1, Ordering::Relaxed),
+            TEMP_EXTENSION
+        ));
+        fs::write(&temp, &data).map_err(|e| cache_error(&temp, e))?;
+        let previous = fs::metadata(&path).map_or(0, |metadata| metadata.len());
+        if let Err(e) = fs::rename(&temp, &path) {
+            let _ = fs::remove_file(&temp);
+            return Err(cache_error(&path, e));
+        }
+
+        self.shrink(previous);
+        let size = self.size.fetch_add(data.len() as u64, Ordering::Relaxed) + data.len() as u64;
+        if size > self.max_size {
+            self.prune()?;
+        }
+        Ok(())
+    }
+
+    /// Removes the entry of a manifest's content.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - The manifest content
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing either whether there was an entry or a `ManifestError`
+    pub fn remove(&self, content: &str) -> Result<bool, ManifestError> {
+        let path = self.entry_path(&ManifestCache::key(content));
+        let Ok(metadata) = fs::metadata(&path) else {
+            return Ok(false);
+        };
+        fs::remove_file(&path).map_err(|e| cache_error(&path, e))?;
+        self.shrink(metadata.len());
+        Ok(true)
+    }
+
+    /// Removes every entry, along with any left half-written by an interrupted process.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing either the number of entries removed or a `ManifestError`
+    pub fn clear(&self) -> Result<usize, ManifestError> {
+        let mut removed = 0;
+        for file in self.files()? {
+            fs::remove_file(&file.path).map_err(|e| cache_error(&file.path, e))?;
+            if file.is_entry {
+                removed += 1;
+            }
+        }
+        self.size.store(0, Ordering::Relaxed);
+        Ok(removed)
+    }
+
+    /// Removes the least recently used entries while the cache is over its size limit.
+    ///
+    /// Entries are removed down to three quarters of the limit, so that a full
+    /// cache is not pruned again on every insert.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing either the number of entries removed or a `ManifestError`
+    pub fn prune(&self) -> Result<usize, ManifestError> {
+        let mut entries = self.entries()?;
+        let mut size: u64 = entries.iter().map(|entry| entry.len).sum();
+        let mut removed = 0;
+        if size > self.max_size {
+            entries.sort_by_key(|entry| entry.modified);
+            for entry in entries {
+                if size <= self.max_size / 4 * 3 {
+                    break;
+                }
+                // Another process may have removed it already
+                if fs::remove_file(&entry.path).is_ok() {
+                    removed += 1;
+                }
+                size -= entry.len;
+            }
+        }
+        self.size.store(size, Ordering::Relaxed);
+        Ok(removed)
+    }
+
+    /// Builds the path of the entry with a key.
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(key).with_extension(ENTRY_EXTENSION)
+    }
+
+    /// Subtracts the size of a removed or replaced entry.
+    fn shrink(&self, len: u64) {
+        let _ = self
+            .size
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |size| {
+                Some(size.saturating_sub(len))
+            });
+    }
+
+    /// Lists the complete entries.
+    fn entries(&self) -> Result<Vec<CacheFile>, ManifestError> {
+        Ok(self
+            .files()?
+            .into_iter()
+            .filter(|file| file.is_entry)
+            .collect())
+    }
+
+    /// Lists the entries and the entries being written.
+    fn files(&self) -> Result<Vec<CacheFile>, ManifestError> {
+        let mut files = Vec::new();
+        let dir = fs::read_dir(&self.dir).map_err(|e| cache_error(&self.dir, e))?;
+        for item in dir {
+            let path = item.map_err(|e| cache_error(&self.dir, e))?.path();
+            // Other files that happen to be in the directory are left alone
+            let Some(is_entry) = file_kind(&path) else {
+                continue;
+            };
+            // Skip files removed since the directory was read
+            let Ok(metadata) = fs::metadata(&path) else {
+                continue;
+            };
+            files.push(CacheFile {
+                path,
+                is_entry,
+                len: metadata.len(),
+                modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            });
+        }
+        Ok(files)
+    }
+}
+
+/// A file of the cache directory.
+struct CacheFile {
+    /// Path of the file
+    path: PathBuf,
+    /// Whether the file is a complete entry rather than one being written
+    is_entry: bool,
+    /// Size in bytes
+    len: u64,
+    /// Last modification time, which `get` updates
+    modified: SystemTime,
+}
+
+/// Tells a cache file from other files by its name: `<key>.json` is an entry
+/// and `<key>.<pid>.<n>.tmp` one being written.
+///
+/// # Returns
+///
+/// Whether the file is a complete entry, or `None` if it is not a cache file
+fn file_kind(path: &Path) -> Option<bool> {
+    let name = path.file_name()?.to_str()?;
+    let parts: Vec<&str> = name.split('.').collect();
+    let is_key = |part: &str| {
+        part.len() == 64
+            && part
+                .bytes()
+                .all(|byte| byte.is_ascii_digit() || (b'a'..=b'f').contains(&byte))
+    };
+    let is_number = |part: &str| !part.is_empty() && part.bytes().all(|byte| byte.is_ascii_digit());
+    match parts.as_slice() {
+        [key, ENTRY_EXTENSION] if is_key(key) => Some(true),
+        [key, pid, n, TEMP_EXTENSION] if is_key(key) && is_number(pid) && is_number(n) => {
+            Some(false)
+        }
+        _ => None,
+    }
+}
+
+/// Describes a failure to read or write a file of the cache.
+fn cache_error(path: &Path, e: std::io::Error) -> ManifestError {
+    ManifestError::Cache(format!("{}: {}", path.display(), e))
+}
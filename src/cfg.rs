@@ -0,0 +1,271 @@
+//! `cfg()` expressions used by `[target.'cfg(...)'.dependencies]` tables.
+
+use crate::ManifestError;
+use pest::Parser;
+use pest_derive::Parser;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashSet;
+use std::fmt;
+
+/// The parser for `cfg(...)` expressions.
+#[derive(Parser)]
+#[grammar = "cfg.pest"]
+pub struct CfgParser;
+
+/// A parsed `cfg()` predicate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CfgExpr {
+    /// A bare name such as `unix`
+    Name(String),
+    /// A key-value pair such as `target_os = "linux"`
+    KeyValue(String, String),
+    /// `all(...)`, true when every predicate is true
+    All(Vec<CfgExpr>),
+    /// `any(...)`, true when at least one predicate is true
+    Any(Vec<CfgExpr>),
+    /// `not(...)`
+    Not(Box<CfgExpr>),
+}
+
+impl CfgExpr {
+    /// Parses a `cfg(...)` expression.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The expression including the surrounding `cfg(...)`
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing either the parsed expression or a `ManifestError`
+    pub fn parse(input: &str) -> Result<Self, ManifestError> {
+        let cfg = CfgParser::parse(Rule::cfg, input.trim())
+            .map_err(|e| ManifestError::ParseError(e.to_string()))?
+            .next()
+            .unwrap();
+
+        Ok(build_expr(cfg.into_inner().next().unwrap()))
+    }
+
+    /// Evaluates the expression against a set of cfg names and values.
+    pub fn eval(&self, cfg: &CfgSet) -> bool {
+        match self {
+            CfgExpr::Name(name) => cfg.names.contains(name),
+            CfgExpr::KeyValue(key, value) => cfg.values.contains(&(key.clone(), value.clone())),
+            CfgExpr::All(exprs) => exprs.iter().all(|expr| expr.eval(cfg)),
+            CfgExpr::Any(exprs) => exprs.iter().any(|expr| expr.eval(cfg)),
+            CfgExpr::Not(expr) => !expr.eval(cfg),
+        }
+    }
+}
+
+impl fmt::Display for CfgExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn list(f: &mut fmt::Formatter<'_>, name: &str, exprs: &[CfgExpr]) -> fmt::Result {
+            write!(f, "{}(", name)?;
+            for (i, expr) in exprs.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+                write!(f, "{}", expr)?;
+            }
+            f.write_str(")")
+        }
+
+        match self {
+            CfgExpr::Name(name) => f.write_str(name),
+            CfgExpr::KeyValue(key, value) => write!(f, "{} = \"{}\"", key, value),
+            CfgExpr::All(exprs) => list(f, "all", exprs),
+            CfgExpr::Any(exprs) => list(f, "any", exprs),
+            CfgExpr::Not(expr) => write!(f, "not({})", expr),
+        }
+    }
+}
+
+/// Serializes the predicate as written inside `cfg(...)`, e.g. `target_os = "linux"`.
+impl Serialize for CfgExpr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Builds an expression from a pair of the `expression` rule.
+fn build_expr(item: pest::iterators::Pair<Rule>) -> CfgExpr {
+    match item.as_rule() {
+        Rule::identifier => CfgExpr::Name(item.as_str().to_string()),
+        Rule::key_value => {
+            let mut inner = item.into_inner();
+            let key = inner.next().unwrap().as_str().to_string();
+            let value = inner.next().unwrap().into_inner().next().unwrap().as_str();
+            CfgExpr::KeyValue(key, value.to_string())
+        }
+        Rule::all => CfgExpr::All(item.into_inner().map(build_expr).collect()),
+        Rule::any => CfgExpr::Any(item.into_inner().map(build_expr).collect()),
+        Rule::not => CfgExpr::Not(Box::new(build_expr(item.into_inner().next().unwrap()))),
+        _ => unreachable!("unexpected rule in cfg expression: {:?}", item.as_rule()),
+    }
+}
+
+/// The platform a `[target.<platform>.*]` table applies to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Platform {
+    /// An exact target triple such as `x86_64-pc-windows-msvc`
+    Triple(String),
+    /// A `cfg(...)` expression
+    Cfg(CfgExpr),
+}
+
+impl Platform {
+    /// Parses the platform part of a target table name, with or without quotes.
+    ///
+    /// # Arguments
+    ///
+    /// * `spec` - A target triple or `cfg(...)` expression
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing either the platform or a `ManifestError`
+    pub fn parse(spec: &str) -> Result<Self, ManifestError> {
+        let spec = unquote_key(spec.trim());
+        if spec.starts_with("cfg(") {
+            Ok(Platform::Cfg(CfgExpr::parse(&spec)?))
+        } else {
+            Ok(Platform::Triple(spec))
+        }
+    }
+
+    /// Checks whether the platform applies to the target described by `cfg`.
+    pub fn matches(&self, cfg: &CfgSet) -> bool {
+        match self {
+            Platform::Triple(triple) => *triple == cfg.triple,
+            Platform::Cfg(expr) => expr.eval(cfg),
+        }
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Platform::Triple(triple) => f.write_str(triple),
+            Platform::Cfg(expr) => write!(f, "cfg({})", expr),
+        }
+    }
+}
+
+/// Serializes the platform as written in a target table name, e.g. `cfg(unix)`.
+impl Serialize for Platform {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Deserializes the platform from a target table name, e.g. `cfg(unix)`.
+impl<'de> Deserialize<'de> for Platform {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let platform = String::deserialize(deserializer)?;
+        Platform::parse(&platform).map_err(de::Error::custom)
+    }
+}
+
+/// Removes the quotes of a literal (`'...'`) or basic (`"..."`) TOML key.
+pub(crate) fn unquote_key(key: &str) -> String {
+    if key.len() >= 2 && key.starts_with('\'') && key.ends_with('\'') {
+        key[1..key.len() - 1].to_string()
+    } else if key.len() >= 2 && key.starts_with('"') && key.ends_with('"') {
+        key[1..key.len() - 1].replace("\\\"", "\"")
+    } else {
+        key.to_string()
+    }
+}
+
+/// The cfg names and key-value pairs that hold for a target.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CfgSet {
+    /// Target triple
+    triple: String,
+    /// Names that are set, e.g. `unix`
+    names: HashSet<String>,
+    /// Key-value pairs that are set, e.g. `target_os = "linux"`
+    values: HashSet<(String, String)>,
+}
+
+/// Cfg values of the bundled targets:
+/// triple, arch, os, family, env, vendor, pointer width.
+#[rustfmt::skip]
+const TARGETS: &[(&str, &str, &str, &str, &str, &str, &str)] = &[
+    ("x86_64-unknown-linux-gnu",      "x86_64",  "linux",   "unix",    "gnu",  "unknown", "64"),
+    ("x86_64-unknown-linux-musl",     "x86_64",  "linux",   "unix",    "musl", "unknown", "64"),
+    ("aarch64-unknown-linux-gnu",     "aarch64", "linux",   "unix",    "gnu",  "unknown", "64"),
+    ("aarch64-unknown-linux-musl",    "aarch64", "linux",   "unix",    "musl", "unknown", "64"),
+    ("i686-unknown-linux-gnu",        "x86",     "linux",   "unix",    "gnu",  "unknown", "32"),
+    ("armv7-unknown-linux-gnueabihf", "arm",     "linux",   "unix",    "gnu",  "unknown", "32"),
+    ("x86_64-pc-windows-msvc",        "x86_64",  "windows", "windows", "msvc", "pc",      "64"),
+    ("x86_64-pc-windows-gnu",         "x86_64",  "windows", "windows", "gnu",  "pc",      "64"),
+    ("i686-pc-windows-msvc",          "x86",     "windows", "windows", "msvc", "pc",      "32"),
+    ("aarch64-pc-windows-msvc",       "aarch64", "windows", "windows", "msvc", "pc",      "64"),
+    ("x86_64-apple-darwin",           "x86_64",  "macos",   "unix",    "",     "apple",   "64"),
+    ("aarch64-apple-darwin",          "aarch64", "macos",   "unix",    "",     "apple",   "64"),
+    ("aarch64-apple-ios",             "aarch64", "ios",     "unix",    "",     "apple",   "64"),
+    ("aarch64-linux-android",         "aarch64", "android", "unix",    "",     "unknown", "64"),
+    ("x86_64-unknown-freebsd",        "x86_64",  "freebsd", "unix",    "",     "unknown", "64"),
+    ("wasm32-unknown-unknown",        "wasm32",  "unknown", "wasm",    "",     "unknown", "32"),
+    ("wasm32-wasip1",                 "wasm32",  "wasi",    "wasm",    "p1",   "unknown", "32"),
+];
+
+impl CfgSet {
+    /// Creates an empty cfg set for a target triple.
+    pub fn new(triple: impl Into<String>) -> Self {
+        CfgSet {
+            triple: triple.into(),
+            ..CfgSet::default()
+        }
+    }
+
+    /// Returns the bundled cfg set of a common target triple.
+    ///
+    /// # Arguments
+    ///
+    /// * `triple` - The target triple, e.g. `x86_64-unknown-linux-gnu`
+    ///
+    /// # Returns
+    ///
+    /// The cfg set of the target, or `None` if the triple is not bundled
+    pub fn for_target(triple: &str) -> Option<Self> {
+        let &(triple, arch, os, family, env, vendor, width) =
+            TARGETS.iter().find(|target| target.0 == triple)?;
+
+        let mut cfg = CfgSet::new(triple)
+            .with_value("target_arch", arch)
+            .with_value("target_os", os)
+            .with_value("target_family", family)
+            .with_value("target_env", env)
+            .with_value("target_vendor", vendor)
+            .with_value("target_pointer_width", width)
+            .with_value("target_endian", "little");
+        if family != "wasm" {
+            cfg = cfg.with_name(family);
+        }
+        Some(cfg)
+    }
+
+    /// Returns the triples that have a bundled cfg set.
+    pub fn bundled_targets() -> impl Iterator<Item = &'static str> {
+        TARGETS.iter().map(|target| target.0)
+    }
+
+    /// Returns the target triple.
+    pub fn triple(&self) -> &str {
+        &self.triple
+    }
+
+    /// Adds a name such as `unix` or `debug_assertions`.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.names.insert(name.into());
+        self
+    }
+
+    /// Adds a key-value pair such as `feature = "serde"`.
+    pub fn with_value(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.values.insert((key.into(), value.into()));
+        self
+    }
+}
@@ -0,0 +1,225 @@
+//! Typed dependency entries extracted from `[dependencies]`-like tables.
+
+use crate::cfg::{CfgSet, Platform};
+use crate::Rule;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The table a dependency was declared in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyKind {
+    /// `[dependencies]`
+    Normal,
+    /// `[dev-dependencies]`
+    Dev,
+    /// `[build-dependencies]`
+    Build,
+}
+
+impl DependencyKind {
+    /// Returns the kind matching a dependency table name such as `dev-dependencies`.
+    pub fn from_table(table: &str) -> Option<Self> {
+        match table {
+            "dependencies" => Some(DependencyKind::Normal),
+            "dev-dependencies" => Some(DependencyKind::Dev),
+            "build-dependencies" => Some(DependencyKind::Build),
+            _ => None,
+        }
+    }
+
+    /// Returns the table name this kind is declared in.
+    pub fn table(&self) -> &'static str {
+        match self {
+            DependencyKind::Normal => "dependencies",
+            DependencyKind::Dev => "dev-dependencies",
+            DependencyKind::Build => "build-dependencies",
+        }
+    }
+}
+
+impl fmt::Display for DependencyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.table())
+    }
+}
+
+/// A single dependency declaration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Dependency {
+    /// The key the dependency is declared under
+    pub name: String,
+    /// The table the dependency was declared in
+    pub kind: DependencyKind,
+    /// The name of the section the dependency was declared in, as written
+    pub table: String,
+    /// The platform of the `[target.<platform>.*]` table, if any
+    pub target: Option<Platform>,
+    /// The version requirement, if any
+    pub version: Option<String>,
+    /// Git repository URL
+    pub git: Option<String>,
+    /// Git branch to track
+    pub branch: Option<String>,
+    /// Git tag to check out
+    pub tag: Option<String>,
+    /// Git revision to check out
+    pub rev: Option<String>,
+    /// Local path of the dependency
+    pub path: Option<String>,
+    /// Alternative registry name
+    pub registry: Option<String>,
+    /// Real package name when the dependency is renamed
+    pub package: Option<String>,
+    /// Whether the dependency is inherited from the workspace
+    pub workspace: bool,
+    /// Whether the dependency is optional
+    pub optional: bool,
+    /// Whether default features are enabled
+    pub default_features: bool,
+    /// Features enabled on the dependency
+    pub features: Vec<String>,
+}
+
+impl Dependency {
+    /// Creates a dependency with no source information.
+    pub fn new(name: impl Into<String>, kind: DependencyKind) -> Self {
+        Dependency {
+            name: name.into(),
+            kind,
+            table: kind.table().to_string(),
+            target: None,
+            version: None,
+            git: None,
+            branch: None,
+            tag: None,
+            rev: None,
+            path: None,
+            registry: None,
+            package: None,
+            workspace: false,
+            optional: false,
+            default_features: true,
+            features: Vec::new(),
+        }
+    }
+
+    /// Returns the name of the package this dependency resolves to,
+    /// taking `package = "..."` renames into account.
+    pub fn package_name(&self) -> &str {
+        self.package.as_deref().unwrap_or(&self.name)
+    }
+
+    /// Checks whether the dependency applies to the target described by `cfg`.
+    pub fn applies_to(&self, cfg: &CfgSet) -> bool {
+        self.target
+            .as_ref()
+            .is_none_or(|platform| platform.matches(cfg))
+    }
+
+    /// Renders the value of the dependency's entry, e.g. `"1.0"` for a plain
+    /// requirement or `{ version = "1.0", optional = true }` otherwise.
+    pub fn spec(&self) -> String {
+        let strings = [
+            ("version", &self.version),
+            ("git", &self.git),
+            ("branch", &self.branch),
+            ("tag", &self.tag),
+            ("rev", &self.rev),
+            ("path", &self.path),
+            ("registry", &self.registry),
+            ("package", &self.package),
+        ];
+        let mut fields: Vec<String> = strings
+            .iter()
+            .filter_map(|(key, value)| Some(format!("{} = \"{}\"", key, value.as_ref()?)))
+            .collect();
+        if self.workspace {
+            fields.push("workspace = true".to_string());
+        }
+        if self.optional {
+            fields.push("optional = true".to_string());
+        }
+        if !self.default_features {
+            fields.push("default-features = false".to_string());
+        }
+        if !self.features.is_empty() {
+            let features: Vec<String> = self
+                .features
+                .iter()
+                .map(|feature| format!("\"{}\"", feature))
+                .collect();
+            fields.push(format!("features = [{}]", features.join(", ")));
+        }
+
+        match (&self.version, fields.len()) {
+            (Some(version), 1) => format!("\"{}\"", version),
+            _ => format!("{{ {} }}", fields.join(", ")),
+        }
+    }
+
+    /// Builds a dependency from a `dependencies_key_value` pair.
+    pub(crate) fn from_pair(
+        item: pest::iterators::Pair<Rule>,
+        kind: DependencyKind,
+        target: Option<Platform>,
+    ) -> Self {
+        let mut inner = item.into_inner();
+        let name = inner.next().unwrap().as_str().trim();
+        Dependency::from_spec(name, inner.next().unwrap(), kind, target)
+    }
+
+    /// Builds a dependency from a `dependency_spec` or `version_requirement` pair.
+    pub(crate) fn from_spec(
+        name: &str,
+        spec: pest::iterators::Pair<Rule>,
+        kind: DependencyKind,
+        target: Option<Platform>,
+    ) -> Self {
+        let mut dependency = Dependency::new(name, kind);
+        dependency.target = target;
+
+        if spec.as_rule() == Rule::version_requirement {
+            dependency.version = Some(string_value(spec));
+            return dependency;
+        }
+
+        for field in spec.into_inner() {
+            match field.as_rule() {
+                Rule::dependency_version => dependency.version = Some(first_string(field)),
+                Rule::dependency_git => dependency.git = Some(first_string(field)),
+                Rule::dependency_branch => dependency.branch = Some(first_string(field)),
+                Rule::dependency_tag => dependency.tag = Some(first_string(field)),
+                Rule::dependency_rev => dependency.rev = Some(first_string(field)),
+                Rule::dependency_path => dependency.path = Some(first_string(field)),
+                Rule::dependency_registry => dependency.registry = Some(first_string(field)),
+                Rule::dependency_package => dependency.package = Some(first_string(field)),
+                Rule::dependency_workspace => dependency.workspace = true,
+                Rule::dependency_optional => dependency.optional = first_string(field) == "true",
+                Rule::dependency_default_features => {
+                    dependency.default_features = first_string(field) == "true"
+                }
+                Rule::features => {
+                    dependency.features = field
+                        .into_inner()
+                        .map(|feature| feature.as_str().to_string())
+                        .collect()
+                }
+                _ => {}
+            }
+        }
+
+        dependency
+    }
+}
+
+/// Returns the text of a quoted value without its quotes.
+fn string_value(item: pest::iterators::Pair<Rule>) -> String {
+    item.as_str().trim().trim_matches('"').to_string()
+}
+
+/// Returns the unquoted text of the first inner pair of a `key = value` field.
+fn first_string(item: pest::iterators::Pair<Rule>) -> String {
+    string_value(item.into_inner().next().unwrap())
+}
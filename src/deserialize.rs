@@ -0,0 +1,309 @@
+//! Serde deserialization of manifests and their tables into user types.
+//!
+//! The manifest is deserialized from the tree built by `Manifest::to_value`, so
+//! dotted tables are nested and version-only dependencies are expanded. Errors
+//! name the path of the value that failed and, where the manifest knows it, the
+//! location of its entry or table.
+
+use crate::query::{parse_query, quote_key, Segment, Value};
+use crate::{Manifest, ManifestError, Span};
+use indexmap::map;
+use serde::de::value::BorrowedStrDeserializer;
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+use serde::forward_to_deserialize_any;
+use std::fmt;
+
+impl Manifest {
+    /// Deserializes the whole manifest into a user type.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing either the deserialized value or a
+    /// `ManifestError::Deserialize` naming the value that did not fit
+    pub fn deserialize<T: DeserializeOwned>(&self) -> Result<T, ManifestError> {
+        let root = self.to_value();
+        T::deserialize(ValueDeserializer::new(self, &root, String::new()))
+    }
+
+    /// Deserializes the table or value at a path into a user type, e.g. a tool's
+    /// settings under `package.metadata.mytool`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Dotted path of the value, as in `Manifest::query` but without wildcards
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing either the deserialized value, a `ManifestError::MissingSection`
+    /// if nothing is at the path or a `ManifestError::Deserialize` naming the value that did not fit
+    pub fn deserialize_section<T: DeserializeOwned>(&self, path: &str) -> Result<T, ManifestError> {
+        if parse_query(path)?.contains(&Segment::Wildcard) {
+            return Err(ManifestError::InvalidQuery(
+                path.to_string(),
+                "wildcards match more than one value".to_string(),
+            ));
+        }
+        let found = self.query(path)?.into_iter().next();
+        let found = found.ok_or_else(|| ManifestError::MissingSection(path.to_string()))?;
+        T::deserialize(ValueDeserializer::new(self, &found.value, found.path))
+    }
+
+    /// Finds the location of the entry or table a value path points into.
+    ///
+    /// Array indexes are skipped, and a value inside an entry is located at the entry.
+    fn value_span(&self, path: &str) -> Option<Span> {
+        let keys: Vec<String> = parse_query(path)
+            .ok()?
+            .into_iter()
+            .filter_map(|segment| match segment {
+                Segment::Key(key) => Some(key),
+                _ => None,
+            })
+            .collect();
+        let table = |keys: &[String]| -> String {
+            let keys: Vec<String> = keys.iter().map(|key| quote_key(key)).collect();
+            keys.join(".")
+        };
+        (1..=keys.len()).rev().find_map(|end| {
+            self.key_span(&table(&keys[..end - 1]), &keys[end - 1])
+                .or_else(|| self.section_span(&table(&keys[..end])))
+        })
+    }
+}
+
+impl de::Error for ManifestError {
+    fn custom<T: fmt::Display>(message: T) -> Self {
+        ManifestError::Deserialize(String::new(), message.to_string(), None)
+    }
+}
+
+/// Deserializes a value of a manifest, remembering where it came from.
+struct ValueDeserializer<'de> {
+    /// The manifest the value belongs to, used to locate errors
+    manifest: &'de Manifest,
+    /// The value to deserialize
+    value: &'de Value,
+    /// Query path of the value, empty for the root
+    path: String,
+}
+
+impl<'de> ValueDeserializer<'de> {
+    fn new(manifest: &'de Manifest, value: &'de Value, path: String) -> Self {
+        ValueDeserializer {
+            manifest,
+            value,
+            path,
+        }
+    }
+
+    /// Creates the deserializer of a value inside this one.
+    fn child(&self, value: &'de Value, path: String) -> Self {
+        ValueDeserializer::new(self.manifest, value, path)
+    }
+
+    /// Names this value in an error that does not name a value yet.
+    fn locate(&self, error: ManifestError) -> ManifestError {
+        match error {
+            ManifestError::Deserialize(path, message, None) if path.is_empty() => {
+                let name = match self.path.is_empty() {
+                    true => "manifest".to_string(),
+                    false => self.path.clone(),
+                };
+                let span = self.manifest.value_span(&self.path);
+                ManifestError::Deserialize(name, message, span)
+            }
+            error => error,
+        }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = ManifestError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ManifestError> {
+        let result = match self.value {
+            Value::String(string) => visitor.visit_borrowed_str(string),
+            Value::Integer(integer) => visitor.visit_i64(*integer),
+            Value::Float(float) => visitor.visit_f64(*float),
+            Value::Boolean(boolean) => visitor.visit_bool(*boolean),
+            Value::Array(values) => visitor.visit_seq(Elements {
+                parent: &self,
+                values: values.iter().enumerate(),
+            }),
+            Value::Table(table) => visitor.visit_map(Entries {
+                parent: &self,
+                entries: table.iter(),
+                value: None,
+            }),
+        };
+        result.map_err(|e| self.locate(e))
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ManifestError> {
+        // A value that is in the manifest is always present
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ManifestError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ManifestError> {
+        let result = match self.value {
+            Value::String(variant) => visitor.visit_enum(variant.as_str().into_deserializer()),
+            Value::Table(table) if table.len() == 1 => {
+                let (variant, value) = table.iter().next().unwrap();
+                let path = join(&self.path, variant);
+                visitor.visit_enum(Variant {
+                    variant,
+                    value: self.child(value, path),
+                })
+            }
+            _ => Err(de::Error::custom(format!(
+                "expected a variant name or a table with one key, found {}",
+                self.value
+            ))),
+        };
+        result.map_err(|e| self.locate(e))
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+/// Appends a key to a query path.
+fn join(path: &str, key: &str) -> String {
+    match path.is_empty() {
+        true => quote_key(key),
+        false => format!("{}.{}", path, quote_key(key)),
+    }
+}
+
+/// The elements of an array.
+struct Elements<'a, 'de> {
+    parent: &'a ValueDeserializer<'de>,
+    values: std::iter::Enumerate<std::slice::Iter<'de, Value>>,
+}
+
+impl<'de> SeqAccess<'de> for Elements<'_, 'de> {
+    type Error = ManifestError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, ManifestError> {
+        let Some((index, value)) = self.values.next() else {
+            return Ok(None);
+        };
+        let path = format!("{}[{}]", self.parent.path, index);
+        seed.deserialize(self.parent.child(value, path)).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.values.len())
+    }
+}
+
+/// The entries of a table.
+struct Entries<'a, 'de> {
+    parent: &'a ValueDeserializer<'de>,
+    entries: map::Iter<'de, String, Value>,
+    /// The entry whose key was returned last
+    value: Option<(&'de String, &'de Value)>,
+}
+
+impl<'de> MapAccess<'de> for Entries<'_, 'de> {
+    type Error = ManifestError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, ManifestError> {
+        let Some((key, value)) = self.entries.next() else {
+            return Ok(None);
+        };
+        self.value = Some((key, value));
+        seed.deserialize(BorrowedStrDeserializer::new(key))
+            .map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, ManifestError> {
+        let (key, value) = self
+            .value
+            .take()
+            .expect("next_value_seed is called after next_key_seed");
+        seed.deserialize(self.parent.child(value, join(&self.parent.path, key)))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+/// An enum variant written as a table with a single key.
+struct Variant<'de> {
+    variant: &'de str,
+    value: ValueDeserializer<'de>,
+}
+
+impl<'de> EnumAccess<'de> for Variant<'de> {
+    type Error = ManifestError;
+    type Variant = ValueDeserializer<'de>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), ManifestError> {
+        let variant = seed.deserialize(BorrowedStrDeserializer::new(self.variant))?;
+        Ok((variant, self.value))
+    }
+}
+
+impl<'de> VariantAccess<'de> for ValueDeserializer<'de> {
+    type Error = ManifestError;
+
+    fn unit_variant(self) -> Result<(), ManifestError> {
+        de::Deserialize::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, ManifestError> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, ManifestError> {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ManifestError> {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+}
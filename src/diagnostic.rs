@@ -0,0 +1,185 @@
+//! Diagnostics reported about a manifest and the fixes attached to them.
+
+use crate::{LintLevel, ManifestError, Span};
+use std::fmt;
+
+/// A text replacement in the manifest source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    /// Byte offset of the first replaced character
+    pub start: usize,
+    /// Byte offset after the last replaced character
+    pub end: usize,
+    /// The text to insert in place of the range
+    pub replacement: String,
+}
+
+/// An automatic fix for a diagnostic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fix {
+    /// What the fix does, e.g. "rename to default-features"
+    pub description: String,
+    /// The edits to apply, which must not overlap
+    pub edits: Vec<Edit>,
+}
+
+/// A problem found in a manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Identifier of the check that produced the diagnostic, e.g. `wildcard-requirement`
+    pub rule: String,
+    /// How serious the problem is
+    pub level: LintLevel,
+    /// Human readable description of the problem
+    pub message: String,
+    /// Where the problem is, if it can be located
+    pub span: Option<Span>,
+    /// An automatic fix, if one is available
+    pub fix: Option<Fix>,
+}
+
+impl Diagnostic {
+    /// Creates a diagnostic without a location or a fix.
+    pub fn new(rule: impl Into<String>, level: LintLevel, message: impl Into<String>) -> Self {
+        Diagnostic {
+            rule: rule.into(),
+            level,
+            message: message.into(),
+            span: None,
+            fix: None,
+        }
+    }
+
+    /// Attaches a location to the diagnostic.
+    pub fn with_span(mut self, span: Option<Span>) -> Self {
+        self.span = span;
+        self
+    }
+
+    /// Attaches an automatic fix to the diagnostic.
+    pub fn with_fix(mut self, description: impl Into<String>, edits: Vec<Edit>) -> Self {
+        self.fix = Some(Fix {
+            description: description.into(),
+            edits,
+        });
+        self
+    }
+
+    /// Reports an error returned while parsing or validating a manifest.
+    ///
+    /// Syntax errors are located using the position pest reports for them.
+    ///
+    /// # Arguments
+    ///
+    /// * `error` - The error to report
+    /// * `source` - The source that failed to parse
+    ///
+    /// # Returns
+    ///
+    /// A denied diagnostic whose rule names the kind of error, e.g. `parse-error`
+    pub fn from_error(error: &ManifestError, source: &str) -> Self {
+        let rule = match error {
+            ManifestError::ParseError(_) => "parse-error",
+            ManifestError::MissingSection(_) => "missing-section",
+            ManifestError::MissingKey(_, _) => "missing-key",
+            ManifestError::InvalidValue(_, _, _) => "invalid-value",
+            ManifestError::MissingMember(_) => "missing-member",
+            ManifestError::DependencyCycle(_) => "dependency-cycle",
+            ManifestError::UnknownRule(_) => "unknown-rule",
+            ManifestError::UnknownPackage(_) => "unknown-package",
+            ManifestError::InvalidQuery(_, _) => "invalid-query",
+            ManifestError::Protocol(_) => "protocol-error",
+            ManifestError::Deserialize(_, _, _) => "deserialize-error",
+            ManifestError::Cache(_) => "cache-error",
+            ManifestError::InvalidArgument(_) => "invalid-argument",
+            ManifestError::UnknownFormat(_) => "unknown-format",
+        };
+        let message = error.to_string();
+        let span = match error {
+            ManifestError::ParseError(message) => pest_position(message)
+                .and_then(|(line, column)| position_span(source, line, column)),
+            ManifestError::Deserialize(_, _, span) => *span,
+            _ => None,
+        };
+
+        Diagnostic::new(rule, LintLevel::Deny, message).with_span(span)
+    }
+
+    /// Checks whether the diagnostic should fail a check, i.e. it is denied or forbidden.
+    pub fn is_error(&self) -> bool {
+        self.level >= LintLevel::Deny
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = if self.is_error() { "error" } else { "warning" };
+        write!(f, "{}[{}]: {}", severity, self.rule, self.message)?;
+        if let Some(span) = self.span {
+            write!(f, " (line {}, column {})", span.line, span.column)?;
+        }
+        Ok(())
+    }
+}
+
+/// Finds the `--> line:column` position in a rendered pest error.
+fn pest_position(message: &str) -> Option<(usize, usize)> {
+    let position = message
+        .lines()
+        .find_map(|line| line.trim_start().strip_prefix("--> "))?;
+    let (line, column) = position.trim().split_once(':')?;
+    Some((line.parse().ok()?, column.parse().ok()?))
+}
+
+/// Builds an empty span at a 1-based line and column of the source.
+fn position_span(source: &str, line: usize, column: usize) -> Option<Span> {
+    let line_start = if line == 1 {
+        0
+    } else {
+        source.match_indices('\n').nth(line - 2)?.0 + 1
+    };
+    let offset = source[line_start..]
+        .char_indices()
+        .nth(column - 1)
+        .map_or(source.len(), |(offset, _)| line_start + offset);
+
+    Some(Span {
+        start: offset,
+        end: offset,
+        line,
+        column,
+    })
+}
+
+/// Applies the fixes of a set of diagnostics to the manifest source.
+///
+/// Fixes whose edits overlap an edit that was already applied are skipped.
+///
+/// # Arguments
+///
+/// * `source` - The manifest source the diagnostics were produced for
+/// * `diagnostics` - The diagnostics whose fixes to apply
+///
+/// # Returns
+///
+/// The fixed source
+pub fn apply_fixes(source: &str, diagnostics: &[Diagnostic]) -> String {
+    let mut edits: Vec<&Edit> = Vec::new();
+    for fix in diagnostics.iter().filter_map(|d| d.fix.as_ref()) {
+        let overlaps = fix.edits.iter().any(|edit| {
+            edits
+                .iter()
+                .any(|other| edit.start < other.end && other.start < edit.end)
+        });
+        if !overlaps {
+            edits.extend(&fix.edits);
+        }
+    }
+    edits.sort_by_key(|edit| std::cmp::Reverse(edit.start));
+
+    let mut fixed = source.to_string();
+    for edit in edits {
+        fixed.replace_range(edit.start..edit.end, &edit.replacement);
+    }
+    fixed
+}
@@ -0,0 +1,614 @@
+//! Semantic differences between two manifests.
+
+use crate::{Dependency, DependencyKind, Lint, Manifest, Value};
+use indexmap::IndexMap;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// A single difference between two manifests.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "change", rename_all = "kebab-case")]
+pub enum Change {
+    /// A key was added to a section
+    KeyAdded {
+        section: String,
+        key: String,
+        value: String,
+    },
+    /// A key was removed from a section
+    KeyRemoved {
+        section: String,
+        key: String,
+        value: String,
+    },
+    /// The value of a key changed
+    KeyChanged {
+        section: String,
+        key: String,
+        old: String,
+        new: String,
+    },
+    /// A dependency was added
+    DependencyAdded {
+        table: String,
+        name: String,
+        requirement: Option<String>,
+    },
+    /// A dependency was removed
+    DependencyRemoved {
+        table: String,
+        name: String,
+        requirement: Option<String>,
+    },
+    /// The version requirement of a dependency changed
+    RequirementChanged {
+        table: String,
+        name: String,
+        old: Option<String>,
+        new: Option<String>,
+    },
+    /// A dependency now comes from another source, e.g. a git repository instead of crates.io
+    SourceChanged {
+        table: String,
+        name: String,
+        old: String,
+        new: String,
+    },
+    /// Whether a dependency is optional, uses default features or renames its package changed
+    DependencyFieldChanged {
+        table: String,
+        name: String,
+        field: String,
+        old: String,
+        new: String,
+    },
+    /// Features were enabled or disabled on a dependency
+    DependencyFeaturesChanged {
+        table: String,
+        name: String,
+        added: Vec<String>,
+        removed: Vec<String>,
+    },
+    /// A dependency moved to the table of another target
+    TargetChanged {
+        name: String,
+        old: String,
+        new: String,
+    },
+    /// A feature was added to `[features]`
+    FeatureAdded { name: String, enables: Vec<String> },
+    /// A feature was removed from `[features]`
+    FeatureRemoved { name: String, enables: Vec<String> },
+    /// The features or dependencies a feature enables changed
+    FeatureChanged {
+        name: String,
+        added: Vec<String>,
+        removed: Vec<String>,
+    },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::KeyAdded {
+                section,
+                key,
+                value,
+            } => write!(f, "[{}] added {} = {}", section, key, value),
+            Change::KeyRemoved {
+                section,
+                key,
+                value,
+            } => write!(f, "[{}] removed {} = {}", section, key, value),
+            Change::KeyChanged {
+                section,
+                key,
+                old,
+                new,
+            } => write!(f, "[{}] {}: {} -> {}", section, key, old, new),
+            Change::DependencyAdded {
+                table,
+                name,
+                requirement,
+            } => write!(
+                f,
+                "[{}] added {} {}",
+                table,
+                name,
+                requirement_or_any(requirement)
+            ),
+            Change::DependencyRemoved {
+                table,
+                name,
+                requirement,
+            } => write!(
+                f,
+                "[{}] removed {} {}",
+                table,
+                name,
+                requirement_or_any(requirement)
+            ),
+            Change::RequirementChanged {
+                table,
+                name,
+                old,
+                new,
+            } => write!(
+                f,
+                "[{}] {}: {} -> {}",
+                table,
+                name,
+                requirement_or_any(old),
+                requirement_or_any(new)
+            ),
+            Change::SourceChanged {
+                table,
+                name,
+                old,
+                new,
+            } => write!(f, "[{}] {} source: {} -> {}", table, name, old, new),
+            Change::DependencyFieldChanged {
+                table,
+                name,
+                field,
+                old,
+                new,
+            } => write!(f, "[{}] {} {}: {} -> {}", table, name, field, old, new),
+            Change::DependencyFeaturesChanged {
+                table,
+                name,
+                added,
+                removed,
+            } => write!(
+                f,
+                "[{}] {} features: {}",
+                table,
+                name,
+                changes(added, removed)
+            ),
+            Change::TargetChanged { name, old, new } => {
+                write!(f, "{} moved from [{}] to [{}]", name, old, new)
+            }
+            Change::FeatureAdded { name, enables } => {
+                write!(f, "[features] added {} = [{}]", name, enables.join(", "))
+            }
+            Change::FeatureRemoved { name, enables } => {
+                write!(f, "[features] removed {} = [{}]", name, enables.join(", "))
+            }
+            Change::FeatureChanged {
+                name,
+                added,
+                removed,
+            } => write!(f, "[features] {}: {}", name, changes(added, removed)),
+        }
+    }
+}
+
+impl Manifest {
+    /// Compares the manifest with a newer version of it.
+    ///
+    /// Formatting and the order of sections, keys and dependencies are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The newer manifest
+    ///
+    /// # Returns
+    ///
+    /// The changes from `self` to `other`: plain keys first, then dependencies,
+    /// then `[patch]` and `[replace]` entries, then features
+    pub fn diff(&self, other: &Manifest) -> Vec<Change> {
+        let mut changes = diff_keys(self, other);
+        changes.extend(diff_dependencies(self, other));
+        changes.extend(diff_overrides(self, other));
+        changes.extend(diff_features(self, other));
+        changes
+    }
+}
+
+/// Compares the plain keys of every section except dependency, `[patch]` and
+/// `[replace]` tables and `[features]`.
+///
+/// The tables of an array of tables such as `[[bin]]` are compared one by one
+/// and named by their index, e.g. `bin[0]`.
+fn diff_keys(old: &Manifest, new: &Manifest) -> Vec<Change> {
+    let sections: BTreeSet<&String> = old.sections().chain(new.sections()).collect();
+    let mut changes = Vec::new();
+
+    for section in sections {
+        if section == "features"
+            || section == "replace"
+            || section.starts_with("patch.")
+            || is_dependency_table(section)
+        {
+            continue;
+        }
+
+        let (old_tables, new_tables) =
+            (old.array_tables.get(section), new.array_tables.get(section));
+        if old_tables.is_some() || new_tables.is_some() {
+            let (old_tables, new_tables) = (
+                old_tables.map_or(&[][..], Vec::as_slice),
+                new_tables.map_or(&[][..], Vec::as_slice),
+            );
+            for index in 0..old_tables.len().max(new_tables.len()) {
+                let (old_keys, new_keys) =
+                    (by_key(old_tables.get(index)), by_key(new_tables.get(index)));
+                let same = |key: &str| {
+                    old_keys.get(key).map(|raw| Value::parse(raw))
+                        == new_keys.get(key).map(|raw| Value::parse(raw))
+                };
+                let section = format!("{}[{}]", section, index);
+                diff_entries(&section, &old_keys, &new_keys, same, &mut changes);
+            }
+            continue;
+        }
+
+        let (old_keys, new_keys) = (
+            by_key(old.raw_values.get(section)),
+            by_key(new.raw_values.get(section)),
+        );
+        let same = |key: &str| same_value(old, new, section, key);
+        diff_entries(section, &old_keys, &new_keys, same, &mut changes);
+    }
+    changes
+}
+
+/// Compares the keys of one table, given as written, pushing a change for every
+/// key that was removed, changed or added.
+fn diff_entries(
+    section: &str,
+    old_keys: &BTreeMap<&str, &str>,
+    new_keys: &BTreeMap<&str, &str>,
+    same: impl Fn(&str) -> bool,
+    changes: &mut Vec<Change>,
+) {
+    let unquote = |raw: &str| raw.trim_matches('"').to_string();
+    for (key, old_value) in old_keys {
+        match new_keys.get(key) {
+            None => changes.push(Change::KeyRemoved {
+                section: section.to_string(),
+                key: key.to_string(),
+                value: unquote(old_value),
+            }),
+            Some(new_value) if !same(key) => changes.push(Change::KeyChanged {
+                section: section.to_string(),
+                key: key.to_string(),
+                old: unquote(old_value),
+                new: unquote(new_value),
+            }),
+            Some(_) => {}
+        }
+    }
+    for (key, value) in new_keys {
+        if !old_keys.contains_key(key) {
+            changes.push(Change::KeyAdded {
+                section: section.to_string(),
+                key: key.to_string(),
+                value: unquote(value),
+            });
+        }
+    }
+}
+
+/// Checks whether a key present in both manifests has the same value, comparing
+/// lints by their typed settings and other values by their parsed form.
+fn same_value(old: &Manifest, new: &Manifest, section: &str, key: &str) -> bool {
+    if let (Some(old_lint), Some(new_lint)) = (lint(old, section, key), lint(new, section, key)) {
+        return old_lint == new_lint;
+    }
+    let value = |manifest: &Manifest| {
+        manifest
+            .raw_values
+            .get(section)
+            .and_then(|entries| entries.get(key))
+            .map(|raw| Value::parse(raw))
+    };
+    value(old) == value(new)
+}
+
+/// Finds the lint declared by an entry of a `[lints.<tool>]` or `[workspace.lints.<tool>]` table.
+fn lint<'a>(manifest: &'a Manifest, section: &str, name: &str) -> Option<&'a Lint> {
+    let (lints, tool) = match section.strip_prefix("workspace.lints.") {
+        Some(tool) => (manifest.workspace_lints.as_slice(), tool),
+        None => (manifest.lints.as_slice(), section.strip_prefix("lints.")?),
+    };
+    lints
+        .iter()
+        .find(|lint| lint.tool == tool && lint.name == name)
+}
+
+/// Returns the entries of a section ordered by key, or none if the section is missing.
+fn entries<'a>(manifest: &'a Manifest, section: &str) -> BTreeMap<&'a str, &'a str> {
+    by_key(manifest.get_by_section(section).ok())
+}
+
+/// Collects the entries of a table in key order.
+fn by_key(entries: Option<&IndexMap<String, String>>) -> BTreeMap<&str, &str> {
+    entries
+        .map(|entries| {
+            entries
+                .iter()
+                .map(|(key, value)| (key.as_str(), value.as_str()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Checks whether a section is a dependency table, including target-specific ones.
+fn is_dependency_table(section: &str) -> bool {
+    DependencyKind::from_table(section.rsplit('.').next().unwrap()).is_some()
+}
+
+/// Identifies a dependency independently of how its table header is written.
+type DependencyKey = (&'static str, Option<String>, String);
+
+/// Compares the dependencies of every table.
+fn diff_dependencies(old: &Manifest, new: &Manifest) -> Vec<Change> {
+    let key = |dependency: &Dependency| -> DependencyKey {
+        (
+            dependency.kind.table(),
+            dependency.target.as_ref().map(|target| target.to_string()),
+            dependency.name.clone(),
+        )
+    };
+    let old_dependencies: BTreeMap<DependencyKey, &Dependency> =
+        old.dependencies().map(|d| (key(d), d)).collect();
+    let new_dependencies: BTreeMap<DependencyKey, &Dependency> =
+        new.dependencies().map(|d| (key(d), d)).collect();
+
+    let mut removed: Vec<&Dependency> = old_dependencies
+        .iter()
+        .filter(|(key, _)| !new_dependencies.contains_key(key))
+        .map(|(_, dependency)| *dependency)
+        .collect();
+    let mut added: Vec<&Dependency> = new_dependencies
+        .iter()
+        .filter(|(key, _)| !old_dependencies.contains_key(key))
+        .map(|(_, dependency)| *dependency)
+        .collect();
+
+    let mut changes = Vec::new();
+    for (key, old_dependency) in &old_dependencies {
+        if let Some(new_dependency) = new_dependencies.get(key) {
+            changes.extend(diff_dependency(old_dependency, new_dependency));
+        }
+    }
+
+    // A dependency removed from one target table and added to another of the same kind moved
+    removed.retain(|old_dependency| {
+        let moved = added.iter().position(|new_dependency| {
+            new_dependency.name == old_dependency.name && new_dependency.kind == old_dependency.kind
+        });
+        match moved {
+            Some(index) => {
+                let new_dependency = added.remove(index);
+                changes.push(Change::TargetChanged {
+                    name: old_dependency.name.clone(),
+                    old: old_dependency.table.clone(),
+                    new: new_dependency.table.clone(),
+                });
+                changes.extend(diff_dependency(old_dependency, new_dependency));
+                false
+            }
+            None => true,
+        }
+    });
+
+    changes.extend(
+        removed
+            .into_iter()
+            .map(|dependency| Change::DependencyRemoved {
+                table: dependency.table.clone(),
+                name: dependency.name.clone(),
+                requirement: dependency.version.clone(),
+            }),
+    );
+    changes.extend(added.into_iter().map(|dependency| Change::DependencyAdded {
+        table: dependency.table.clone(),
+        name: dependency.name.clone(),
+        requirement: dependency.version.clone(),
+    }));
+    changes
+}
+
+/// Identifies a `[patch]` entry by its source and crate, or a `[replace]` entry by its package id.
+type OverrideKey = (Option<String>, String);
+
+/// Compares the entries of the `[patch.<source>]` and `[replace]` tables.
+fn diff_overrides(old: &Manifest, new: &Manifest) -> Vec<Change> {
+    let overrides = |manifest: &Manifest| -> BTreeMap<OverrideKey, Dependency> {
+        let patches = manifest.patches().map(|patch| {
+            let key = (Some(patch.source.clone()), patch.replacement.name.clone());
+            (key, patch.replacement.clone())
+        });
+        let replacements = manifest.replacements().map(|entry| {
+            let id = format!("{}:{}", entry.package, entry.version);
+            let replacement = Dependency {
+                name: id.clone(),
+                ..entry.replacement.clone()
+            };
+            ((None, id), replacement)
+        });
+        patches.chain(replacements).collect()
+    };
+    let old_overrides = overrides(old);
+    let new_overrides = overrides(new);
+
+    let mut changes = Vec::new();
+    for (key, old_override) in &old_overrides {
+        match new_overrides.get(key) {
+            Some(new_override) => changes.extend(diff_dependency(old_override, new_override)),
+            None => changes.push(Change::DependencyRemoved {
+                table: old_override.table.clone(),
+                name: old_override.name.clone(),
+                requirement: old_override.version.clone(),
+            }),
+        }
+    }
+    for (key, new_override) in &new_overrides {
+        if !old_overrides.contains_key(key) {
+            changes.push(Change::DependencyAdded {
+                table: new_override.table.clone(),
+                name: new_override.name.clone(),
+                requirement: new_override.version.clone(),
+            });
+        }
+    }
+    changes
+}
+
+/// Compares two declarations of the same dependency.
+fn diff_dependency(old: &Dependency, new: &Dependency) -> Vec<Change> {
+    let mut changes = Vec::new();
+    let table = new.table.clone();
+    let name = new.name.clone();
+
+    if old.version != new.version {
+        changes.push(Change::RequirementChanged {
+            table: table.clone(),
+            name: name.clone(),
+            old: old.version.clone(),
+            new: new.version.clone(),
+        });
+    }
+    let (old_source, new_source) = (source(old), source(new));
+    if old_source != new_source {
+        changes.push(Change::SourceChanged {
+            table: table.clone(),
+            name: name.clone(),
+            old: old_source,
+            new: new_source,
+        });
+    }
+    let fields = [
+        (
+            "optional",
+            old.optional.to_string(),
+            new.optional.to_string(),
+        ),
+        (
+            "default-features",
+            old.default_features.to_string(),
+            new.default_features.to_string(),
+        ),
+        (
+            "package",
+            old.package_name().to_string(),
+            new.package_name().to_string(),
+        ),
+    ];
+    for (field, old_value, new_value) in fields {
+        if old_value != new_value {
+            changes.push(Change::DependencyFieldChanged {
+                table: table.clone(),
+                name: name.clone(),
+                field: field.to_string(),
+                old: old_value,
+                new: new_value,
+            });
+        }
+    }
+    let (added, removed) = set_difference(&old.features, &new.features);
+    if !added.is_empty() || !removed.is_empty() {
+        changes.push(Change::DependencyFeaturesChanged {
+            table,
+            name,
+            added,
+            removed,
+        });
+    }
+    changes
+}
+
+/// Describes where a dependency comes from, e.g. `git https://... (tag v1)`.
+fn source(dependency: &Dependency) -> String {
+    if dependency.workspace {
+        return "workspace".to_string();
+    }
+    if let Some(path) = &dependency.path {
+        return format!("path {}", path);
+    }
+    if let Some(git) = &dependency.git {
+        let reference = [
+            ("branch", &dependency.branch),
+            ("tag", &dependency.tag),
+            ("rev", &dependency.rev),
+        ]
+        .into_iter()
+        .find_map(|(name, value)| value.as_ref().map(|value| format!(" ({} {})", name, value)));
+        return format!("git {}{}", git, reference.unwrap_or_default());
+    }
+    match &dependency.registry {
+        Some(registry) => format!("registry {}", registry),
+        None => "crates.io".to_string(),
+    }
+}
+
+/// Compares the `[features]` table.
+fn diff_features(old: &Manifest, new: &Manifest) -> Vec<Change> {
+    let old_features = entries(old, "features");
+    let new_features = entries(new, "features");
+    let mut changes = Vec::new();
+
+    for (name, enables) in &old_features {
+        match new_features.get(name) {
+            None => changes.push(Change::FeatureRemoved {
+                name: name.to_string(),
+                enables: parse_list(enables),
+            }),
+            Some(new_enables) => {
+                let (added, removed) =
+                    set_difference(&parse_list(enables), &parse_list(new_enables));
+                if !added.is_empty() || !removed.is_empty() {
+                    changes.push(Change::FeatureChanged {
+                        name: name.to_string(),
+                        added,
+                        removed,
+                    });
+                }
+            }
+        }
+    }
+    for (name, enables) in new_features {
+        if !old_features.contains_key(name) {
+            changes.push(Change::FeatureAdded {
+                name: name.to_string(),
+                enables: parse_list(enables),
+            });
+        }
+    }
+    changes
+}
+
+/// Splits an array value such as `["std", "serde/derive"]` into its elements.
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .split(',')
+        .map(|element| element.trim().trim_matches('"').to_string())
+        .filter(|element| !element.is_empty())
+        .collect()
+}
+
+/// Returns the elements only in `new` and the elements only in `old`.
+fn set_difference(old: &[String], new: &[String]) -> (Vec<String>, Vec<String>) {
+    let added = new.iter().filter(|e| !old.contains(e)).cloned().collect();
+    let removed = old.iter().filter(|e| !new.contains(e)).cloned().collect();
+    (added, removed)
+}
+
+/// Renders a requirement, using `*` for dependencies without one.
+fn requirement_or_any(requirement: &Option<String>) -> &str {
+    requirement.as_deref().unwrap_or("*")
+}
+
+/// Renders added and removed elements as `+added -removed`.
+fn changes(added: &[String], removed: &[String]) -> String {
+    let added = added.iter().map(|e| format!("+{}", e));
+    let removed = removed.iter().map(|e| format!("-{}", e));
+    added.chain(removed).collect::<Vec<_>>().join(" ")
+}
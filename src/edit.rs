@@ -0,0 +1,410 @@
+//! In-place edits of manifests that keep comments and formatting.
+//!
+//! Every edit touches only the lines of the entry or table it changes, so the
+//! rest of the manifest stays byte for byte the same. Paths use the syntax of
+//! `Manifest::query` without wildcards, e.g. `package.version` or
+//! `target.'cfg(unix)'.dependencies.openssl`.
+
+use crate::cfg::unquote_key;
+use crate::formatter::{Document, Item, Table};
+use crate::query::{parse_query, quote_key, split_table_name, Segment};
+use crate::{Dependency, Manifest, ManifestError, Value};
+
+/// Lines of unchanged context around the change in a unified diff.
+const DIFF_CONTEXT: usize = 3;
+
+/// Sets the value at a path, adding the entry or table if it does not exist.
+///
+/// # Arguments
+///
+/// * `source` - The manifest content as a string
+/// * `path` - The path of the entry, e.g. `package.version` or `bin[1].path`
+/// * `value` - The new value
+///
+/// # Returns
+///
+/// A `Result` containing either the edited manifest or a `ManifestError` if the
+/// path is not an entry or the edited manifest does not parse
+pub fn set_value(source: &str, path: &str, value: &Value) -> Result<String, ManifestError> {
+    let segments = parse_path(path)?;
+    let Some((Segment::Key(key), table_segments)) = segments.split_last() else {
+        return Err(invalid_path(path, "expected a key at the end"));
+    };
+    let (keys, index) = table_path(path, table_segments)?;
+    let document = Document::parse(source)?;
+
+    let edited = match find_table(&document, &keys, index) {
+        Some(table) => upsert(source, table, key, &value.to_string(), false),
+        None if index > 0 => return Err(ManifestError::MissingSection(table_name(&keys))),
+        None => {
+            if let Some((name, parent)) = keys.split_last() {
+                let inline = find_table(&document, parent, 0)
+                    .is_some_and(|table| find_entry(table, name).is_some());
+                if inline {
+                    return Err(ManifestError::InvalidValue(
+                        table_name(parent),
+                        name.clone(),
+                        "is an inline table, set the whole entry instead".to_string(),
+                    ));
+                }
+            }
+            append_table(source, &keys, &format!("{} = {}", quote_key(key), value))
+        }
+    };
+    validate(edited)
+}
+
+/// Removes the entry or the whole table at a path.
+///
+/// # Arguments
+///
+/// * `source` - The manifest content as a string
+/// * `path` - The path of the entry or table, e.g. `package.readme` or `profile.release`
+///
+/// # Returns
+///
+/// A `Result` containing either the edited manifest or a `ManifestError` if
+/// nothing exists at the path
+pub fn unset_value(source: &str, path: &str) -> Result<String, ManifestError> {
+    let segments = parse_path(path)?;
+    let document = Document::parse(source)?;
+
+    if let Some((Segment::Key(key), table_segments)) = segments.split_last() {
+        let (keys, index) = table_path(path, table_segments)?;
+        if let Some(entry) =
+            find_table(&document, &keys, index).and_then(|table| find_entry(table, key))
+        {
+            return validate(remove_lines(source, entry.start, entry.end));
+        }
+    }
+
+    let (keys, index) = table_path(path, &segments)?;
+    match find_table(&document, &keys, index) {
+        Some(table) => {
+            let end = table.entries.last().unwrap_or(&table.header).end;
+            validate(remove_lines(source, table.header.start, end))
+        }
+        None => match keys.split_last() {
+            Some((key, parent)) if find_table(&document, parent, 0).is_some() => {
+                Err(ManifestError::MissingKey(table_name(parent), key.clone()))
+            }
+            _ => Err(ManifestError::MissingSection(table_name(&keys))),
+        },
+    }
+}
+
+/// Adds a dependency to its table, or merges it into the entry if it is already there.
+///
+/// New entries go in key order when the table is sorted and at the end otherwise,
+/// and the table is added at the end of the manifest if it does not exist. An
+/// existing entry keeps its other fields: the version is replaced, the features
+/// are added to those already enabled and `optional` is only ever turned on.
+///
+/// # Arguments
+///
+/// * `source` - The manifest content as a string
+/// * `dependency` - The dependency, declared in the table named by its `table` field
+///
+/// # Returns
+///
+/// A `Result` containing either the edited manifest or a `ManifestError` if the
+/// edited manifest does not parse
+pub fn add_dependency(source: &str, dependency: &Dependency) -> Result<String, ManifestError> {
+    let keys = split_table_name(&dependency.table);
+    let document = Document::parse(source)?;
+    let line = format!("{} = {}", quote_key(&dependency.name), dependency.spec());
+
+    let edited = match find_table(&document, &keys, 0) {
+        Some(table) => {
+            let spec = match find_entry(table, &dependency.name) {
+                Some(_) => merge_dependency(source, dependency)?.spec(),
+                None => dependency.spec(),
+            };
+            upsert(source, table, &dependency.name, &spec, true)
+        }
+        None => append_table(source, &keys, &line),
+    };
+    validate(edited)
+}
+
+/// Merges a dependency into the one declared under the same name in the same table.
+fn merge_dependency(source: &str, dependency: &Dependency) -> Result<Dependency, ManifestError> {
+    let manifest = Manifest::parse(source)?;
+    let Some(existing) = manifest.dependencies().find(|existing| {
+        existing.table == dependency.table && unquote_key(&existing.name) == dependency.name
+    }) else {
+        return Ok(dependency.clone());
+    };
+
+    let mut merged = existing.clone();
+    if dependency.version.is_some() {
+        merged.version = dependency.version.clone();
+    }
+    for feature in &dependency.features {
+        if !merged.features.contains(feature) {
+            merged.features.push(feature.clone());
+        }
+    }
+    merged.optional |= dependency.optional;
+    Ok(merged)
+}
+
+/// Removes a dependency from a dependency table.
+///
+/// # Arguments
+///
+/// * `source` - The manifest content as a string
+/// * `name` - The key the dependency is declared under
+/// * `table` - The name of the table, e.g. `dev-dependencies`
+///
+/// # Returns
+///
+/// A `Result` containing either the edited manifest or a `ManifestError` if the
+/// table has no such dependency
+pub fn remove_dependency(source: &str, name: &str, table: &str) -> Result<String, ManifestError> {
+    let document = Document::parse(source)?;
+    let entry = find_table(&document, &split_table_name(table), 0)
+        .ok_or_else(|| ManifestError::MissingSection(table.to_string()))?
+        .entries
+        .iter()
+        .find(|entry| unquote_key(&entry.key) == name)
+        .ok_or_else(|| ManifestError::MissingKey(table.to_string(), name.to_string()))?;
+    validate(remove_lines(source, entry.start, entry.end))
+}
+
+/// Renders the change between two versions of a file as a unified diff.
+///
+/// Edits change a single region of the manifest, so the diff has at most one hunk.
+///
+/// # Arguments
+///
+/// * `path` - The file name shown in the diff header
+/// * `old` - The content before the change
+/// * `new` - The content after the change
+///
+/// # Returns
+///
+/// The diff, or an empty string if the contents are equal
+pub fn unified_diff(path: &str, old: &str, new: &str) -> String {
+    if old == new {
+        return String::new();
+    }
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let start = prefix.saturating_sub(DIFF_CONTEXT);
+    let (old_end, new_end) = (old.len() - suffix, new.len() - suffix);
+    let after = suffix.min(DIFF_CONTEXT);
+
+    let range = |count: usize| {
+        let first = if count == 0 { start } else { start + 1 };
+        format!("{},{}", first, count)
+    };
+    let mut diff = format!(
+        "--- {}\n+++ {}\n@@ -{} +{} @@\n",
+        path,
+        path,
+        range(old_end + after - start),
+        range(new_end + after - start)
+    );
+    for line in &old[start..prefix] {
+        diff.push_str(&format!(" {}\n", line));
+    }
+    for line in &old[prefix..old_end] {
+        diff.push_str(&format!("-{}\n", line));
+    }
+    for line in &new[prefix..new_end] {
+        diff.push_str(&format!("+{}\n", line));
+    }
+    for line in &old[old_end..old_end + after] {
+        diff.push_str(&format!(" {}\n", line));
+    }
+    diff
+}
+
+/// Parses an edit path, which may not contain wildcards.
+fn parse_path(path: &str) -> Result<Vec<Segment>, ManifestError> {
+    let segments = parse_query(path)?;
+    if segments.contains(&Segment::Wildcard) {
+        return Err(invalid_path(path, "wildcards cannot be edited"));
+    }
+    Ok(segments)
+}
+
+/// Splits the segments naming a table into its keys and the index among tables
+/// of the same name, e.g. `bin[1]` into `["bin"]` and `1`.
+fn table_path(path: &str, segments: &[Segment]) -> Result<(Vec<String>, usize), ManifestError> {
+    let (index, segments) = match segments.split_last() {
+        Some((Segment::Index(index), rest)) => (*index, rest),
+        _ => (0, segments),
+    };
+    let keys = segments
+        .iter()
+        .map(|segment| match segment {
+            Segment::Key(key) => Ok(key.clone()),
+            _ => Err(invalid_path(
+                path,
+                "indexes select a table, e.g. `bin[1].path`",
+            )),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((keys, index))
+}
+
+/// Builds the error for a path that cannot be edited.
+fn invalid_path(path: &str, reason: &str) -> ManifestError {
+    ManifestError::InvalidQuery(path.to_string(), reason.to_string())
+}
+
+/// Writes the keys of a table as a dotted name.
+fn table_name(keys: &[String]) -> String {
+    keys.iter()
+        .map(|key| quote_key(key))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Finds the `index`-th table whose name has the given keys.
+fn find_table<'a>(document: &'a Document, keys: &[String], index: usize) -> Option<&'a Table> {
+    document
+        .tables
+        .iter()
+        .filter(|table| {
+            split_table_name(table.header.text.trim_matches(['[', ']'])).as_slice() == keys
+        })
+        .nth(index)
+}
+
+/// Finds the entry with the given key in a table.
+fn find_entry<'a>(table: &'a Table, key: &str) -> Option<&'a Item> {
+    table
+        .entries
+        .iter()
+        .find(|entry| unquote_key(&entry.key) == key)
+}
+
+/// Replaces the value of an entry, or inserts the entry if the table lacks it.
+///
+/// With `sorted`, a new entry goes before the first greater key if the table's
+/// entries are in key order.
+fn upsert(source: &str, table: &Table, key: &str, value: &str, sorted: bool) -> String {
+    if let Some(entry) = find_entry(table, key) {
+        let start = value_start(source, entry);
+        return splice(source, start, entry.end, value);
+    }
+
+    let line = format!("{} = {}", quote_key(key), value);
+    let in_order = table
+        .entries
+        .windows(2)
+        .all(|pair| pair[0].key <= pair[1].key);
+    let next = table.entries.iter().find(|entry| entry.key.as_str() > key);
+    match next {
+        Some(next) if sorted && in_order => {
+            let mut start = line_start(source, next.start);
+            while start > 0 {
+                let previous = line_start(source, start - 1);
+                if !source[previous..start].trim_start().starts_with('#') {
+                    break;
+                }
+                start = previous;
+            }
+            let indent = indent(source, next.start);
+            splice(source, start, start, &format!("{}{}\n", indent, line))
+        }
+        _ => {
+            let last = table.entries.last().unwrap_or(&table.header);
+            let end = line_end(source, last.end);
+            let indent = if table.entries.is_empty() {
+                ""
+            } else {
+                indent(source, last.start)
+            };
+            splice(source, end, end, &format!("\n{}{}", indent, line))
+        }
+    }
+}
+
+/// Appends a new table holding a single entry to the end of the manifest.
+fn append_table(source: &str, keys: &[String], line: &str) -> String {
+    let mut edited = source.to_string();
+    if !edited.is_empty() && !edited.ends_with('\n') {
+        edited.push('\n');
+    }
+    if !edited.trim().is_empty() {
+        edited.push('\n');
+    }
+    edited.push_str(&format!("[{}]\n{}\n", table_name(keys), line));
+    edited
+}
+
+/// Removes the lines from the one holding `start` to the one holding `end`,
+/// together with a blank line that would otherwise be doubled.
+fn remove_lines(source: &str, start: usize, end: usize) -> String {
+    let start = line_start(source, start);
+    let mut end = (line_end(source, end) + 1).min(source.len());
+    if source[end..].starts_with('\n') && (start == 0 || source[..start].ends_with("\n\n")) {
+        end += 1;
+    }
+
+    let edited = format!("{}{}", &source[..start], &source[end..]);
+    if end == source.len() && !edited.is_empty() {
+        format!("{}\n", edited.trim_end())
+    } else {
+        edited
+    }
+}
+
+/// Finds where the value of an entry starts, after `=` and any spaces.
+fn value_start(source: &str, entry: &Item) -> usize {
+    let text = &source[entry.start..entry.end];
+    let key_end = match text.chars().next() {
+        Some(quote @ ('"' | '\'')) => text[1..].find(quote).map_or(0, |end| end + 2),
+        _ => 0,
+    };
+    let equals = key_end + text[key_end..].find('=').unwrap_or(0) + 1;
+    let spaces = text[equals..].len() - text[equals..].trim_start().len();
+    entry.start + equals + spaces
+}
+
+/// Replaces the text between two byte offsets.
+fn splice(source: &str, start: usize, end: usize, text: &str) -> String {
+    format!("{}{}{}", &source[..start], text, &source[end..])
+}
+
+/// Returns the byte offset of the start of the line holding `offset`.
+fn line_start(source: &str, offset: usize) -> usize {
+    source[..offset]
+        .rfind('\n')
+        .map_or(0, |newline| newline + 1)
+}
+
+/// Returns the byte offset of the newline ending the line holding `offset`.
+fn line_end(source: &str, offset: usize) -> usize {
+    source[offset..]
+        .find('\n')
+        .map_or(source.len(), |newline| offset + newline)
+}
+
+/// Returns the whitespace before `offset` on its line.
+fn indent(source: &str, offset: usize) -> &str {
+    let prefix = &source[line_start(source, offset)..offset];
+    if prefix.trim().is_empty() {
+        prefix
+    } else {
+        ""
+    }
+}
+
+/// Checks that an edited manifest still parses.
+fn validate(edited: String) -> Result<String, ManifestError> {
+    Manifest::parse(&edited)?;
+    Ok(edited)
+}
@@ -0,0 +1,215 @@
+//! A C API over `Manifest`, built into the `cdylib` and `staticlib` of the crate.
+//!
+//! Manifests are handed out as opaque `MpManifest` pointers. Strings returned
+//! to the caller are allocated by this library and released with
+//! `mp_string_free`; functions that fail return `NULL` and leave a message for
+//! `mp_last_error`. The declarations are generated into
+//! `include/manifest_parser.h` by cbindgen from this file.
+
+use crate::{Manifest, ManifestError};
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+/// A parsed manifest owned by the caller of the C API.
+pub struct MpManifest {
+    manifest: Manifest,
+}
+
+thread_local! {
+    /// The message of the last call on this thread that failed
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// The crate version as a C string.
+const VERSION: &CStr =
+    match CStr::from_bytes_with_nul(concat!(env!("CARGO_PKG_VERSION"), "\0").as_bytes()) {
+        Ok(version) => version,
+        Err(_) => panic!("the crate version contains a NUL byte"),
+    };
+
+/// Parses a manifest.
+///
+/// # Arguments
+///
+/// * `input` - The manifest content as UTF-8, which does not need to be NUL-terminated
+/// * `len` - The length of the content in bytes
+///
+/// # Returns
+///
+/// The parsed manifest, to be released with `mp_manifest_free`, or `NULL` if
+/// the content is not valid UTF-8 or is not a valid manifest
+///
+/// # Safety
+///
+/// `input` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn mp_manifest_parse(input: *const c_char, len: usize) -> *mut MpManifest {
+    guard(ptr::null_mut(), || {
+        if input.is_null() {
+            return Err(invalid_argument("input"));
+        }
+        let bytes = std::slice::from_raw_parts(input.cast::<u8>(), len);
+        let input = std::str::from_utf8(bytes)
+            .map_err(|e| ManifestError::ParseError(format!("Input is not UTF-8: {}", e)))?;
+        let manifest = Manifest::parse(input)?;
+        Ok(Box::into_raw(Box::new(MpManifest { manifest })))
+    })
+}
+
+/// Retrieves a value from a manifest given a section and key.
+///
+/// # Arguments
+///
+/// * `manifest` - The manifest
+/// * `section` - The NUL-terminated section name, such as `package` or `dependencies`
+/// * `key` - The NUL-terminated key within the section
+///
+/// # Returns
+///
+/// The value as `get_by_key` returns it, to be released with `mp_string_free`,
+/// or `NULL` if the section or key does not exist
+///
+/// # Safety
+///
+/// `manifest` must come from `mp_manifest_parse` and not be freed, and
+/// `section` and `key` must be NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn mp_manifest_get(
+    manifest: *const MpManifest,
+    section: *const c_char,
+    key: *const c_char,
+) -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        let manifest = manifest_ref(manifest)?;
+        let section = c_str(section, "section")?;
+        let key = c_str(key, "key")?;
+        let value = manifest.get_by_key(section, key)?;
+        CString::new(value).map(CString::into_raw).map_err(|_| {
+            ManifestError::InvalidValue(
+                section.to_string(),
+                key.to_string(),
+                "contains a NUL byte".to_string(),
+            )
+        })
+    })
+}
+
+/// Serializes a manifest as compact JSON, the same typed tree that `parse --format json` prints.
+///
+/// # Arguments
+///
+/// * `manifest` - The manifest
+///
+/// # Returns
+///
+/// The JSON document, to be released with `mp_string_free`, or `NULL` on failure
+///
+/// # Safety
+///
+/// `manifest` must come from `mp_manifest_parse` and not be freed.
+#[no_mangle]
+pub unsafe extern "C" fn mp_manifest_to_json(manifest: *const MpManifest) -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        let manifest = manifest_ref(manifest)?;
+        let json = serde_json::to_string(manifest).expect("a manifest serializes to JSON");
+        Ok(CString::new(json)
+            .expect("JSON escapes NUL bytes")
+            .into_raw())
+    })
+}
+
+/// Releases a manifest. Passing `NULL` does nothing.
+///
+/// # Safety
+///
+/// `manifest` must come from `mp_manifest_parse` and not be freed already.
+#[no_mangle]
+pub unsafe extern "C" fn mp_manifest_free(manifest: *mut MpManifest) {
+    if !manifest.is_null() {
+        drop(Box::from_raw(manifest));
+    }
+}
+
+/// Releases a string returned by this library. Passing `NULL` does nothing.
+///
+/// # Safety
+///
+/// `string` must come from this library and not be freed already.
+#[no_mangle]
+pub unsafe extern "C" fn mp_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+/// Retrieves the error message of the last parse, get or JSON call on this thread.
+///
+/// # Returns
+///
+/// The message, valid until the next such call on the same thread, or `NULL`
+/// if the last call succeeded
+#[no_mangle]
+pub extern "C" fn mp_last_error() -> *const c_char {
+    LAST_ERROR.with(|error| {
+        error
+            .borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Retrieves the version of the library, such as `0.2.0`.
+///
+/// # Returns
+///
+/// A static NUL-terminated string that must not be freed
+#[no_mangle]
+pub extern "C" fn mp_version() -> *const c_char {
+    VERSION.as_ptr()
+}
+
+/// Runs the body of an API function, recording its error or panic for
+/// `mp_last_error` and returning `failed` in that case.
+fn guard<T>(failed: T, body: impl FnOnce() -> Result<T, ManifestError>) -> T {
+    let (result, error) = match catch_unwind(AssertUnwindSafe(body)) {
+        Ok(Ok(value)) => (value, None),
+        Ok(Err(e)) => (failed, Some(e.to_string())),
+        Err(panic) => {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            (failed, Some(format!("Internal error: {}", message)))
+        }
+    };
+    let error = error
+        .map(|message| CString::new(message.replace('\0', "\\0")).expect("NUL bytes are escaped"));
+    LAST_ERROR.with(|last| *last.borrow_mut() = error);
+    result
+}
+
+/// Borrows the manifest behind a handle.
+unsafe fn manifest_ref<'a>(manifest: *const MpManifest) -> Result<&'a Manifest, ManifestError> {
+    manifest
+        .as_ref()
+        .map(|handle| &handle.manifest)
+        .ok_or_else(|| invalid_argument("manifest"))
+}
+
+/// Reads a NUL-terminated UTF-8 argument.
+unsafe fn c_str<'a>(string: *const c_char, name: &str) -> Result<&'a str, ManifestError> {
+    if string.is_null() {
+        return Err(invalid_argument(name));
+    }
+    CStr::from_ptr(string)
+        .to_str()
+        .map_err(|e| ManifestError::InvalidArgument(format!("{} is not UTF-8: {}", name, e)))
+}
+
+/// Reports a `NULL` pointer passed for a required argument.
+fn invalid_argument(name: &str) -> ManifestError {
+    ManifestError::InvalidArgument(format!("{} is NULL", name))
+}
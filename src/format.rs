@@ -0,0 +1,259 @@
+//! A normalized package model shared by the manifest formats of different ecosystems.
+//!
+//! A `ManifestFormat` reads one kind of manifest, such as `Cargo.toml`, into a
+//! `Package` with its dependencies. Tooling written against `Package` works the
+//! same for every format registered in a `Formats`.
+
+use crate::{Dependency, DependencyKind, Manifest, ManifestError};
+use serde::Serialize;
+use std::fmt;
+use std::path::Path;
+
+/// A package as described by a manifest of any format.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Package {
+    /// The id of the format the package was read with, e.g. `cargo`
+    pub format: String,
+    /// The package name, if the manifest declares one
+    pub name: Option<String>,
+    /// The package version, if the manifest declares one
+    pub version: Option<String>,
+    /// The dependencies in the order they are declared
+    pub dependencies: Vec<PackageDependency>,
+}
+
+/// A dependency of a `Package`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PackageDependency {
+    /// The name of the package depended on
+    pub name: String,
+    /// The name the dependency is declared and used under, if it differs from `name`
+    pub alias: Option<String>,
+    /// The version requirement, if any
+    pub requirement: Option<String>,
+    /// Whether the dependency is needed at run time, for development or for building
+    pub kind: DependencyKind,
+    /// Where the dependency comes from
+    pub source: DependencySource,
+    /// The platform the dependency is limited to, as written in the manifest
+    pub target: Option<String>,
+    /// Whether the dependency is optional
+    pub optional: bool,
+}
+
+/// Where a dependency comes from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencySource {
+    /// A package registry, named if it is not the default one
+    Registry(Option<String>),
+    /// A git repository along with the branch, tag or revision to check out
+    Git {
+        /// Repository URL
+        url: String,
+        /// Branch, tag or revision, if any
+        reference: Option<String>,
+    },
+    /// A local path
+    Path(String),
+    /// Whatever the enclosing workspace declares for the dependency
+    Workspace,
+}
+
+/// A kind of manifest that can be read into a `Package`.
+pub trait ManifestFormat: Send + Sync {
+    /// The identifier of the format, e.g. `cargo`.
+    fn id(&self) -> &'static str;
+
+    /// The file names manifests of the format have, e.g. `Cargo.toml`.
+    fn file_names(&self) -> &'static [&'static str];
+
+    /// Reads a manifest into the normalized model.
+    fn parse(&self, input: &str) -> Result<Package, ManifestError>;
+}
+
+/// The `Cargo.toml` format, read with `Manifest::parse`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CargoFormat;
+
+impl ManifestFormat for CargoFormat {
+    fn id(&self) -> &'static str {
+        "cargo"
+    }
+
+    fn file_names(&self) -> &'static [&'static str] {
+        &["Cargo.toml"]
+    }
+
+    fn parse(&self, input: &str) -> Result<Package, ManifestError> {
+        Ok(Package::from(&Manifest::parse(input)?))
+    }
+}
+
+impl From<&Manifest> for Package {
+    fn from(manifest: &Manifest) -> Self {
+        let package = |key| manifest.get_by_key("package", key).ok().map(String::from);
+        Package {
+            format: CargoFormat.id().to_string(),
+            name: package("name"),
+            version: package("version"),
+            dependencies: manifest
+                .dependencies()
+                .map(PackageDependency::from)
+                .collect(),
+        }
+    }
+}
+
+impl From<&Dependency> for PackageDependency {
+    fn from(dependency: &Dependency) -> Self {
+        let source = if dependency.workspace {
+            DependencySource::Workspace
+        } else if let Some(url) = &dependency.git {
+            DependencySource::Git {
+                url: url.clone(),
+                reference: [&dependency.rev, &dependency.tag, &dependency.branch]
+                    .into_iter()
+                    .find_map(|reference| reference.clone()),
+            }
+        } else if let Some(path) = &dependency.path {
+            DependencySource::Path(path.clone())
+        } else {
+            DependencySource::Registry(dependency.registry.clone())
+        };
+        PackageDependency {
+            name: dependency.package_name().to_string(),
+            alias: dependency.package.as_ref().map(|_| dependency.name.clone()),
+            requirement: dependency.version.clone(),
+            kind: dependency.kind,
+            source,
+            target: dependency.target.as_ref().map(|target| target.to_string()),
+            optional: dependency.optional,
+        }
+    }
+}
+
+/// Describes a dependency on one line, e.g. `serde ^1.0 (dev-dependencies, optional)`.
+impl fmt::Display for PackageDependency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)?;
+        if let Some(requirement) = &self.requirement {
+            write!(f, " {}", requirement)?;
+        }
+
+        let mut notes = Vec::new();
+        if self.kind != DependencyKind::Normal {
+            notes.push(self.kind.to_string());
+        }
+        if let Some(alias) = &self.alias {
+            notes.push(format!("as {}", alias));
+        }
+        match &self.source {
+            DependencySource::Registry(None) => {}
+            DependencySource::Registry(Some(registry)) => {
+                notes.push(format!("registry {}", registry))
+            }
+            DependencySource::Git { url, reference } => notes.push(match reference {
+                Some(reference) => format!("git {}#{}", url, reference),
+                None => format!("git {}", url),
+            }),
+            DependencySource::Path(path) => notes.push(format!("path {}", path)),
+            DependencySource::Workspace => notes.push("workspace".to_string()),
+        }
+        if let Some(target) = &self.target {
+            notes.push(format!("target {}", target));
+        }
+        if self.optional {
+            notes.push("optional".to_string());
+        }
+        if !notes.is_empty() {
+            write!(f, " ({})", notes.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// A set of manifest formats to pick from by id or file name.
+pub struct Formats {
+    /// The formats, in the order they are tried
+    formats: Vec<Box<dyn ManifestFormat>>,
+}
+
+impl Default for Formats {
+    fn default() -> Self {
+        Formats::new()
+    }
+}
+
+impl Formats {
+    /// Creates a set of every built-in format.
+    pub fn new() -> Self {
+        Formats {
+            formats: vec![Box::new(CargoFormat)],
+        }
+    }
+
+    /// Creates a set without any formats.
+    pub fn empty() -> Self {
+        Formats {
+            formats: Vec::new(),
+        }
+    }
+
+    /// Adds a format to the set.
+    pub fn add_format(&mut self, format: Box<dyn ManifestFormat>) {
+        self.formats.push(format);
+    }
+
+    /// Retrieves an iterator over the formats of the set.
+    pub fn formats(&self) -> impl Iterator<Item = &dyn ManifestFormat> {
+        self.formats.iter().map(|format| format.as_ref())
+    }
+
+    /// Retrieves a format by its id.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the format, e.g. `cargo`
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing either the format or a `ManifestError` if no format has the id
+    pub fn get(&self, id: &str) -> Result<&dyn ManifestFormat, ManifestError> {
+        self.formats()
+            .find(|format| format.id() == id)
+            .ok_or_else(|| ManifestError::UnknownFormat(id.to_string()))
+    }
+
+    /// Finds the format of a manifest by its file name.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the manifest
+    ///
+    /// # Returns
+    ///
+    /// The first format with the file name of `path`, or `None` if there is none
+    pub fn detect(&self, path: &Path) -> Option<&dyn ManifestFormat> {
+        let name = path.file_name()?.to_str()?;
+        self.formats()
+            .find(|format| format.file_names().contains(&name))
+    }
+
+    /// Reads a manifest with the format matching its file name.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the manifest, used to pick the format
+    /// * `input` - The manifest content
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing either the `Package` or a `ManifestError` if no
+    /// format matches the file name or the manifest does not parse
+    pub fn parse(&self, path: &Path, input: &str) -> Result<Package, ManifestError> {
+        self.detect(path)
+            .ok_or_else(|| ManifestError::UnknownFormat(path.display().to_string()))?
+            .parse(input)
+    }
+}
@@ -0,0 +1,496 @@
+//! Rewrites manifests into a canonical layout.
+//!
+//! Tables are put in the standard section order, dependency tables are sorted,
+//! strings are double-quoted, inline tables are laid out as `{ key = value, ... }`
+//! with consecutive inline-table entries aligned at their `=`, and spacing is
+//! normalized. Comment lines stay attached to the header or entry
+//! below them, and end-of-line comments to the line they end.
+
+use crate::{ManifestError, ManifestParser, Rule};
+use pest::iterators::Pair;
+use pest::Parser;
+
+/// Rank of every table whose header is not listed in `SECTION_ORDER`.
+const UNKNOWN_RANK: usize = 100;
+
+/// The standard order of tables; `target` covers target-specific dependency tables.
+const SECTION_ORDER: &[&str] = &[
+    "package",
+    "lib",
+    "bin",
+    "example",
+    "test",
+    "bench",
+    "features",
+    "dependencies",
+    "dev-dependencies",
+    "build-dependencies",
+    "target",
+    "lints",
+    "badges",
+    "profile",
+    "workspace",
+    "patch",
+    "replace",
+];
+
+/// A header or entry together with the comments attached to it.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Item {
+    /// The canonical text
+    pub(crate) text: String,
+    /// The key identifying an entry within its table
+    pub(crate) key: String,
+    /// Comment lines above the item
+    pub(crate) comments: Vec<String>,
+    /// Comment at the end of the item's last line
+    pub(crate) trailing: Option<String>,
+    /// Whether the item is a canonical entry with an inline table value
+    pub(crate) inline: bool,
+    /// Byte offset of the item in the source
+    pub(crate) start: usize,
+    /// Byte offset after the item in the source, without trailing whitespace
+    pub(crate) end: usize,
+}
+
+/// A table with its header and entries.
+#[derive(Debug, Clone)]
+pub(crate) struct Table {
+    /// Position of the table in `SECTION_ORDER`
+    rank: usize,
+    /// Whether entries are sorted by key
+    sorted: bool,
+    /// The `[header]` line
+    pub(crate) header: Item,
+    /// The `key = value` entries
+    pub(crate) entries: Vec<Item>,
+}
+
+/// The tables of a manifest in source order, with comments attached.
+#[derive(Debug, Clone)]
+pub(crate) struct Document {
+    /// The tables in source order
+    pub(crate) tables: Vec<Table>,
+    /// Comments after the last entry
+    pub(crate) footer: Vec<String>,
+}
+
+impl Document {
+    /// Parses a manifest into tables of canonical entries.
+    pub(crate) fn parse(source: &str) -> Result<Self, ManifestError> {
+        let manifest = ManifestParser::parse(Rule::manifest, source)
+            .map_err(|e| ManifestError::ParseError(e.to_string()))?
+            .next()
+            .unwrap();
+
+        let mut tables: Vec<Table> = manifest
+            .into_inner()
+            .filter(|item| item.as_rule() != Rule::EOI)
+            .map(|item| parse_table(item, source))
+            .collect();
+        let footer = attach_comments(&mut tables, source);
+        Ok(Document { tables, footer })
+    }
+
+    /// Renders the document in the canonical layout.
+    pub(crate) fn render(mut self) -> String {
+        self.tables.sort_by_key(|table| table.rank);
+        for table in self.tables.iter_mut() {
+            if table.sorted {
+                table.entries.sort_by(|a, b| a.key.cmp(&b.key));
+            }
+            align(&mut table.entries);
+        }
+
+        let mut formatted = String::new();
+        for (i, table) in self.tables.iter().enumerate() {
+            if i > 0 {
+                formatted.push('\n');
+            }
+            render(&mut formatted, &table.header);
+            for entry in &table.entries {
+                render(&mut formatted, entry);
+            }
+        }
+        if !self.footer.is_empty() {
+            formatted.push('\n');
+            for comment in self.footer {
+                formatted.push_str(&comment);
+                formatted.push('\n');
+            }
+        }
+        formatted
+    }
+}
+
+/// Rewrites a manifest into the canonical layout.
+///
+/// # Arguments
+///
+/// * `source` - The manifest content as a string
+///
+/// # Returns
+///
+/// A `Result` containing either the formatted manifest or a `ManifestError` if it does not parse
+pub fn format_manifest(source: &str) -> Result<String, ManifestError> {
+    Ok(Document::parse(source)?.render())
+}
+
+/// Checks whether a manifest is already in the canonical layout.
+///
+/// # Arguments
+///
+/// * `source` - The manifest content as a string
+///
+/// # Returns
+///
+/// A `Result` containing whether formatting would leave the manifest unchanged
+pub fn is_formatted(source: &str) -> Result<bool, ManifestError> {
+    Ok(format_manifest(source)? == source)
+}
+
+/// Builds a table from a top-level pair of the manifest.
+fn parse_table(item: Pair<Rule>, source: &str) -> Table {
+    let start = item.as_span().start();
+    let rule = item.as_rule();
+    let mut inner = item.into_inner();
+
+    match rule {
+        Rule::package_section => {
+            let mut entries = Vec::new();
+            for field in inner {
+                match field.as_rule() {
+                    Rule::package_name | Rule::package_version => {
+                        let name = if field.as_rule() == Rule::package_name {
+                            "name"
+                        } else {
+                            "version"
+                        };
+                        let value = field.clone().into_inner().next().unwrap();
+                        let text = format!("{} = {}", name, value.as_str().trim());
+                        entries.push(entry(field, name, text));
+                    }
+                    _ => entries.extend(field.into_inner().map(key_value)),
+                }
+            }
+            Table {
+                rank: rank("package"),
+                sorted: false,
+                header: literal_header(start, source, "[package]"),
+                entries,
+            }
+        }
+        Rule::dependencies_section | Rule::patch_section => {
+            let header_pair = inner.next().unwrap();
+            let mut section = String::new();
+            for part in header_pair
+                .clone()
+                .into_inner()
+                .next()
+                .unwrap()
+                .into_inner()
+            {
+                match (rule, part.as_rule()) {
+                    (Rule::patch_section, _) => {
+                        section.push_str(&format!("patch.{}", requote(part.as_str())))
+                    }
+                    (_, Rule::table_key) => {
+                        section.push_str(&format!("target.{}.", requote(part.as_str())))
+                    }
+                    _ => section.push_str(part.as_str()),
+                }
+            }
+            let group = section.split('.').next().unwrap();
+
+            Table {
+                rank: rank(group),
+                sorted: true,
+                header: header(&header_pair, format!("[{}]", section)),
+                entries: inner.map(dependency).collect(),
+            }
+        }
+        Rule::replace_section => Table {
+            rank: rank("replace"),
+            sorted: false,
+            header: literal_header(start, source, "[replace]"),
+            entries: inner.map(dependency).collect(),
+        },
+        Rule::lints_section => {
+            let header_pair = inner.next().unwrap();
+            let section = header_pair.clone().into_inner().next().unwrap().as_str();
+            Table {
+                rank: rank(section.split('.').next().unwrap()),
+                sorted: false,
+                header: header(&header_pair, format!("[{}]", section)),
+                entries: inner.map(lint).collect(),
+            }
+        }
+        _ => {
+            let definition = inner.next().unwrap();
+            let text: String = definition
+                .as_str()
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .collect();
+            let name = text.trim_matches(|c| c == '[' || c == ']');
+            Table {
+                rank: rank(name.split('.').next().unwrap()),
+                sorted: false,
+                header: header(&definition, text.clone()),
+                entries: inner.next().unwrap().into_inner().map(key_value).collect(),
+            }
+        }
+    }
+}
+
+/// Returns the position of a table in the standard order.
+fn rank(section: &str) -> usize {
+    SECTION_ORDER
+        .iter()
+        .position(|name| *name == section)
+        .unwrap_or(UNKNOWN_RANK)
+}
+
+/// Builds the header item of a table from its `[header]` pair.
+fn header(item: &Pair<Rule>, text: String) -> Item {
+    Item {
+        text,
+        start: item.as_span().start(),
+        end: item.as_span().end(),
+        ..Item::default()
+    }
+}
+
+/// Builds the header item of a table whose header is a literal starting at `start`.
+fn literal_header(start: usize, source: &str, text: &str) -> Item {
+    Item {
+        text: text.to_string(),
+        start,
+        end: start + source[start..].find(']').unwrap() + 1,
+        ..Item::default()
+    }
+}
+
+/// Builds an entry item, keeping the original text if it contains comments.
+fn entry(item: Pair<Rule>, key: &str, text: String) -> Item {
+    let raw = item.as_str().trim_end();
+    let start = item.as_span().start();
+    Item {
+        text: if has_comment(raw) {
+            raw.to_string()
+        } else {
+            text
+        },
+        key: key.to_string(),
+        start,
+        end: start + raw.len(),
+        ..Item::default()
+    }
+}
+
+/// Builds an entry item whose value is an inline table.
+fn inline_entry(item: Pair<Rule>, key: &str, text: String) -> Item {
+    let mut entry = entry(item, key, text.clone());
+    entry.inline = entry.text == text;
+    entry
+}
+
+/// Pads the keys of consecutive inline-table entries so that their `=` line up.
+fn align(entries: &mut [Item]) {
+    for run in entries.chunk_by_mut(|a, b| a.inline && b.inline) {
+        let width = run.iter().map(|entry| entry.key.chars().count()).max();
+        let Some(width) = width.filter(|_| run.len() > 1) else {
+            continue;
+        };
+        for entry in run {
+            let value = &entry.text[entry.key.len()..];
+            entry.text = format!("{:<width$}{}", entry.key, value);
+        }
+    }
+}
+
+/// Formats a plain `key = value` pair.
+fn key_value(item: Pair<Rule>) -> Item {
+    let mut inner = item.clone().into_inner();
+    let key = inner.next().unwrap().as_str();
+    let value = normalize_array(inner.next().unwrap().as_str().trim());
+    let text = format!("{} = {}", key, value);
+    entry(item, key, text)
+}
+
+/// Formats an entry of a dependency, `[patch]` or `[replace]` table.
+fn dependency(item: Pair<Rule>) -> Item {
+    let mut inner = item.clone().into_inner();
+    let key = inner.next().unwrap().as_str();
+    let value = inner.next().unwrap();
+    let inline = value.as_rule() == Rule::dependency_spec;
+    let value = match value.as_rule() {
+        Rule::dependency_spec => inline_table(value.into_inner().map(|field| {
+            let name = match field.as_rule() {
+                Rule::dependency_default_features => "default-features",
+                Rule::features => {
+                    let features: Vec<String> = field
+                        .into_inner()
+                        .map(|feature| format!("\"{}\"", feature.as_str()))
+                        .collect();
+                    return format!("features = [{}]", features.join(", "));
+                }
+                _ => field.as_str().split('=').next().unwrap().trim(),
+            };
+            match field.into_inner().next() {
+                Some(value) => format!("{} = {}", name, value.as_str()),
+                None => format!("{} = true", name),
+            }
+        })),
+        _ => value.as_str().to_string(),
+    };
+    let text = format!("{} = {}", key, value);
+    if inline {
+        inline_entry(item, key, text)
+    } else {
+        entry(item, key, text)
+    }
+}
+
+/// Formats an entry of a `[lints.<tool>]` table.
+fn lint(item: Pair<Rule>) -> Item {
+    let mut inner = item.clone().into_inner();
+    let key = inner.next().unwrap().as_str();
+    let value = inner.next().unwrap();
+    let inline = value.as_rule() == Rule::lint_spec;
+    let value = match value.as_rule() {
+        Rule::lint_spec => inline_table(value.into_inner().map(|field| match field.as_rule() {
+            Rule::lint_check_cfg => {
+                let values: Vec<String> = field.into_inner().map(|v| requote(v.as_str())).collect();
+                format!("check-cfg = [{}]", values.join(", "))
+            }
+            Rule::lint_level => format!("level = {}", field.into_inner().next().unwrap().as_str()),
+            _ => format!("priority = {}", field.into_inner().next().unwrap().as_str()),
+        })),
+        _ => value.as_str().to_string(),
+    };
+    let text = format!("{} = {}", key, value);
+    if inline {
+        inline_entry(item, key, text)
+    } else {
+        entry(item, key, text)
+    }
+}
+
+/// Lays out the fields of an inline table.
+fn inline_table(fields: impl Iterator<Item = String>) -> String {
+    format!("{{ {} }}", fields.collect::<Vec<_>>().join(", "))
+}
+
+/// Double-quotes a single-quoted string unless that would need escapes.
+fn requote(value: &str) -> String {
+    match value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+        Some(content) if !content.contains(['"', '\\']) => format!("\"{}\"", content),
+        _ => value.to_string(),
+    }
+}
+
+/// Normalizes the spacing of a one-line array value, e.g. `["a","b",]` to `["a", "b"]`.
+fn normalize_array(value: &str) -> String {
+    let Some(content) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) else {
+        return value.to_string();
+    };
+    if content.contains(['[', ']']) {
+        return value.to_string();
+    }
+
+    let mut elements = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in content.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                current.push(c);
+            }
+            ',' if !quoted => elements.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    elements.push(current);
+
+    let elements: Vec<&str> = elements
+        .iter()
+        .map(|element| element.trim())
+        .filter(|element| !element.is_empty())
+        .collect();
+    format!("[{}]", elements.join(", "))
+}
+
+/// Checks whether a piece of source contains a comment outside of strings.
+fn has_comment(text: &str) -> bool {
+    let mut quote = None;
+    for c in text.chars() {
+        match (quote, c) {
+            (None, '#') => return true,
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Moves the comments between items of the source onto the items.
+///
+/// # Returns
+///
+/// The comments after the last item
+fn attach_comments(tables: &mut [Table], source: &str) -> Vec<String> {
+    let mut items: Vec<&mut Item> = Vec::new();
+    for table in tables.iter_mut() {
+        items.push(&mut table.header);
+        items.extend(table.entries.iter_mut());
+    }
+
+    let mut previous_end = 0;
+    let mut pending = Vec::new();
+    for index in 0..=items.len() {
+        let start = items.get(index).map_or(source.len(), |item| item.start);
+        let gap = &source[previous_end..start];
+        let mut lines = gap.split('\n');
+
+        let first = lines.next().unwrap_or_default();
+        if index > 0 {
+            if let Some(comment) = first.find('#') {
+                items[index - 1].trailing = Some(first[comment..].trim().to_string());
+            }
+        } else if first.trim_start().starts_with('#') {
+            pending.push(first.trim().to_string());
+        }
+        pending.extend(
+            lines
+                .map(str::trim)
+                .filter(|line| line.starts_with('#'))
+                .map(str::to_string),
+        );
+
+        match items.get_mut(index) {
+            Some(item) => {
+                item.comments = std::mem::take(&mut pending);
+                previous_end = item.end;
+            }
+            None => return pending,
+        }
+    }
+    pending
+}
+
+/// Appends an item with its comments.
+fn render(formatted: &mut String, item: &Item) {
+    for comment in &item.comments {
+        formatted.push_str(comment);
+        formatted.push('\n');
+    }
+    formatted.push_str(&item.text);
+    if let Some(comment) = &item.trailing {
+        formatted.push(' ');
+        formatted.push_str(comment);
+    }
+    formatted.push('\n');
+}
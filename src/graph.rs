@@ -0,0 +1,233 @@
+//! Dependency graph between the members of a workspace.
+
+use crate::{DependencyKind, Manifest, ManifestError};
+use std::collections::{BTreeSet, HashMap};
+
+/// A dependency edge between two workspace members.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Edge {
+    /// Index of the dependent member
+    from: usize,
+    /// Index of the member depended upon
+    to: usize,
+    /// The table the dependency was declared in
+    kind: DependencyKind,
+}
+
+/// A graph of workspace members connected by their path and workspace dependencies.
+#[derive(Debug, Default)]
+pub struct DependencyGraph {
+    /// Member package names in the order the manifests were given
+    members: Vec<String>,
+    /// Edges between members
+    edges: Vec<Edge>,
+}
+
+impl DependencyGraph {
+    /// Builds the graph of the given workspace members.
+    ///
+    /// Only dependencies declared with `path` or `workspace = true` that name
+    /// another member become edges; everything else is external.
+    ///
+    /// # Arguments
+    ///
+    /// * `manifests` - The manifests of every workspace member
+    ///
+    /// # Returns
+    ///
+    /// The dependency graph of the workspace
+    pub fn new(manifests: &[Manifest]) -> Self {
+        let members: Vec<String> = manifests
+            .iter()
+            .map(|manifest| manifest.package_name().to_string())
+            .collect();
+        let index: HashMap<&str, usize> = members
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.as_str(), i))
+            .collect();
+
+        let mut edges = Vec::new();
+        for (from, manifest) in manifests.iter().enumerate() {
+            for dependency in manifest.dependencies() {
+                if dependency.path.is_none() && !dependency.workspace {
+                    continue;
+                }
+                if let Some(&to) = index.get(dependency.package_name()) {
+                    let edge = Edge {
+                        from,
+                        to,
+                        kind: dependency.kind,
+                    };
+                    if !edges.contains(&edge) {
+                        edges.push(edge);
+                    }
+                }
+            }
+        }
+
+        DependencyGraph { members, edges }
+    }
+
+    /// Retrieves an iterator over the member names.
+    pub fn members(&self) -> impl Iterator<Item = &str> {
+        self.members.iter().map(String::as_str)
+    }
+
+    /// Retrieves the members a member depends on.
+    ///
+    /// # Arguments
+    ///
+    /// * `member` - The name of the dependent member
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the dependencies with their kind, or a `ManifestError`
+    /// if the member does not exist
+    pub fn dependencies_of(
+        &self,
+        member: &str,
+    ) -> Result<Vec<(&str, DependencyKind)>, ManifestError> {
+        let index = self.index_of(member)?;
+        Ok(self
+            .edges
+            .iter()
+            .filter(|edge| edge.from == index)
+            .map(|edge| (self.members[edge.to].as_str(), edge.kind))
+            .collect())
+    }
+
+    /// Retrieves the members that depend directly on a member.
+    ///
+    /// # Arguments
+    ///
+    /// * `member` - The name of the member depended upon
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the dependents with their kind, or a `ManifestError`
+    /// if the member does not exist
+    pub fn dependents_of(
+        &self,
+        member: &str,
+    ) -> Result<Vec<(&str, DependencyKind)>, ManifestError> {
+        let index = self.index_of(member)?;
+        Ok(self
+            .edges
+            .iter()
+            .filter(|edge| edge.to == index)
+            .map(|edge| (self.members[edge.from].as_str(), edge.kind))
+            .collect())
+    }
+
+    /// Retrieves every member that depends on a member directly or indirectly
+    /// through normal and build dependencies, i.e. everything that has to be
+    /// republished after it.
+    ///
+    /// # Arguments
+    ///
+    /// * `member` - The name of the member depended upon
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the dependents in member order, or a `ManifestError`
+    /// if the member does not exist
+    pub fn transitive_dependents_of(&self, member: &str) -> Result<Vec<&str>, ManifestError> {
+        let start = self.index_of(member)?;
+        let mut seen = vec![false; self.members.len()];
+        let mut stack = vec![start];
+
+        while let Some(index) = stack.pop() {
+            for edge in self.ordering_edges().filter(|edge| edge.to == index) {
+                if !seen[edge.from] {
+                    seen[edge.from] = true;
+                    stack.push(edge.from);
+                }
+            }
+        }
+
+        Ok(self
+            .members
+            .iter()
+            .enumerate()
+            .filter(|&(index, _)| seen[index] && index != start)
+            .map(|(_, name)| name.as_str())
+            .collect())
+    }
+
+    /// Orders the members so that every member comes after the members it
+    /// depends on, which is the order they have to be published in.
+    ///
+    /// Dev-dependencies are ignored, so cycles through them are allowed.
+    /// Members without constraints between them keep the order they were given in.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the ordered member names, or a
+    /// `ManifestError::DependencyCycle` naming the members of a cycle
+    pub fn topological_order(&self) -> Result<Vec<&str>, ManifestError> {
+        let mut remaining = vec![0usize; self.members.len()];
+        for edge in self.ordering_edges() {
+            remaining[edge.from] += 1;
+        }
+
+        let mut ready: BTreeSet<usize> = (0..self.members.len())
+            .filter(|&index| remaining[index] == 0)
+            .collect();
+        let mut order = Vec::with_capacity(self.members.len());
+
+        while let Some(index) = ready.pop_first() {
+            order.push(self.members[index].as_str());
+            for edge in self.ordering_edges().filter(|edge| edge.to == index) {
+                remaining[edge.from] -= 1;
+                if remaining[edge.from] == 0 {
+                    ready.insert(edge.from);
+                }
+            }
+        }
+
+        if order.len() == self.members.len() {
+            Ok(order)
+        } else {
+            Err(ManifestError::DependencyCycle(self.find_cycle(&remaining)))
+        }
+    }
+
+    /// Returns the edges that constrain the publishing order.
+    fn ordering_edges(&self) -> impl Iterator<Item = &Edge> {
+        self.edges
+            .iter()
+            .filter(|edge| edge.kind != DependencyKind::Dev)
+    }
+
+    /// Walks the unordered members to extract one cycle.
+    fn find_cycle(&self, remaining: &[usize]) -> Vec<String> {
+        let mut path: Vec<usize> = Vec::new();
+        let mut current = (0..self.members.len())
+            .find(|&index| remaining[index] > 0)
+            .unwrap();
+
+        while !path.contains(&current) {
+            path.push(current);
+            current = self
+                .ordering_edges()
+                .find(|edge| edge.from == current && remaining[edge.to] > 0)
+                .map(|edge| edge.to)
+                .unwrap();
+        }
+
+        let start = path.iter().position(|&index| index == current).unwrap();
+        path[start..]
+            .iter()
+            .map(|&index| self.members[index].clone())
+            .collect()
+    }
+
+    /// Looks up the index of a member by name.
+    fn index_of(&self, member: &str) -> Result<usize, ManifestError> {
+        self.members
+            .iter()
+            .position(|name| name == member)
+            .ok_or_else(|| ManifestError::MissingMember(member.to_string()))
+    }
+}
@@ -0,0 +1,1299 @@
+//! A hand-written recursive descent parser for the manifest grammar.
+//!
+//! It accepts exactly the manifests `grammar.pest` accepts and builds the same
+//! `Manifest`, but it records sections straight from the input instead of
+//! building a tree of pairs, and it finds lines and columns in a table of line
+//! starts instead of rescanning the input for every span.
+//!
+//! Every rule of the grammar has a method of the same name. Whitespace and
+//! comments are skipped exactly where pest skips them between the parts of a
+//! non-atomic rule, and failed rules are tracked the way pest tracks them, so a
+//! manifest that does not parse gets the same error message and position.
+
+use crate::cfg::{unquote_key, Platform};
+use crate::lints::LintField;
+use crate::{
+    header_name, Dependency, DependencyKind, Lint, Manifest, ManifestError, PatchEntry,
+    ReplaceEntry, Rule, Span,
+};
+use pest::error::{Error, ErrorVariant};
+use pest::Position;
+
+/// Parses a manifest string.
+///
+/// # Arguments
+///
+/// * `input` - The manifest content as a string
+///
+/// # Returns
+///
+/// A `Result` containing either a parsed `Manifest` or a `ManifestError`
+pub(crate) fn parse(input: &str) -> Result<Manifest, ManifestError> {
+    let mut parser = Parser::new(input);
+    match parser.manifest() {
+        Some(items) => build(input, items),
+        None => Err(parser.error()),
+    }
+}
+
+/// A `key = value` entry, with its value as written.
+struct Entry<'a> {
+    key: &'a str,
+    value: &'a str,
+    start: usize,
+    end: usize,
+}
+
+/// A section of the manifest, as recognized by the grammar.
+enum Item<'a> {
+    /// `[package]`, starting with its `name` and `version` entries
+    Package {
+        span: (usize, usize),
+        entries: Vec<Entry<'a>>,
+    },
+    /// A dependencies table, optionally under `[target.<platform>]`
+    Dependencies {
+        span: (usize, usize),
+        table: &'a str,
+        target: Option<&'a str>,
+        kind: &'a str,
+        entries: Vec<(Entry<'a>, Dependency)>,
+    },
+    /// A `[patch.<source>]` table
+    Patch {
+        span: (usize, usize),
+        table: &'a str,
+        source: &'a str,
+        entries: Vec<(Entry<'a>, Dependency)>,
+    },
+    /// The `[replace]` table, keyed by `name:version`
+    Replace {
+        span: (usize, usize),
+        entries: Vec<(Entry<'a>, Dependency)>,
+    },
+    /// A `[lints.<tool>]` or `[workspace.lints.<tool>]` table
+    Lints {
+        span: (usize, usize),
+        table: &'a str,
+        tool: &'a str,
+        entries: Vec<(Entry<'a>, Vec<LintField<'a>>)>,
+    },
+    /// Any other section, given by its header as written
+    Section {
+        span: (usize, usize),
+        header: &'a str,
+        entries: Vec<Entry<'a>>,
+    },
+}
+
+/// Builds the manifest from its sections, the way `Manifest::parse` walks its pairs.
+fn build(input: &str, items: Vec<Item>) -> Result<Manifest, ManifestError> {
+    let lines = Lines::new(input);
+    let mut manifest = Manifest::default();
+    let insert = |manifest: &mut Manifest, section: &str, entry: &Entry| {
+        let span = lines.span(entry.start, entry.end);
+        manifest.insert_entry(section, entry.key, entry.value, span);
+    };
+
+    for item in items {
+        match item {
+            Item::Package { span, entries } => {
+                manifest.open_section("package", lines.span(span.0, span.1));
+                for entry in &entries {
+                    insert(&mut manifest, "package", entry);
+                }
+            }
+            Item::Dependencies {
+                span,
+                table,
+                target,
+                kind,
+                entries,
+            } => {
+                let target = target.map(Platform::parse).transpose()?;
+                let kind = DependencyKind::from_table(kind).unwrap_or(DependencyKind::Normal);
+                manifest.open_section(table, lines.span(span.0, span.1));
+                for (entry, mut dependency) in entries {
+                    dependency.kind = kind;
+                    dependency.target = target.clone();
+                    dependency.table = table.to_string();
+                    manifest.dependencies.push(dependency);
+                    insert(&mut manifest, table, &entry);
+                }
+            }
+            Item::Patch {
+                span,
+                table,
+                source,
+                entries,
+            } => {
+                let source = unquote_key(source);
+                manifest.open_section(table, lines.span(span.0, span.1));
+                for (entry, mut replacement) in entries {
+                    replacement.table = table.to_string();
+                    manifest.patches.push(PatchEntry {
+                        source: source.clone(),
+                        replacement,
+                    });
+                    insert(&mut manifest, table, &entry);
+                }
+            }
+            Item::Replace { span, entries } => {
+                manifest.open_section("replace", lines.span(span.0, span.1));
+                for (entry, mut replacement) in entries {
+                    let (package, version) = entry.key.split_once(':').ok_or_else(|| {
+                        ManifestError::ParseError(format!("Invalid package id {}", entry.key))
+                    })?;
+                    replacement.name = package.to_string();
+                    replacement.table = "replace".to_string();
+                    manifest.replacements.push(ReplaceEntry {
+                        package: package.to_string(),
+                        version: version.to_string(),
+                        replacement,
+                    });
+                    insert(&mut manifest, "replace", &entry);
+                }
+            }
+            Item::Lints {
+                span,
+                table,
+                tool,
+                entries,
+            } => {
+                manifest.open_section(table, lines.span(span.0, span.1));
+                for (entry, fields) in entries {
+                    let lint = Lint::from_fields(tool, table, entry.key, fields)?;
+                    if table.starts_with("workspace.") {
+                        manifest.workspace_lints.push(lint);
+                    } else {
+                        manifest.lints.push(lint);
+                    }
+                    insert(&mut manifest, table, &entry);
+                }
+            }
+            Item::Section {
+                span,
+                header,
+                entries,
+            } => {
+                let section = header_name(header);
+                let array = header.trim_start().starts_with("[[");
+                manifest.replace_section(section, lines.span(span.0, span.1), array);
+                for entry in &entries {
+                    insert(&mut manifest, section, entry);
+                }
+            }
+        }
+    }
+
+    manifest.collect_patch_tables()?;
+    Ok(manifest)
+}
+
+/// The start of every line of the input, to find the line and column of a position.
+struct Lines<'a> {
+    input: &'a str,
+    starts: Vec<usize>,
+}
+
+impl<'a> Lines<'a> {
+    fn new(input: &'a str) -> Self {
+        let mut starts = vec![0];
+        starts.extend(
+            input
+                .bytes()
+                .enumerate()
+                .filter(|(_, byte)| *byte == b'\n')
+                .map(|(index, _)| index + 1),
+        );
+        Lines { input, starts }
+    }
+
+    /// Builds the span of a range, with the line and column pest would give its start.
+    fn span(&self, start: usize, end: usize) -> Span {
+        let line = self
+            .starts
+            .partition_point(|&line_start| line_start <= start);
+        let line_start = self.starts[line - 1];
+        Span {
+            start,
+            end,
+            line,
+            column: self.input[line_start..start].chars().count() + 1,
+        }
+    }
+}
+
+/// Characters of a `key` and of identifiers in versions.
+fn is_key_char(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'-'
+}
+
+/// Characters of semver identifiers: `ASCII_DIGIT | ASCII_ALPHA | "-"`.
+fn is_identifier_char(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'-'
+}
+
+/// `WHITESPACE`
+fn is_whitespace(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t' | b'\r' | b'\n')
+}
+
+/// `possible_value_char`
+fn is_value_char(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric()
+        || matches!(
+            byte,
+            b'_' | b'-'
+                | b'['
+                | b']'
+                | b'"'
+                | b'.'
+                | b'<'
+                | b'>'
+                | b'@'
+                | b':'
+                | b'\\'
+                | b'/'
+                | b','
+                | b'*'
+                | b'?'
+        )
+}
+
+/// The state of a parse: the input, the position and the failed rules pest would report.
+struct Parser<'a> {
+    input: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+    /// Furthest position at which a rule failed
+    attempt_pos: usize,
+    /// The rules that failed at `attempt_pos`
+    attempts: Vec<Rule>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Parser {
+            input,
+            bytes: input.as_bytes(),
+            pos: 0,
+            attempt_pos: 0,
+            attempts: Vec::new(),
+        }
+    }
+
+    /// Builds the error pest reports for the failed rules.
+    fn error(&mut self) -> ManifestError {
+        self.attempts.sort();
+        self.attempts.dedup();
+        let variant = ErrorVariant::ParsingError {
+            positives: self.attempts.clone(),
+            negatives: Vec::new(),
+        };
+        let position = Position::new(self.input, self.attempt_pos)
+            .unwrap_or_else(|| Position::from_start(self.input));
+        ManifestError::ParseError(Error::new_from_pos(variant, position).to_string())
+    }
+
+    /// Runs a rule called outside atomic rules, tracking it if it fails.
+    fn rule<T>(&mut self, rule: Rule, f: impl FnOnce(&mut Self) -> Option<T>) -> Option<T> {
+        self.rule_in(rule, true, f)
+    }
+
+    /// Runs a rule, restoring the position and, if `tracked`, tracking the rule if it fails.
+    ///
+    /// Rules called inside atomic rules are not tracked by pest.
+    fn rule_in<T>(
+        &mut self,
+        rule: Rule,
+        tracked: bool,
+        f: impl FnOnce(&mut Self) -> Option<T>,
+    ) -> Option<T> {
+        let start = self.pos;
+        let index = match start == self.attempt_pos {
+            true => self.attempts.len(),
+            false => 0,
+        };
+        let previous = self.attempts_at(start);
+        let result = f(self);
+        if result.is_none() {
+            self.pos = start;
+            if tracked {
+                self.track(rule, start, index, previous);
+            }
+        }
+        result
+    }
+
+    /// Records a failed rule, keeping only the rules that failed furthest into the input.
+    ///
+    /// Like pest, a rule is not recorded in place of the single rule it failed in.
+    fn track(&mut self, rule: Rule, pos: usize, index: usize, previous: usize) {
+        let current = self.attempts_at(pos);
+        if current > previous && current - previous == 1 {
+            return;
+        }
+        if pos == self.attempt_pos {
+            self.attempts.truncate(index);
+        }
+        if pos > self.attempt_pos {
+            self.attempts.clear();
+            self.attempt_pos = pos;
+        }
+        if pos == self.attempt_pos {
+            self.attempts.push(rule);
+        }
+    }
+
+    fn attempts_at(&self, pos: usize) -> usize {
+        match pos == self.attempt_pos {
+            true => self.attempts.len(),
+            false => 0,
+        }
+    }
+
+    /// Runs a part of a rule, restoring the position if it fails.
+    fn attempt<T>(&mut self, f: impl FnOnce(&mut Self) -> Option<T>) -> Option<T> {
+        let start = self.pos;
+        let result = f(self);
+        if result.is_none() {
+            self.pos = start;
+        }
+        result
+    }
+
+    /// Matches `e*` in a non-atomic rule, skipping whitespace between repetitions.
+    fn repeat<T>(&mut self, mut f: impl FnMut(&mut Self) -> Option<T>) -> Vec<T> {
+        let mut items = Vec::new();
+        if let Some(first) = self.attempt(&mut f) {
+            items.push(first);
+            while let Some(item) = self.attempt(|p| {
+                p.skip();
+                f(p)
+            }) {
+                items.push(item);
+            }
+        }
+        items
+    }
+
+    /// Matches `e+` in a non-atomic rule.
+    fn repeat1<T>(&mut self, f: impl FnMut(&mut Self) -> Option<T>) -> Option<Vec<T>> {
+        let items = self.repeat(f);
+        (!items.is_empty()).then_some(items)
+    }
+
+    /// Skips whitespace and comments, as pest does between the parts of non-atomic rules.
+    fn skip(&mut self) {
+        while let Some(&byte) = self.bytes.get(self.pos) {
+            if is_whitespace(byte) {
+                self.pos += 1;
+            } else if byte == b'#' {
+                while self
+                    .bytes
+                    .get(self.pos)
+                    .is_some_and(|&byte| byte != b'\n' && byte != b'\r')
+                {
+                    self.pos += 1;
+                }
+                if !self.eat("\r\n") && !self.eat("\n") {
+                    self.eat("\r");
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Skips whitespace only if the rule is not atomic.
+    fn separate(&mut self, atomic: bool) {
+        if !atomic {
+            self.skip();
+        }
+    }
+
+    /// Matches a literal, advancing past it if it is there.
+    fn eat(&mut self, literal: &str) -> bool {
+        let found = self.bytes[self.pos..].starts_with(literal.as_bytes());
+        if found {
+            self.pos += literal.len();
+        }
+        found
+    }
+
+    /// Matches a literal that is required.
+    fn expect(&mut self, literal: &str) -> Option<()> {
+        self.eat(literal).then_some(())
+    }
+
+    /// Matches one byte of a class.
+    fn byte(&mut self, class: impl Fn(u8) -> bool) -> Option<()> {
+        let found = self.bytes.get(self.pos).is_some_and(|&byte| class(byte));
+        if found {
+            self.pos += 1;
+        }
+        found.then_some(())
+    }
+
+    /// Matches as many bytes of a class as possible, returning how many.
+    fn bytes_while(&mut self, class: impl Fn(u8) -> bool) -> usize {
+        let start = self.pos;
+        while self.bytes.get(self.pos).is_some_and(|&byte| class(byte)) {
+            self.pos += 1;
+        }
+        self.pos - start
+    }
+
+    fn slice(&self, start: usize) -> &'a str {
+        &self.input[start..self.pos]
+    }
+
+    // manifest = { SOI ~ package_section ~ (dependencies_section | patch_section |
+    //              replace_section | lints_section | section)* ~ EOI }
+    fn manifest(&mut self) -> Option<Vec<Item<'a>>> {
+        self.rule(Rule::manifest, |p| {
+            p.skip();
+            let mut items = vec![p.package_section()?];
+            p.skip();
+            items.extend(p.repeat(|p| {
+                p.dependencies_section()
+                    .or_else(|| p.patch_section())
+                    .or_else(|| p.replace_section())
+                    .or_else(|| p.lints_section())
+                    .or_else(|| p.section())
+            }));
+            p.skip();
+            p.rule(Rule::EOI, |p| (p.pos == p.bytes.len()).then_some(()))?;
+            Some(items)
+        })
+    }
+
+    // package_section = { "[package]" ~ package_name ~ package_version ~ section_inside }
+    fn package_section(&mut self) -> Option<Item<'a>> {
+        self.rule(Rule::package_section, |p| {
+            let start = p.pos;
+            p.expect("[package]")?;
+            p.skip();
+            let name = p.package_name()?;
+            p.skip();
+            let version = p.package_version()?;
+            p.skip();
+            let mut entries = vec![name, version];
+            entries.extend(p.section_inside());
+            Some(Item::Package {
+                span: (start, p.pos),
+                entries,
+            })
+        })
+    }
+
+    // package_name = { "name" ~ "=" ~ value }
+    fn package_name(&mut self) -> Option<Entry<'a>> {
+        self.rule(Rule::package_name, |p| {
+            let start = p.pos;
+            p.expect("name")?;
+            p.skip();
+            p.expect("=")?;
+            p.skip();
+            let value = p.value()?;
+            Some(Entry {
+                key: "name",
+                value: value.trim(),
+                start,
+                end: p.pos,
+            })
+        })
+    }
+
+    // package_version = { "version" ~ "=" ~ version }
+    fn package_version(&mut self) -> Option<Entry<'a>> {
+        self.rule(Rule::package_version, |p| {
+            let start = p.pos;
+            p.expect("version")?;
+            p.skip();
+            p.expect("=")?;
+            p.skip();
+            let version = p.version()?;
+            Some(Entry {
+                key: "version",
+                value: version.trim(),
+                start,
+                end: p.pos,
+            })
+        })
+    }
+
+    // version = { "\"" ~ version_core ~ ("-" ~ pre_release)? ~ ("+" ~ build)? ~ "\"" }
+    fn version(&mut self) -> Option<&'a str> {
+        self.rule(Rule::version, |p| {
+            let start = p.pos;
+            p.expect("\"")?;
+            p.skip();
+            p.version_core()?;
+            p.skip();
+            p.attempt(|p| {
+                p.expect("-")?;
+                p.skip();
+                p.pre_release(false)
+            });
+            p.skip();
+            p.attempt(|p| {
+                p.expect("+")?;
+                p.skip();
+                p.build(false)
+            });
+            p.skip();
+            p.expect("\"")?;
+            Some(p.slice(start))
+        })
+    }
+
+    // version_core = _{ numeric_identifier ~ "." ~ numeric_identifier ~ "." ~ numeric_identifier }
+    fn version_core(&mut self) -> Option<()> {
+        self.numeric_identifier(false)?;
+        for _ in 0..2 {
+            self.skip();
+            self.expect(".")?;
+            self.skip();
+            self.numeric_identifier(false)?;
+        }
+        Some(())
+    }
+
+    // numeric_identifier = _{ "0" | ASCII_NONZERO_DIGIT ~ ASCII_DIGIT* }
+    fn numeric_identifier(&mut self, atomic: bool) -> Option<()> {
+        if self.eat("0") {
+            return Some(());
+        }
+        self.byte(|byte| matches!(byte, b'1'..=b'9'))?;
+        if atomic {
+            self.bytes_while(|byte| byte.is_ascii_digit());
+        } else {
+            self.skip();
+            self.repeat(|p| p.byte(|byte| byte.is_ascii_digit()));
+        }
+        Some(())
+    }
+
+    // pre_release = { dot_separated_pre_release_identifiers }
+    fn pre_release(&mut self, atomic: bool) -> Option<()> {
+        self.rule_in(Rule::pre_release, !atomic, |p| p.dot_separated(atomic))
+    }
+
+    // build = { dot_separated_build_identifiers | ASCII_DIGIT+ }
+    //
+    // `ASCII_DIGIT+` can only match where the identifiers already do.
+    fn build(&mut self, atomic: bool) -> Option<()> {
+        self.rule_in(Rule::build, !atomic, |p| {
+            p.rule_in(Rule::dot_separated_build_identifiers, !atomic, |p| {
+                p.dot_separated(atomic)
+            })
+        })
+    }
+
+    // dot_separated_pre_release_identifiers = _{ pre_release_identifier ~ ("." ~ pre_release_identifier)* }
+    // dot_separated_build_identifiers       =  { alphanumeric_identifier ~ ("." ~ alphanumeric_identifier)* }
+    //
+    // `pre_release_identifier` falls back to `numeric_identifier` only where
+    // `alphanumeric_identifier`, which also starts with a digit, does not match.
+    fn dot_separated(&mut self, atomic: bool) -> Option<()> {
+        self.alphanumeric_identifier(atomic)?;
+        self.separate(atomic);
+        let next = |p: &mut Self| {
+            p.expect(".")?;
+            p.separate(atomic);
+            p.alphanumeric_identifier(atomic)
+        };
+        if atomic {
+            while self.attempt(next).is_some() {}
+        } else {
+            self.repeat(next);
+        }
+        Some(())
+    }
+
+    // alphanumeric_identifier = _{ non_digit ~ identifier_characters? | identifier_characters ~ non_digit?
+    //                            | identifier_characters ~ non_digit ~ identifier_characters }
+    //
+    // The third alternative can only match where the second one does.
+    fn alphanumeric_identifier(&mut self, atomic: bool) -> Option<()> {
+        if atomic {
+            return (self.bytes_while(is_identifier_char) > 0).then_some(());
+        }
+        if self
+            .byte(|byte| byte.is_ascii_alphabetic() || byte == b'-')
+            .is_some()
+        {
+            self.skip();
+            self.attempt(|p| p.repeat1(|p| p.byte(is_identifier_char)));
+            return Some(());
+        }
+        self.repeat1(|p| p.byte(is_identifier_char))?;
+        self.skip();
+        Some(())
+    }
+
+    // version_requirement = ${ "\"" ~ requirement ~ "\"" }
+    fn version_requirement(&mut self) -> Option<&'a str> {
+        self.rule(Rule::version_requirement, |p| {
+            p.expect("\"")?;
+            let requirement = p.requirement()?;
+            p.expect("\"")?;
+            Some(requirement)
+        })
+    }
+
+    // requirement = @{ comparator ~ (" "* ~ "," ~ " "* ~ comparator)* }
+    fn requirement(&mut self) -> Option<&'a str> {
+        self.rule(Rule::requirement, |p| {
+            let start = p.pos;
+            p.comparator()?;
+            while p
+                .attempt(|p| {
+                    p.bytes_while(|byte| byte == b' ');
+                    p.expect(",")?;
+                    p.bytes_while(|byte| byte == b' ');
+                    p.comparator()
+                })
+                .is_some()
+            {}
+            Some(p.slice(start))
+        })
+    }
+
+    // comparator = _{ version_operator? ~ " "* ~ partial_version }
+    fn comparator(&mut self) -> Option<()> {
+        for operator in [">=", "<=", ">", "<", "=", "~", "^"] {
+            if self.eat(operator) {
+                break;
+            }
+        }
+        self.bytes_while(|byte| byte == b' ');
+        self.partial_version()
+    }
+
+    // partial_version = _{ version_part ~ ("." ~ version_part ~ ("." ~ version_part ~
+    //                      ("-" ~ pre_release)? ~ ("+" ~ build)?)?)? }
+    fn partial_version(&mut self) -> Option<()> {
+        self.version_part()?;
+        self.attempt(|p| {
+            p.expect(".")?;
+            p.version_part()?;
+            p.attempt(|p| {
+                p.expect(".")?;
+                p.version_part()?;
+                p.attempt(|p| {
+                    p.expect("-")?;
+                    p.pre_release(true)
+                });
+                p.attempt(|p| {
+                    p.expect("+")?;
+                    p.build(true)
+                });
+                Some(())
+            });
+            Some(())
+        });
+        Some(())
+    }
+
+    // version_part = _{ version_wildcard | numeric_identifier }
+    fn version_part(&mut self) -> Option<()> {
+        if self
+            .byte(|byte| matches!(byte, b'*' | b'x' | b'X'))
+            .is_some()
+        {
+            return Some(());
+        }
+        self.numeric_identifier(true)
+    }
+
+    // string_content = @{ (!NEWLINE ~ !"\"" ~ ANY)+ }
+    fn string_content(&mut self) -> Option<&'a str> {
+        self.rule(Rule::string_content, |p| {
+            let start = p.pos;
+            let length = p.bytes_while(|byte| !matches!(byte, b'\n' | b'\r' | b'"'));
+            (length > 0).then(|| p.slice(start))
+        })
+    }
+
+    // quoted_string = ${ "\"" ~ string_content ~ "\"" }
+    fn quoted_string(&mut self) -> Option<&'a str> {
+        self.rule(Rule::quoted_string, |p| {
+            p.expect("\"")?;
+            let content = p.string_content()?;
+            p.expect("\"")?;
+            Some(content)
+        })
+    }
+
+    // boolean = { "true" | "false" }
+    fn boolean(&mut self) -> Option<bool> {
+        self.rule(Rule::boolean, |p| {
+            if p.eat("true") {
+                Some(true)
+            } else {
+                p.expect("false").map(|_| false)
+            }
+        })
+    }
+
+    // features = { "features" ~ "=" ~ "[" ~ (("\"" ~ key ~ "\"") ~ ","?)+ ~ "]" }
+    fn features(&mut self) -> Option<Vec<&'a str>> {
+        self.rule(Rule::features, |p| {
+            p.expect("features")?;
+            p.skip();
+            p.expect("=")?;
+            p.skip();
+            p.expect("[")?;
+            p.skip();
+            let features = p.repeat1(|p| {
+                p.expect("\"")?;
+                p.skip();
+                let feature = p.key()?;
+                p.skip();
+                p.expect("\"")?;
+                p.skip();
+                p.eat(",");
+                Some(feature)
+            })?;
+            p.skip();
+            p.expect("]")?;
+            Some(features)
+        })
+    }
+
+    /// Matches a `<name> = <value>` field rule of an inline table.
+    fn field<T>(
+        &mut self,
+        rule: Rule,
+        names: &[&str],
+        value: impl FnOnce(&mut Self) -> Option<T>,
+    ) -> Option<T> {
+        self.rule(rule, |p| {
+            names.iter().find(|name| p.eat(name))?;
+            p.skip();
+            p.expect("=")?;
+            p.skip();
+            value(p)
+        })
+    }
+
+    // dependency_spec = { "{" ~ (WHITESPACE? ~ (dependency_version | ... | features) ~ ","?)+ ~ "}" }
+    fn dependency_spec(&mut self) -> Option<Dependency> {
+        self.rule(Rule::dependency_spec, |p| {
+            let mut dependency = Dependency::new(String::new(), DependencyKind::Normal);
+            p.expect("{")?;
+            p.skip();
+            p.repeat1(|p| {
+                p.byte(is_whitespace);
+                p.skip();
+                p.dependency_field(&mut dependency)?;
+                p.skip();
+                p.eat(",");
+                Some(())
+            })?;
+            p.skip();
+            p.expect("}")?;
+            Some(dependency)
+        })
+    }
+
+    /// Matches one of the field rules of `dependency_spec` and sets it on the dependency.
+    fn dependency_field(&mut self, dependency: &mut Dependency) -> Option<()> {
+        let strings = [
+            (Rule::dependency_git, "git"),
+            (Rule::dependency_branch, "branch"),
+            (Rule::dependency_tag, "tag"),
+            (Rule::dependency_rev, "rev"),
+            (Rule::dependency_path, "path"),
+            (Rule::dependency_registry, "registry"),
+            (Rule::dependency_package, "package"),
+        ];
+
+        if let Some(version) = self.field(Rule::dependency_version, &["version"], |p| {
+            p.version_requirement()
+        }) {
+            dependency.version = Some(version.to_string());
+            return Some(());
+        }
+        for (rule, name) in strings {
+            if let Some(value) = self.field(rule, &[name], |p| p.quoted_string()) {
+                let value = Some(value.to_string());
+                match rule {
+                    Rule::dependency_git => dependency.git = value,
+                    Rule::dependency_branch => dependency.branch = value,
+                    Rule::dependency_tag => dependency.tag = value,
+                    Rule::dependency_rev => dependency.rev = value,
+                    Rule::dependency_path => dependency.path = value,
+                    Rule::dependency_registry => dependency.registry = value,
+                    _ => dependency.package = value,
+                }
+                return Some(());
+            }
+        }
+        if self
+            .field(Rule::dependency_workspace, &["workspace"], |p| {
+                p.expect("true")
+            })
+            .is_some()
+        {
+            dependency.workspace = true;
+            return Some(());
+        }
+        if let Some(optional) =
+            self.field(Rule::dependency_optional, &["optional"], |p| p.boolean())
+        {
+            dependency.optional = optional;
+            return Some(());
+        }
+        if let Some(default_features) = self.field(
+            Rule::dependency_default_features,
+            &["default-features", "default_features"],
+            |p| p.boolean(),
+        ) {
+            dependency.default_features = default_features;
+            return Some(());
+        }
+        let features = self.features()?;
+        dependency.features = features.into_iter().map(str::to_string).collect();
+        Some(())
+    }
+
+    // dependencies_key_value = { key ~ "=" ~ (dependency_spec | version_requirement) }
+    fn dependencies_key_value(&mut self) -> Option<(Entry<'a>, Dependency)> {
+        self.rule(Rule::dependencies_key_value, |p| {
+            let start = p.pos;
+            let key = p.key()?;
+            p.skip();
+            p.expect("=")?;
+            p.skip();
+            let value_start = p.pos;
+            let mut dependency = match p.dependency_spec() {
+                Some(dependency) => dependency,
+                None => {
+                    let version = p.version_requirement()?;
+                    let mut dependency = Dependency::new(String::new(), DependencyKind::Normal);
+                    dependency.version = Some(version.to_string());
+                    dependency
+                }
+            };
+            dependency.name = key.to_string();
+            let entry = Entry {
+                key,
+                value: p.slice(value_start),
+                start,
+                end: p.pos,
+            };
+            Some((entry, dependency))
+        })
+    }
+
+    // table_key = @{ "'" ~ (!"'" ~ !NEWLINE ~ ANY)+ ~ "'"
+    //              | "\"" ~ ("\\\"" | !"\"" ~ !NEWLINE ~ ANY)+ ~ "\""
+    //              | (ASCII_ALPHANUMERIC | "_" | "-")+ }
+    fn table_key(&mut self) -> Option<&'a str> {
+        self.rule(Rule::table_key, |p| {
+            let start = p.pos;
+            let literal = p.attempt(|p| {
+                p.expect("'")?;
+                let length = p.bytes_while(|byte| !matches!(byte, b'\'' | b'\n' | b'\r'));
+                (length > 0).then_some(())?;
+                p.expect("'")
+            });
+            let mut basic = || {
+                p.attempt(|p| {
+                    p.expect("\"")?;
+                    let content = p.pos;
+                    loop {
+                        if !p.eat("\\\"")
+                            && p.byte(|byte| !matches!(byte, b'"' | b'\n' | b'\r'))
+                                .is_none()
+                        {
+                            break;
+                        }
+                    }
+                    (p.pos > content).then_some(())?;
+                    p.expect("\"")
+                })
+            };
+            if literal.is_some() || basic().is_some() || p.bytes_while(is_key_char) > 0 {
+                Some(p.slice(start))
+            } else {
+                None
+            }
+        })
+    }
+
+    // dependency_table_kind = @{ "dependencies" | "dev-dependencies" | "build-dependencies" }
+    fn dependency_table_kind(&mut self) -> Option<&'a str> {
+        self.rule(Rule::dependency_table_kind, |p| {
+            let start = p.pos;
+            ["dependencies", "dev-dependencies", "build-dependencies"]
+                .iter()
+                .find(|kind| p.eat(kind))?;
+            Some(p.slice(start))
+        })
+    }
+
+    // dependencies_table = ${ ("target." ~ table_key ~ ".")? ~ dependency_table_kind }
+    fn dependencies_table(&mut self) -> Option<(&'a str, Option<&'a str>, &'a str)> {
+        self.rule(Rule::dependencies_table, |p| {
+            let start = p.pos;
+            let target = p.attempt(|p| {
+                p.expect("target.")?;
+                let platform = p.table_key()?;
+                p.expect(".")?;
+                Some(platform)
+            });
+            let kind = p.dependency_table_kind()?;
+            Some((p.slice(start), target, kind))
+        })
+    }
+
+    // dependencies_section = { dependencies_header ~ dependencies_key_value* }
+    // dependencies_header  = { "[" ~ dependencies_table ~ "]" }
+    fn dependencies_section(&mut self) -> Option<Item<'a>> {
+        self.rule(Rule::dependencies_section, |p| {
+            let start = p.pos;
+            let (table, target, kind) = p.rule(Rule::dependencies_header, |p| {
+                p.expect("[")?;
+                p.skip();
+                let table = p.dependencies_table()?;
+                p.skip();
+                p.expect("]")?;
+                Some(table)
+            })?;
+            p.skip();
+            let entries = p.repeat(|p| p.dependencies_key_value());
+            Some(Item::Dependencies {
+                span: (start, p.pos),
+                table,
+                target,
+                kind,
+                entries,
+            })
+        })
+    }
+
+    // patch_section = { patch_header ~ dependencies_key_value* }
+    // patch_header  = { "[" ~ patch_table ~ "]" }
+    // patch_table   = ${ "patch." ~ table_key }
+    fn patch_section(&mut self) -> Option<Item<'a>> {
+        self.rule(Rule::patch_section, |p| {
+            let start = p.pos;
+            let (table, source) = p.rule(Rule::patch_header, |p| {
+                p.expect("[")?;
+                p.skip();
+                let table = p.rule(Rule::patch_table, |p| {
+                    let start = p.pos;
+                    p.expect("patch.")?;
+                    let source = p.table_key()?;
+                    Some((p.slice(start), source))
+                })?;
+                p.skip();
+                p.expect("]")?;
+                Some(table)
+            })?;
+            p.skip();
+            let entries = p.repeat(|p| p.dependencies_key_value());
+            Some(Item::Patch {
+                span: (start, p.pos),
+                table,
+                source,
+                entries,
+            })
+        })
+    }
+
+    // replace_section = { "[" ~ "replace" ~ "]" ~ replace_key_value* }
+    fn replace_section(&mut self) -> Option<Item<'a>> {
+        self.rule(Rule::replace_section, |p| {
+            let start = p.pos;
+            p.expect("[")?;
+            p.skip();
+            p.expect("replace")?;
+            p.skip();
+            p.expect("]")?;
+            p.skip();
+            let entries = p.repeat(|p| p.replace_key_value());
+            Some(Item::Replace {
+                span: (start, p.pos),
+                entries,
+            })
+        })
+    }
+
+    // replace_key_value = { package_id ~ "=" ~ dependency_spec }
+    // package_id        = ${ "\"" ~ string_content ~ "\"" }
+    fn replace_key_value(&mut self) -> Option<(Entry<'a>, Dependency)> {
+        self.rule(Rule::replace_key_value, |p| {
+            let start = p.pos;
+            let key = p.rule(Rule::package_id, |p| {
+                p.expect("\"")?;
+                let id = p.string_content()?;
+                p.expect("\"")?;
+                Some(id)
+            })?;
+            p.skip();
+            p.expect("=")?;
+            p.skip();
+            let value_start = p.pos;
+            let dependency = p.dependency_spec()?;
+            let entry = Entry {
+                key,
+                value: p.slice(value_start),
+                start,
+                end: p.pos,
+            };
+            Some((entry, dependency))
+        })
+    }
+
+    // lints_section = { lints_header ~ lint_key_value* }
+    // lints_header  = { "[" ~ lints_table ~ "]" }
+    // lints_table   = ${ "workspace."? ~ "lints." ~ key }
+    fn lints_section(&mut self) -> Option<Item<'a>> {
+        self.rule(Rule::lints_section, |p| {
+            let start = p.pos;
+            let (table, tool) = p.rule(Rule::lints_header, |p| {
+                p.expect("[")?;
+                p.skip();
+                let table = p.rule(Rule::lints_table, |p| {
+                    let start = p.pos;
+                    p.eat("workspace.");
+                    p.expect("lints.")?;
+                    let tool = p.key()?;
+                    Some((p.slice(start), tool))
+                })?;
+                p.skip();
+                p.expect("]")?;
+                Some(table)
+            })?;
+            p.skip();
+            let entries = p.repeat(|p| p.lint_key_value());
+            Some(Item::Lints {
+                span: (start, p.pos),
+                table,
+                tool,
+                entries,
+            })
+        })
+    }
+
+    // lint_key_value = { key ~ "=" ~ (lint_spec | quoted_string) }
+    fn lint_key_value(&mut self) -> Option<(Entry<'a>, Vec<LintField<'a>>)> {
+        self.rule(Rule::lint_key_value, |p| {
+            let start = p.pos;
+            let key = p.key()?;
+            p.skip();
+            p.expect("=")?;
+            p.skip();
+            let value_start = p.pos;
+            let fields = match p.lint_spec() {
+                Some(fields) => fields,
+                None => vec![LintField::Level(p.quoted_string()?)],
+            };
+            let entry = Entry {
+                key,
+                value: p.slice(value_start),
+                start,
+                end: p.pos,
+            };
+            Some((entry, fields))
+        })
+    }
+
+    // lint_spec = { "{" ~ ((lint_level | lint_priority | lint_check_cfg) ~ ","?)+ ~ "}" }
+    fn lint_spec(&mut self) -> Option<Vec<LintField<'a>>> {
+        self.rule(Rule::lint_spec, |p| {
+            p.expect("{")?;
+            p.skip();
+            let fields = p.repeat1(|p| {
+                let field = p.lint_field()?;
+                p.skip();
+                p.eat(",");
+                Some(field)
+            })?;
+            p.skip();
+            p.expect("}")?;
+            Some(fields)
+        })
+    }
+
+    /// Matches one of the field rules of `lint_spec`.
+    fn lint_field(&mut self) -> Option<LintField<'a>> {
+        if let Some(level) = self.field(Rule::lint_level, &["level"], |p| p.quoted_string()) {
+            return Some(LintField::Level(level));
+        }
+        if let Some(priority) = self.field(Rule::lint_priority, &["priority"], |p| {
+            // integer = @{ "-"? ~ ASCII_DIGIT+ }
+            p.rule(Rule::integer, |p| {
+                let start = p.pos;
+                p.eat("-");
+                (p.bytes_while(|byte| byte.is_ascii_digit()) > 0).then(|| p.slice(start))
+            })
+        }) {
+            return Some(LintField::Priority(priority));
+        }
+        // lint_check_cfg = { "check-cfg" ~ "=" ~ "[" ~ ((quoted_string | literal_string) ~ ","?)* ~ "]" }
+        let values = self.field(Rule::lint_check_cfg, &["check-cfg"], |p| {
+            p.expect("[")?;
+            p.skip();
+            let values = p.repeat(|p| {
+                let value = p.quoted_string().or_else(|| p.literal_string())?;
+                p.skip();
+                p.eat(",");
+                Some(value)
+            });
+            p.skip();
+            p.expect("]")?;
+            Some(values)
+        })?;
+        Some(LintField::CheckCfg(values))
+    }
+
+    // literal_string  = ${ "'" ~ literal_content ~ "'" }
+    // literal_content = @{ (!NEWLINE ~ !"'" ~ ANY)* }
+    fn literal_string(&mut self) -> Option<&'a str> {
+        self.rule(Rule::literal_string, |p| {
+            p.expect("'")?;
+            let start = p.pos;
+            p.bytes_while(|byte| !matches!(byte, b'\n' | b'\r' | b'\''));
+            let content = p.slice(start);
+            p.expect("'")?;
+            Some(content)
+        })
+    }
+
+    // key = @{ (ASCII_ALPHANUMERIC | "_" | "-")+ }
+    fn key(&mut self) -> Option<&'a str> {
+        self.rule(Rule::key, |p| {
+            let start = p.pos;
+            (p.bytes_while(is_key_char) > 0).then(|| p.slice(start))
+        })
+    }
+
+    // value = @{ (!NEWLINE ~ WHITESPACE* ~ possible_value_char)+ ~ WHITESPACE* }
+    fn value(&mut self) -> Option<&'a str> {
+        self.rule(Rule::value, |p| {
+            let start = p.pos;
+            let mut matched = false;
+            while p
+                .bytes
+                .get(p.pos)
+                .is_some_and(|&byte| byte != b'\n' && byte != b'\r')
+            {
+                let next = p.pos;
+                p.bytes_while(is_whitespace);
+                if p.byte(is_value_char).is_none() {
+                    p.pos = next;
+                    break;
+                }
+                matched = true;
+            }
+            if !matched {
+                return None;
+            }
+            p.bytes_while(is_whitespace);
+            Some(p.slice(start))
+        })
+    }
+
+    // key_value = { key ~ "=" ~ value }
+    fn key_value(&mut self) -> Option<Entry<'a>> {
+        self.rule(Rule::key_value, |p| {
+            let start = p.pos;
+            let key = p.key()?;
+            p.skip();
+            p.expect("=")?;
+            p.skip();
+            let value = p.value()?;
+            Some(Entry {
+                key,
+                value: value.trim(),
+                start,
+                end: p.pos,
+            })
+        })
+    }
+
+    // section = { section_definition ~ section_inside }
+    fn section(&mut self) -> Option<Item<'a>> {
+        self.rule(Rule::section, |p| {
+            let start = p.pos;
+            let header = p.section_definition()?;
+            p.skip();
+            let entries = p.section_inside();
+            Some(Item::Section {
+                span: (start, p.pos),
+                header,
+                entries,
+            })
+        })
+    }
+
+    // section_inside = { key_value* }
+    fn section_inside(&mut self) -> Vec<Entry<'a>> {
+        self.repeat(|p| p.key_value())
+    }
+
+    // section_definition = { "[" ~ "["? ~ table_name ~ "]" ~ "]"? }
+    fn section_definition(&mut self) -> Option<&'a str> {
+        self.rule(Rule::section_definition, |p| {
+            let start = p.pos;
+            p.expect("[")?;
+            p.skip();
+            p.eat("[");
+            p.skip();
+            p.table_name()?;
+            p.skip();
+            p.expect("]")?;
+            p.skip();
+            p.eat("]");
+            Some(p.slice(start))
+        })
+    }
+
+    // table_name = ${ (section_name | "package" ~ &".") ~ ("." ~ table_key)* }
+    fn table_name(&mut self) -> Option<()> {
+        self.rule(Rule::table_name, |p| {
+            if p.section_name().is_none() {
+                p.expect("package")?;
+                (p.bytes.get(p.pos) == Some(&b'.')).then_some(())?;
+            }
+            while p
+                .attempt(|p| {
+                    p.expect(".")?;
+                    p.table_key()
+                })
+                .is_some()
+            {}
+            Some(())
+        })
+    }
+
+    // section_name = @{ "lib" | "bin" | "example" | ... | "workspace" }
+    fn section_name(&mut self) -> Option<()> {
+        const NAMES: [&str; 14] = [
+            "lib",
+            "bin",
+            "example",
+            "test",
+            "bench",
+            "build-dependencies",
+            "target",
+            "badges",
+            "features",
+            "lints",
+            "patch",
+            "replace",
+            "profile",
+            "workspace",
+        ];
+        self.rule(Rule::section_name, |p| {
+            NAMES.iter().find(|name| p.eat(name)).map(|_| ())
+        })
+    }
+}
@@ -1,11 +1,61 @@
 #![doc = include_str!("../docs.md")]
 //! A parser for manifest files using Pest grammar.
 
+use indexmap::IndexMap;
 use pest::Parser;
 use pest_derive::Parser;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+pub mod borrowed;
+pub mod cache;
+pub mod cfg;
+pub mod dependency;
+pub mod deserialize;
+pub mod diagnostic;
+pub mod diff;
+pub mod edit;
+pub mod ffi;
+pub mod format;
+pub mod formatter;
+pub mod graph;
+mod handwritten;
+pub mod lint;
+pub mod lints;
+pub mod lockfile;
+pub mod lsp;
+pub mod merge;
+pub mod patch;
+pub mod query;
+pub mod report;
+pub mod tree;
+pub mod watch;
+
+pub use borrowed::BorrowedManifest;
+pub use cache::ManifestCache;
+pub use cfg::{CfgExpr, CfgSet, Platform};
+pub use dependency::{Dependency, DependencyKind};
+pub use diagnostic::{apply_fixes, Diagnostic, Edit, Fix};
+pub use diff::Change;
+pub use edit::{add_dependency, remove_dependency, set_value, unified_diff, unset_value};
+pub use format::{
+    CargoFormat, DependencySource, Formats, ManifestFormat, Package, PackageDependency,
+};
+pub use formatter::{format_manifest, is_formatted};
+pub use graph::DependencyGraph;
+pub use lint::{LintContext, LintRule, Linter};
+pub use lints::{lint_args, Lint, LintLevel};
+pub use lockfile::{LockIssue, LockedPackage, Lockfile};
+pub use lsp::serve;
+pub use merge::{merge_manifests, Conflict, Merge};
+pub use patch::{
+    apply_patches, Override, PatchEntry, PatchReport, PatchedDependency, ReplaceEntry,
+};
+pub use query::{QueryMatch, Value};
+pub use report::{to_github_annotations, to_sarif};
+pub use tree::{render_tree, TreeOptions};
+pub use watch::{workspace_members, Watcher};
+
 /// The main parser for manifest files.
 /// This parser reads and validates manifest files that define sections
 /// including package information and dependencies.
@@ -30,13 +80,125 @@ pub enum ManifestError {
     /// Indicates a missing key within a section
     #[error("Missing key {1} in section {0}")]
     MissingKey(String, String),
+
+    /// Indicates a value that is not allowed for a key
+    #[error("Invalid value for {1} in section {0}: {2}")]
+    InvalidValue(String, String, String),
+
+    /// Indicates a package that is not a member of the workspace
+    #[error("Missing workspace member: {0}")]
+    MissingMember(String),
+
+    /// Indicates a dependency cycle between workspace members
+    #[error("Dependency cycle: {}", .0.join(" -> "))]
+    DependencyCycle(Vec<String>),
+
+    /// Indicates a lint rule id that no rule of a linter has
+    #[error("Unknown lint rule: {0}")]
+    UnknownRule(String),
+
+    /// Indicates a package that is not in a dependency tree
+    #[error("Unknown package: {0}")]
+    UnknownPackage(String),
+
+    /// Indicates a query path that is not well formed
+    #[error("Invalid query {0}: {1}")]
+    InvalidQuery(String, String),
+
+    /// Indicates a language server client that broke the protocol or went away
+    #[error("Language server protocol error: {0}")]
+    Protocol(String),
+
+    /// Indicates a value that does not fit the type it is deserialized into,
+    /// with its path and, if known, the location of its entry or table
+    #[error("Cannot deserialize {0}: {1}{at}", at = location(.2))]
+    Deserialize(String, String, Option<Span>),
+
+    /// Indicates a file of a parse cache that cannot be read or written
+    #[error("Cache error: {0}")]
+    Cache(String),
+
+    /// Indicates an argument passed to the C API that is `NULL` or not UTF-8
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+
+    /// Indicates a manifest format id or file name that no format has
+    #[error("Unknown manifest format: {0}")]
+    UnknownFormat(String),
+}
+
+/// The parser implementation `Manifest::parse_with` reads a manifest with.
+///
+/// Both accept the same manifests, build the same `Manifest` and report errors
+/// at the same position with the same message.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Backend {
+    /// The parser generated from `grammar.pest`
+    #[default]
+    Pest,
+    /// A hand-written recursive descent parser, faster on large manifests
+    Handwritten,
 }
 
 /// Represents a parsed manifest containing sections of key-value pairs.
-#[derive(Debug, Default)]
+///
+/// Sections and their keys are kept in the order they first appear in the source.
+#[derive(Debug, Default, PartialEq)]
 pub struct Manifest {
     /// Map of section names to their key-value pairs
-    sections: HashMap<String, HashMap<String, String>>,
+    sections: IndexMap<String, IndexMap<String, String>>,
+    /// Map of section names to their key-value pairs as written, with quotes kept
+    raw_values: IndexMap<String, IndexMap<String, String>>,
+    /// Key-value pairs as written of every table of an array of tables such as
+    /// `[[bin]]`, in source order
+    array_tables: IndexMap<String, Vec<IndexMap<String, String>>>,
+    /// Typed entries of all dependency tables, in declaration order
+    dependencies: Vec<Dependency>,
+    /// Entries of all `[patch.<source>]` and `[patch.<source>.<name>]` tables, in declaration order
+    patches: Vec<PatchEntry>,
+    /// Entries of the `[replace]` table, in declaration order
+    replacements: Vec<ReplaceEntry>,
+    /// Entries of the `[lints.<tool>]` tables
+    lints: Vec<Lint>,
+    /// Entries of the `[workspace.lints.<tool>]` tables
+    workspace_lints: Vec<Lint>,
+    /// Location of every section in the source
+    section_spans: IndexMap<String, Span>,
+    /// Location of every `key = value` entry in the source, per section
+    key_spans: IndexMap<String, IndexMap<String, Span>>,
+}
+
+/// A location in the manifest source.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Span {
+    /// Byte offset of the first character
+    pub start: usize,
+    /// Byte offset after the last character
+    pub end: usize,
+    /// 1-based line of the first character
+    pub line: usize,
+    /// 1-based column of the first character
+    pub column: usize,
+}
+
+/// Describes where an error is, e.g. ` (line 3, column 1)`, or nothing if it is unknown.
+fn location(span: &Option<Span>) -> String {
+    span.map_or(String::new(), |span| {
+        format!(" (line {}, column {})", span.line, span.column)
+    })
+}
+
+impl Span {
+    /// Converts a pest span into a `Span`.
+    pub(crate) fn from_pest(span: pest::Span) -> Self {
+        let (line, column) = span.start_pos().line_col();
+        Span {
+            start: span.start(),
+            end: span.end(),
+            line,
+            column,
+        }
+    }
 }
 
 impl Manifest {
@@ -50,18 +212,34 @@ impl Manifest {
     ///
     /// A `Result` containing either a parsed `Manifest` or a `ManifestError`
     pub fn parse(input: &str) -> Result<Self, ManifestError> {
-        let mut manifest = Manifest::default();
         let parsed_item = ManifestParser::parse(Rule::manifest, input)
             .map_err(|e| ManifestError::ParseError(e.to_string()))?;
+        Manifest::from_pairs(parsed_item)
+    }
+
+    /// Builds a manifest from the parse tree of its source.
+    ///
+    /// # Arguments
+    ///
+    /// * `parsed_item` - The pairs of a `Rule::manifest` parse
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing either the `Manifest` or a `ManifestError` if a typed
+    /// section does not check out
+    pub(crate) fn from_pairs(
+        parsed_item: pest::iterators::Pairs<Rule>,
+    ) -> Result<Self, ManifestError> {
+        let mut manifest = Manifest::default();
         let mut current_section = None;
 
         for item in parsed_item.flatten() {
             match item.as_rule() {
                 Rule::section => {
+                    let span = Span::from_pest(item.as_span());
+                    let array = item.as_str().trim_start().starts_with("[[");
                     current_section = parse_section(item);
-                    manifest
-                        .sections
-                        .insert(current_section.clone().unwrap(), HashMap::new());
+                    manifest.replace_section(current_section.as_deref().unwrap(), span, array);
                 }
                 Rule::key_value => {
                     parse_key_value(item, &mut manifest, &current_section)?;
@@ -72,22 +250,80 @@ impl Manifest {
                 Rule::dependencies_section => {
                     parse_dependencies_section(item, &mut manifest)?;
                 }
+                Rule::patch_section => {
+                    parse_patch_section(item, &mut manifest)?;
+                }
+                Rule::replace_section => {
+                    parse_replace_section(item, &mut manifest)?;
+                }
+                Rule::lints_section => {
+                    parse_lints_section(item, &mut manifest)?;
+                }
                 _ => {}
             }
         }
 
+        manifest.collect_patch_tables()?;
         Ok(manifest)
     }
 
+    /// Parses a manifest string with a chosen parser implementation.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The manifest content as a string
+    /// * `backend` - The parser to use
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing either a parsed `Manifest` or a `ManifestError`
+    pub fn parse_with(input: &str, backend: Backend) -> Result<Self, ManifestError> {
+        match backend {
+            Backend::Pest => Manifest::parse(input),
+            Backend::Handwritten => handwritten::parse(input),
+        }
+    }
+
     /// Retrieves an iterator over the section names in the manifest.
     ///
     /// # Returns
     ///
-    /// An iterator yielding references to the section names as strings.
+    /// An iterator yielding references to the section names as strings, in the
+    /// order the sections first appear in the source.
     pub fn sections(&self) -> impl Iterator<Item = &String> {
         self.sections.keys()
     }
 
+    /// Retrieves an iterator over the sections of the manifest along with their key-value pairs.
+    ///
+    /// # Returns
+    ///
+    /// An iterator yielding each section name and its map of key-value pairs, with
+    /// sections and keys in the order they first appear in the source.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &IndexMap<String, String>)> {
+        self.sections.iter()
+    }
+
+    /// Retrieves an iterator over the key-value pairs of a section.
+    ///
+    /// # Arguments
+    ///
+    /// * `section` - The section name
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing either an iterator yielding the keys and values in the
+    /// order they appear in the source, or a `ManifestError`
+    pub fn entries(
+        &self,
+        section: &str,
+    ) -> Result<impl Iterator<Item = (&str, &str)>, ManifestError> {
+        let section_map = self.get_by_section(section)?;
+        Ok(section_map
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str())))
+    }
+
     /// Retrieves a value from the manifest given a section and key.
     ///
     /// # Arguments
@@ -119,8 +355,11 @@ impl Manifest {
     ///
     /// # Returns
     ///
-    /// A `Result` containing either the section map or a `ManifestError`
-    pub fn get_by_section(&self, section: &str) -> Result<&HashMap<String, String>, ManifestError> {
+    /// A `Result` containing either the section map, with keys in source order, or a `ManifestError`
+    pub fn get_by_section(
+        &self,
+        section: &str,
+    ) -> Result<&IndexMap<String, String>, ManifestError> {
         let section_map = self
             .sections
             .get(section)
@@ -128,21 +367,208 @@ impl Manifest {
 
         Ok(section_map)
     }
+
+    /// Retrieves an iterator over the typed dependencies of every kind.
+    ///
+    /// # Returns
+    ///
+    /// An iterator yielding the dependencies in declaration order.
+    pub fn dependencies(&self) -> impl Iterator<Item = &Dependency> {
+        self.dependencies.iter()
+    }
+
+    /// Retrieves an iterator over the entries of every `[patch.<source>]` table.
+    pub fn patches(&self) -> impl Iterator<Item = &PatchEntry> {
+        self.patches.iter()
+    }
+
+    /// Retrieves an iterator over the entries of the `[replace]` table.
+    pub fn replacements(&self) -> impl Iterator<Item = &ReplaceEntry> {
+        self.replacements.iter()
+    }
+
+    /// Retrieves an iterator over the lints of the `[lints.<tool>]` tables.
+    pub fn lints(&self) -> impl Iterator<Item = &Lint> {
+        self.lints.iter()
+    }
+
+    /// Retrieves an iterator over the lints of the `[workspace.lints.<tool>]` tables.
+    pub fn workspace_lints(&self) -> impl Iterator<Item = &Lint> {
+        self.workspace_lints.iter()
+    }
+
+    /// Checks whether the package inherits its lints with `[lints] workspace = true`.
+    pub fn inherits_workspace_lints(&self) -> bool {
+        matches!(self.get_by_key("lints", "workspace"), Ok("true"))
+    }
+
+    /// Retrieves the dependencies that apply to a target, i.e. every
+    /// dependency outside `[target.*]` tables plus those whose platform matches.
+    ///
+    /// # Arguments
+    ///
+    /// * `cfg` - The cfg values of the target, e.g. from `CfgSet::for_target`
+    ///
+    /// # Returns
+    ///
+    /// An iterator yielding the applicable dependencies in declaration order.
+    pub fn dependencies_for<'a>(
+        &'a self,
+        cfg: &'a CfgSet,
+    ) -> impl Iterator<Item = &'a Dependency> + 'a {
+        self.dependencies
+            .iter()
+            .filter(move |dependency| dependency.applies_to(cfg))
+    }
+
+    /// Retrieves the location of a section in the source.
+    ///
+    /// # Arguments
+    ///
+    /// * `section` - The section name
+    ///
+    /// # Returns
+    ///
+    /// The span of the whole section starting at its header, or `None` if the section does not exist
+    pub fn section_span(&self, section: &str) -> Option<Span> {
+        self.section_spans.get(section).copied()
+    }
+
+    /// Retrieves the location of a `key = value` entry in the source.
+    ///
+    /// # Arguments
+    ///
+    /// * `section` - The section name
+    /// * `key` - The key within the section
+    ///
+    /// # Returns
+    ///
+    /// The span of the entry starting at its key, or `None` if the key does not exist
+    pub fn key_span(&self, section: &str, key: &str) -> Option<Span> {
+        self.key_spans.get(section)?.get(key).copied()
+    }
+
+    /// Retrieves the name of the package described by the manifest.
+    pub fn package_name(&self) -> &str {
+        self.get_by_key("package", "name").unwrap_or_default()
+    }
+
+    /// Starts a generic section, replacing an earlier section of the same name
+    /// such as a previous `[[bin]]` while keeping its place in the order of sections.
+    ///
+    /// The tables of an array of tables, given by a `[[header]]`, are also kept
+    /// one by one for `Manifest::to_value`.
+    fn replace_section(&mut self, section: &str, span: Span, array: bool) {
+        if array {
+            self.array_tables
+                .entry(section.to_string())
+                .or_default()
+                .push(IndexMap::new());
+        }
+        self.sections.insert(section.to_string(), IndexMap::new());
+        self.raw_values.insert(section.to_string(), IndexMap::new());
+        self.key_spans.insert(section.to_string(), IndexMap::new());
+        self.section_spans.insert(section.to_string(), span);
+    }
+
+    /// Adds a patch for every `[patch.<source>.<name>]` table, whose keys are the
+    /// fields of the replacement, and puts all patches in declaration order.
+    ///
+    /// Both parser backends read such tables as generic sections, so this runs
+    /// once the whole manifest has been read.
+    fn collect_patch_tables(&mut self) -> Result<(), ManifestError> {
+        let declared = std::mem::take(&mut self.patches);
+        for (section, entries) in &self.raw_values {
+            match query::split_table_name(section).as_slice() {
+                [patch, _] if patch == "patch" => self.patches.extend(
+                    declared
+                        .iter()
+                        .filter(|entry| entry.replacement.table == *section)
+                        .cloned(),
+                ),
+                [patch, source, name] if patch == "patch" => {
+                    let fields = entries
+                        .iter()
+                        .map(|(key, value)| format!("{} = {}", key, value));
+                    let mut replacement = patch_table(section, name, fields)?;
+                    replacement.table = section.clone();
+                    self.patches.push(PatchEntry {
+                        source: source.clone(),
+                        replacement,
+                    });
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates a section if it does not exist yet and records its location.
+    fn open_section(&mut self, section: &str, span: Span) {
+        self.sections.entry(section.to_string()).or_default();
+        self.key_spans.entry(section.to_string()).or_default();
+        self.section_spans
+            .entry(section.to_string())
+            .or_insert(span);
+    }
+
+    /// Stores a `key = value` entry of a section along with its location.
+    ///
+    /// The value is given as written; the unquoted form is what `get_by_key` returns.
+    fn insert_entry(&mut self, section: &str, key: &str, value: &str, span: Span) {
+        if let Some(section_map) = self.sections.get_mut(section) {
+            section_map.insert(key.to_string(), value.trim_matches('"').to_string());
+            self.raw_values
+                .entry(section.to_string())
+                .or_default()
+                .insert(key.to_string(), value.to_string());
+            if let Some(table) = self
+                .array_tables
+                .get_mut(section)
+                .and_then(|tables| tables.last_mut())
+            {
+                table.insert(key.to_string(), value.to_string());
+            }
+            self.key_spans
+                .entry(section.to_string())
+                .or_default()
+                .insert(key.to_string(), span);
+        }
+    }
+}
+
+/// Reads the `key = value` fields of a `[patch.<source>.<name>]` table as the
+/// inline table of the replacement they describe.
+pub(crate) fn patch_table(
+    section: &str,
+    name: &str,
+    fields: impl Iterator<Item = String>,
+) -> Result<Dependency, ManifestError> {
+    let spec = format!("{{ {} }}", fields.collect::<Vec<_>>().join(", "));
+    let mut pairs = ManifestParser::parse(Rule::dependency_spec, &spec).map_err(|e| {
+        ManifestError::ParseError(format!("Invalid patch table [{}]: {}", section, e))
+    })?;
+    Ok(Dependency::from_spec(
+        name,
+        pairs.next().unwrap(),
+        DependencyKind::Normal,
+        None,
+    ))
 }
 
 /// Parses a section name from the manifest.
 fn parse_section(item: pest::iterators::Pair<Rule>) -> Option<String> {
     let inner = item.into_inner().next().unwrap();
-    let section_name = inner.as_str().trim().to_string();
-
-    if section_name.starts_with('[') && section_name.ends_with(']') {
-        Some(
-            section_name
-                .trim_matches(|c| c == '[' || c == ']')
-                .to_string(),
-        )
+    Some(header_name(inner.as_str()).to_string())
+}
+
+/// Returns the name of a generic section given its `[name]` or `[[name]]` header.
+fn header_name(header: &str) -> &str {
+    let header = header.trim();
+    if header.starts_with('[') && header.ends_with(']') {
+        header.trim_matches(|c| c == '[' || c == ']')
     } else {
-        Some(section_name)
+        header
     }
 }
 
@@ -152,14 +578,13 @@ fn parse_key_value(
     manifest: &mut Manifest,
     current_section: &Option<String>,
 ) -> Result<(), ManifestError> {
+    let span = Span::from_pest(item.as_span());
     let mut inner = item.into_inner();
     let key = inner.next().unwrap().as_str().trim();
-    let value = inner.next().unwrap().as_str().trim().trim_matches('"');
+    let value = inner.next().unwrap().as_str().trim();
 
     if let Some(section) = current_section {
-        if let Some(section_map) = manifest.sections.get_mut(section) {
-            section_map.insert(key.to_string(), value.to_string());
-        }
+        manifest.insert_entry(section, key, value, span);
     }
 
     Ok(())
@@ -171,53 +596,152 @@ fn parse_package_section(
     manifest: &mut Manifest,
 ) -> Result<(), ManifestError> {
     let section_name = "package".to_string();
-    manifest
-        .sections
-        .insert(section_name.clone(), HashMap::new());
+    manifest.open_section(&section_name, Span::from_pest(item.as_span()));
 
+    for field in item.into_inner() {
+        let span = Span::from_pest(field.as_span());
+        match field.as_rule() {
+            Rule::package_name | Rule::package_version => {
+                let key = if field.as_rule() == Rule::package_name {
+                    "name"
+                } else {
+                    "version"
+                };
+                let value = field.into_inner().next().unwrap().as_str().trim();
+                manifest.insert_entry(&section_name, key, value, span);
+            }
+            Rule::section_inside => {
+                for entry in field.into_inner() {
+                    insert_pair(manifest, &section_name, entry);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses a dependencies section from the manifest.
+fn parse_dependencies_section(
+    item: pest::iterators::Pair<Rule>,
+    manifest: &mut Manifest,
+) -> Result<(), ManifestError> {
+    let section_span = Span::from_pest(item.as_span());
     let mut inner = item.into_inner();
-    let name = inner.next().unwrap().as_str().trim();
-    let version = inner.next().unwrap().as_str().trim();
+    let table = inner.next().unwrap().into_inner().next().unwrap();
+    let section_name = table.as_str().to_string();
 
-    let section_map = manifest.sections.get_mut(&section_name).unwrap();
-    section_map.insert("name".to_string(), name.trim_matches('"').to_string());
-    section_map.insert("version".to_string(), version.trim_matches('"').to_string());
+    let mut target = None;
+    let mut kind = DependencyKind::Normal;
+    for part in table.into_inner() {
+        match part.as_rule() {
+            Rule::table_key => target = Some(Platform::parse(part.as_str())?),
+            Rule::dependency_table_kind => {
+                kind = DependencyKind::from_table(part.as_str()).unwrap_or(kind)
+            }
+            _ => {}
+        }
+    }
+    manifest.open_section(&section_name, section_span);
+
+    for dep in inner {
+        let mut dependency = Dependency::from_pair(dep.clone(), kind, target.clone());
+        dependency.table = section_name.clone();
+        manifest.dependencies.push(dependency);
+        insert_pair(manifest, &section_name, dep);
+    }
 
-    //Check if there are items left
-    let inner = inner.next().unwrap().into_inner();
+    Ok(())
+}
 
-    for item in inner {
-        let mut inner = item.into_inner();
-        let key = inner.next().unwrap().as_str().trim();
-        let value = inner.next().unwrap().as_str().trim().trim_matches('"');
-        section_map.insert(key.to_string(), value.to_string());
+/// Parses a `[patch.<source>]` section from the manifest.
+fn parse_patch_section(
+    item: pest::iterators::Pair<Rule>,
+    manifest: &mut Manifest,
+) -> Result<(), ManifestError> {
+    let section_span = Span::from_pest(item.as_span());
+    let mut inner = item.into_inner();
+    let table = inner.next().unwrap().into_inner().next().unwrap();
+    let section_name = table.as_str().to_string();
+    let source = cfg::unquote_key(table.into_inner().next().unwrap().as_str());
+    manifest.open_section(&section_name, section_span);
+
+    for dep in inner {
+        let mut replacement = Dependency::from_pair(dep.clone(), DependencyKind::Normal, None);
+        replacement.table = section_name.clone();
+        manifest.patches.push(PatchEntry {
+            source: source.clone(),
+            replacement,
+        });
+        insert_pair(manifest, &section_name, dep);
     }
 
     Ok(())
 }
 
-/// Parses a dependencies section from the manifest.
-fn parse_dependencies_section(
+/// Parses the `[replace]` section from the manifest.
+fn parse_replace_section(
     item: pest::iterators::Pair<Rule>,
     manifest: &mut Manifest,
 ) -> Result<(), ManifestError> {
-    let mut section_name = "dependencies".to_string();
+    let section_name = "replace".to_string();
+    manifest.open_section(&section_name, Span::from_pest(item.as_span()));
+
+    for entry in item.into_inner() {
+        let span = Span::from_pest(entry.as_span());
+        let mut inner = entry.into_inner();
+        let key = inner.next().unwrap().into_inner().next().unwrap().as_str();
+        let spec = inner.next().unwrap();
+
+        let (package, version) = key
+            .split_once(':')
+            .ok_or_else(|| ManifestError::ParseError(format!("Invalid package id {}", key)))?;
+        let mut replacement =
+            Dependency::from_spec(package, spec.clone(), DependencyKind::Normal, None);
+        replacement.table = section_name.clone();
+        manifest.replacements.push(ReplaceEntry {
+            package: package.to_string(),
+            version: version.to_string(),
+            replacement,
+        });
 
-    if manifest.sections.contains_key(&section_name) {
-        section_name = "dev-dependencies".to_string();
+        manifest.insert_entry(&section_name, key, spec.as_str().trim(), span);
     }
-    manifest
-        .sections
-        .insert(section_name.clone(), HashMap::new());
 
-    for dep in item.into_inner() {
-        let mut inner = dep.into_inner();
-        let key = inner.next().unwrap().as_str().trim();
-        let value = inner.next().unwrap().as_str().trim().trim_matches('"');
+    Ok(())
+}
 
-        let section_map = manifest.sections.get_mut(&section_name).unwrap();
-        section_map.insert(key.to_string(), value.to_string());
+/// Parses a `[lints.<tool>]` or `[workspace.lints.<tool>]` section from the manifest.
+fn parse_lints_section(
+    item: pest::iterators::Pair<Rule>,
+    manifest: &mut Manifest,
+) -> Result<(), ManifestError> {
+    let section_span = Span::from_pest(item.as_span());
+    let mut inner = item.into_inner();
+    let table = inner.next().unwrap().into_inner().next().unwrap();
+    let section_name = table.as_str().to_string();
+    let tool = table.into_inner().next().unwrap().as_str().to_string();
+    manifest.open_section(&section_name, section_span);
+
+    for entry in inner {
+        let lint = Lint::from_pair(entry.clone(), &tool, &section_name)?;
+        if section_name.starts_with("workspace.") {
+            manifest.workspace_lints.push(lint);
+        } else {
+            manifest.lints.push(lint);
+        }
+        insert_pair(manifest, &section_name, entry);
     }
 
     Ok(())
 }
+
+/// Stores a `key = value` pair of a typed section as raw text.
+fn insert_pair(manifest: &mut Manifest, section: &str, item: pest::iterators::Pair<Rule>) {
+    let span = Span::from_pest(item.as_span());
+    let mut inner = item.into_inner();
+    let key = inner.next().unwrap().as_str().trim();
+    let value = inner.next().unwrap().as_str().trim();
+    manifest.insert_entry(section, key, value, span);
+}
@@ -0,0 +1,513 @@
+//! A configurable engine of checks over a parsed manifest.
+
+use crate::diagnostic::{Diagnostic, Edit};
+use crate::{Dependency, DependencyKind, LintLevel, Manifest, ManifestError};
+use std::collections::HashMap;
+
+/// What a rule gets to look at.
+#[derive(Debug, Clone, Copy)]
+pub struct LintContext<'a> {
+    /// The parsed manifest
+    pub manifest: &'a Manifest,
+    /// The source the manifest was parsed from, for locating problems and building fixes
+    pub source: &'a str,
+}
+
+/// A single check over a manifest.
+pub trait LintRule {
+    /// The identifier used to configure the rule, e.g. `wildcard-requirement`.
+    fn id(&self) -> &'static str;
+
+    /// A short description of what the rule checks.
+    fn description(&self) -> &'static str;
+
+    /// The level the rule reports at unless configured otherwise.
+    fn default_level(&self) -> LintLevel;
+
+    /// Runs the rule, reporting problems at the rule's default level.
+    fn check(&self, context: &LintContext) -> Vec<Diagnostic>;
+}
+
+/// Runs a set of rules at configurable levels.
+pub struct Linter {
+    /// The rules to run
+    rules: Vec<Box<dyn LintRule>>,
+    /// Levels overriding the default level of a rule, by rule id
+    levels: HashMap<String, LintLevel>,
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        Linter::new()
+    }
+}
+
+impl Linter {
+    /// Creates a linter with every built-in rule at its default level.
+    pub fn new() -> Self {
+        Linter {
+            rules: builtin_rules(),
+            levels: HashMap::new(),
+        }
+    }
+
+    /// Creates a linter without any rules.
+    pub fn empty() -> Self {
+        Linter {
+            rules: Vec::new(),
+            levels: HashMap::new(),
+        }
+    }
+
+    /// Adds a rule to the linter.
+    pub fn add_rule(&mut self, rule: Box<dyn LintRule>) {
+        self.rules.push(rule);
+    }
+
+    /// Retrieves an iterator over the rules of the linter.
+    pub fn rules(&self) -> impl Iterator<Item = &dyn LintRule> {
+        self.rules.iter().map(|rule| rule.as_ref())
+    }
+
+    /// Overrides the level of a rule; `allow` disables it.
+    ///
+    /// # Arguments
+    ///
+    /// * `rule` - The id of the rule
+    /// * `level` - The level to report at
+    ///
+    /// # Returns
+    ///
+    /// A `Result` that is an error if no rule has the given id
+    pub fn set_level(&mut self, rule: &str, level: LintLevel) -> Result<(), ManifestError> {
+        if !self.rules.iter().any(|r| r.id() == rule) {
+            return Err(ManifestError::UnknownRule(rule.to_string()));
+        }
+        self.levels.insert(rule.to_string(), level);
+        Ok(())
+    }
+
+    /// Runs every enabled rule over a manifest.
+    ///
+    /// # Arguments
+    ///
+    /// * `manifest` - The parsed manifest
+    /// * `source` - The source the manifest was parsed from
+    ///
+    /// # Returns
+    ///
+    /// The diagnostics at their configured level, ordered by location
+    pub fn check(&self, manifest: &Manifest, source: &str) -> Vec<Diagnostic> {
+        let context = LintContext { manifest, source };
+        let mut diagnostics = Vec::new();
+
+        for rule in &self.rules {
+            let level = self
+                .levels
+                .get(rule.id())
+                .copied()
+                .unwrap_or_else(|| rule.default_level());
+            if level == LintLevel::Allow {
+                continue;
+            }
+            diagnostics.extend(rule.check(&context).into_iter().map(|mut diagnostic| {
+                diagnostic.level = level;
+                diagnostic
+            }));
+        }
+
+        diagnostics.sort_by_key(|diagnostic| diagnostic.span.map(|span| span.start));
+        diagnostics
+    }
+}
+
+/// Returns every built-in rule.
+pub fn builtin_rules() -> Vec<Box<dyn LintRule>> {
+    vec![
+        Box::new(MissingPackageKey {
+            id: "missing-license",
+            description: "package has no license or license-file, which crates.io requires",
+            keys: &["license", "license-file"],
+        }),
+        Box::new(MissingPackageKey {
+            id: "missing-description",
+            description: "package has no description, which crates.io requires",
+            keys: &["description"],
+        }),
+        Box::new(MissingPackageKey {
+            id: "missing-repository",
+            description: "package does not link to its repository",
+            keys: &["repository"],
+        }),
+        Box::new(WildcardRequirement),
+        Box::new(GitWithoutRev),
+        Box::new(PathWithoutVersion),
+        Box::new(UnsortedDependencies),
+        Box::new(DeprecatedKeys),
+    ]
+}
+
+/// Reports a `[package]` without any of a set of keys.
+struct MissingPackageKey {
+    /// The rule id
+    id: &'static str,
+    /// What the rule checks for
+    description: &'static str,
+    /// Keys of which at least one must be present
+    keys: &'static [&'static str],
+}
+
+impl LintRule for MissingPackageKey {
+    fn id(&self) -> &'static str {
+        self.id
+    }
+
+    fn description(&self) -> &'static str {
+        self.description
+    }
+
+    fn default_level(&self) -> LintLevel {
+        LintLevel::Warn
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<Diagnostic> {
+        let manifest = context.manifest;
+        if self
+            .keys
+            .iter()
+            .any(|key| manifest.get_by_key("package", key).is_ok())
+        {
+            return Vec::new();
+        }
+
+        vec![Diagnostic::new(
+            self.id,
+            self.default_level(),
+            format!("[package] has no {}", self.keys.join(" or ")),
+        )
+        .with_span(manifest.section_span("package"))]
+    }
+}
+
+/// Reports `*` version requirements, which crates.io rejects.
+struct WildcardRequirement;
+
+impl LintRule for WildcardRequirement {
+    fn id(&self) -> &'static str {
+        "wildcard-requirement"
+    }
+
+    fn description(&self) -> &'static str {
+        "dependency accepts any version"
+    }
+
+    fn default_level(&self) -> LintLevel {
+        LintLevel::Deny
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<Diagnostic> {
+        context
+            .manifest
+            .dependencies()
+            .filter(|dependency| dependency.version.as_deref().map(str::trim) == Some("*"))
+            .map(|dependency| {
+                Diagnostic::new(
+                    self.id(),
+                    self.default_level(),
+                    format!("{} uses a wildcard version requirement", dependency.name),
+                )
+                .with_span(dependency_span(context, dependency))
+            })
+            .collect()
+    }
+}
+
+/// Reports git dependencies that are not pinned to a revision.
+struct GitWithoutRev;
+
+impl LintRule for GitWithoutRev {
+    fn id(&self) -> &'static str {
+        "git-without-rev"
+    }
+
+    fn description(&self) -> &'static str {
+        "git dependency is not pinned to a revision"
+    }
+
+    fn default_level(&self) -> LintLevel {
+        LintLevel::Warn
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<Diagnostic> {
+        context
+            .manifest
+            .dependencies()
+            .filter(|dependency| dependency.git.is_some() && dependency.rev.is_none())
+            .map(|dependency| {
+                Diagnostic::new(
+                    self.id(),
+                    self.default_level(),
+                    format!("git dependency {} has no rev", dependency.name),
+                )
+                .with_span(dependency_span(context, dependency))
+            })
+            .collect()
+    }
+}
+
+/// Reports path dependencies without a version in crates that can be published.
+struct PathWithoutVersion;
+
+impl LintRule for PathWithoutVersion {
+    fn id(&self) -> &'static str {
+        "path-without-version"
+    }
+
+    fn description(&self) -> &'static str {
+        "path dependency of a publishable crate has no version"
+    }
+
+    fn default_level(&self) -> LintLevel {
+        LintLevel::Deny
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<Diagnostic> {
+        let publish = context.manifest.get_by_key("package", "publish");
+        if matches!(publish, Ok("false") | Ok("[]")) {
+            return Vec::new();
+        }
+
+        context
+            .manifest
+            .dependencies()
+            .filter(|dependency| {
+                dependency.path.is_some()
+                    && dependency.version.is_none()
+                    && dependency.kind != DependencyKind::Dev
+            })
+            .map(|dependency| {
+                Diagnostic::new(
+                    self.id(),
+                    self.default_level(),
+                    format!(
+                        "path dependency {} needs a version to be published",
+                        dependency.name
+                    ),
+                )
+                .with_span(dependency_span(context, dependency))
+            })
+            .collect()
+    }
+}
+
+/// Reports dependency tables whose entries are not in alphabetical order.
+struct UnsortedDependencies;
+
+impl LintRule for UnsortedDependencies {
+    fn id(&self) -> &'static str {
+        "unsorted-dependencies"
+    }
+
+    fn description(&self) -> &'static str {
+        "dependency table is not sorted alphabetically"
+    }
+
+    fn default_level(&self) -> LintLevel {
+        LintLevel::Warn
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<Diagnostic> {
+        let mut tables: Vec<(&str, Vec<&Dependency>)> = Vec::new();
+        for dependency in context.manifest.dependencies() {
+            match tables
+                .iter_mut()
+                .find(|(table, _)| *table == dependency.table)
+            {
+                Some((_, entries)) => entries.push(dependency),
+                None => tables.push((&dependency.table, vec![dependency])),
+            }
+        }
+
+        let mut diagnostics = Vec::new();
+        for (table, entries) in tables {
+            let mut sorted = entries.clone();
+            sorted.sort_by(|a, b| a.name.cmp(&b.name));
+            if sorted == entries {
+                continue;
+            }
+
+            let diagnostic = Diagnostic::new(
+                self.id(),
+                self.default_level(),
+                format!("[{}] is not sorted alphabetically", table),
+            )
+            .with_span(context.manifest.section_span(table));
+
+            diagnostics.push(match sort_edit(context, &entries, &sorted) {
+                Some(edit) => diagnostic.with_fix("sort the table", vec![edit]),
+                None => diagnostic,
+            });
+        }
+        diagnostics
+    }
+}
+
+/// Builds the edit that reorders a table, unless comments sit between its entries.
+fn sort_edit(
+    context: &LintContext,
+    entries: &[&Dependency],
+    sorted: &[&Dependency],
+) -> Option<Edit> {
+    let spans = entries
+        .iter()
+        .map(|dependency| dependency_span(context, dependency))
+        .collect::<Option<Vec<_>>>()?;
+    let separators: Vec<&str> = spans
+        .windows(2)
+        .map(|pair| &context.source[pair[0].end..pair[1].start])
+        .collect();
+    if separators
+        .iter()
+        .any(|separator| !separator.trim().is_empty())
+    {
+        return None;
+    }
+
+    let mut replacement = String::new();
+    for (i, dependency) in sorted.iter().enumerate() {
+        let span = dependency_span(context, dependency)?;
+        replacement.push_str(&context.source[span.start..span.end]);
+        if let Some(separator) = separators.get(i) {
+            replacement.push_str(separator);
+        }
+    }
+
+    Some(Edit {
+        start: spans.first()?.start,
+        end: spans.last()?.end,
+        replacement,
+    })
+}
+
+/// Keys that were renamed, with the name that replaces them.
+const DEPRECATED_KEYS: &[(&str, &str)] = &[
+    ("default_features", "default-features"),
+    ("crate_type", "crate-type"),
+    ("proc_macro", "proc-macro"),
+];
+
+/// Reports keys and sections that Cargo deprecated.
+struct DeprecatedKeys;
+
+impl LintRule for DeprecatedKeys {
+    fn id(&self) -> &'static str {
+        "deprecated-keys"
+    }
+
+    fn description(&self) -> &'static str {
+        "key or section is deprecated"
+    }
+
+    fn default_level(&self) -> LintLevel {
+        LintLevel::Warn
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<Diagnostic> {
+        let manifest = context.manifest;
+        let mut diagnostics = Vec::new();
+
+        for section in manifest.sections() {
+            for &(old, new) in DEPRECATED_KEYS {
+                if manifest.get_by_key(section, old).is_err() {
+                    continue;
+                }
+                let span = manifest.key_span(section, old);
+                let diagnostic = Diagnostic::new(
+                    self.id(),
+                    self.default_level(),
+                    format!("{} in [{}] is deprecated, use {}", old, section, new),
+                )
+                .with_span(span);
+
+                diagnostics.push(match span {
+                    Some(span) => diagnostic.with_fix(
+                        format!("rename to {}", new),
+                        vec![Edit {
+                            start: span.start,
+                            end: span.start + old.len(),
+                            replacement: new.to_string(),
+                        }],
+                    ),
+                    None => diagnostic,
+                });
+            }
+        }
+
+        for dependency in manifest.dependencies() {
+            let Some(span) = dependency_span(context, dependency) else {
+                continue;
+            };
+            let entry = &context.source[span.start..span.end];
+            if let Some(offset) = field_offset(entry, "default_features") {
+                let start = span.start + offset;
+                diagnostics.push(
+                    Diagnostic::new(
+                        self.id(),
+                        self.default_level(),
+                        format!(
+                            "default_features of {} is deprecated, use default-features",
+                            dependency.name
+                        ),
+                    )
+                    .with_span(Some(span))
+                    .with_fix(
+                        "rename to default-features",
+                        vec![Edit {
+                            start,
+                            end: start + "default_features".len(),
+                            replacement: "default-features".to_string(),
+                        }],
+                    ),
+                );
+            }
+        }
+
+        if manifest.replacements().next().is_some() {
+            diagnostics.push(
+                Diagnostic::new(
+                    self.id(),
+                    self.default_level(),
+                    "[replace] is deprecated, use [patch] instead",
+                )
+                .with_span(manifest.section_span("replace")),
+            );
+        }
+
+        diagnostics
+    }
+}
+
+/// Finds a field of the inline table of a `key = { ... }` entry, skipping the key
+/// and any string that merely contains the field's name.
+///
+/// # Returns
+///
+/// The offset of the field's key in the entry, or `None` if the table has no such field
+fn field_offset(entry: &str, field: &str) -> Option<usize> {
+    let value = entry.find('=')? + 1;
+    entry[value..]
+        .match_indices(field)
+        .map(|(offset, _)| value + offset)
+        .find(|&offset| {
+            let before = entry[..offset].trim_end();
+            let after = entry[offset + field.len()..].trim_start();
+            (before.ends_with('{') || before.ends_with(',')) && after.starts_with('=')
+        })
+}
+
+/// Locates the entry declaring a dependency.
+fn dependency_span(context: &LintContext, dependency: &Dependency) -> Option<crate::Span> {
+    context
+        .manifest
+        .key_span(&dependency.table, &dependency.name)
+}
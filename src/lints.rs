@@ -0,0 +1,216 @@
+//! Typed `[lints.<tool>]` and `[workspace.lints.<tool>]` tables.
+
+use crate::{ManifestError, Rule};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// The level a lint is set to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LintLevel {
+    /// `allow`
+    Allow,
+    /// `warn`
+    Warn,
+    /// `deny`
+    Deny,
+    /// `forbid`
+    Forbid,
+}
+
+impl LintLevel {
+    /// Returns the compiler flag setting a lint to this level.
+    pub fn flag(&self) -> &'static str {
+        match self {
+            LintLevel::Allow => "-A",
+            LintLevel::Warn => "-W",
+            LintLevel::Deny => "-D",
+            LintLevel::Forbid => "-F",
+        }
+    }
+
+    /// Returns the level as written in the manifest.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LintLevel::Allow => "allow",
+            LintLevel::Warn => "warn",
+            LintLevel::Deny => "deny",
+            LintLevel::Forbid => "forbid",
+        }
+    }
+}
+
+impl FromStr for LintLevel {
+    type Err = ManifestError;
+
+    fn from_str(level: &str) -> Result<Self, Self::Err> {
+        match level {
+            "allow" => Ok(LintLevel::Allow),
+            "warn" => Ok(LintLevel::Warn),
+            "deny" => Ok(LintLevel::Deny),
+            "forbid" => Ok(LintLevel::Forbid),
+            _ => Err(ManifestError::InvalidValue(
+                "lints".to_string(),
+                "level".to_string(),
+                format!("unknown lint level {}", level),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for LintLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A single lint setting.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Lint {
+    /// The tool owning the lint: `rust`, `clippy` or `rustdoc`
+    pub tool: String,
+    /// The lint or lint group name without the tool prefix
+    pub name: String,
+    /// The level the lint is set to
+    pub level: LintLevel,
+    /// Ordering between lints; lower priorities are passed first and overridden by higher ones
+    pub priority: i32,
+    /// `check-cfg` values of the `unexpected_cfgs` lint
+    pub check_cfg: Vec<String>,
+}
+
+impl Lint {
+    /// Returns the name the compiler knows the lint by, e.g. `clippy::all`.
+    pub fn qualified_name(&self) -> String {
+        if self.tool == "rust" {
+            self.name.clone()
+        } else {
+            format!("{}::{}", self.tool, self.name)
+        }
+    }
+
+    /// Builds a lint from a `lint_key_value` pair of a `[lints.<tool>]` table.
+    pub(crate) fn from_pair(
+        item: pest::iterators::Pair<Rule>,
+        tool: &str,
+        section: &str,
+    ) -> Result<Self, ManifestError> {
+        let mut inner = item.into_inner();
+        let name = inner.next().unwrap().as_str().trim();
+        let value = inner.next().unwrap();
+
+        let fields = if value.as_rule() == Rule::quoted_string {
+            vec![LintField::Level(string_content(value))]
+        } else {
+            value
+                .into_inner()
+                .filter_map(|field| match field.as_rule() {
+                    Rule::lint_level => Some(LintField::Level(string_content(
+                        field.into_inner().next().unwrap(),
+                    ))),
+                    Rule::lint_priority => Some(LintField::Priority(
+                        field.into_inner().next().unwrap().as_str(),
+                    )),
+                    Rule::lint_check_cfg => Some(LintField::CheckCfg(
+                        field.into_inner().map(string_content).collect(),
+                    )),
+                    _ => None,
+                })
+                .collect()
+        };
+        Lint::from_fields(tool, section, name, fields)
+    }
+
+    /// Builds a lint from the fields of its entry, in the order they are written.
+    pub(crate) fn from_fields<'a>(
+        tool: &str,
+        section: &str,
+        name: &str,
+        fields: impl IntoIterator<Item = LintField<'a>>,
+    ) -> Result<Self, ManifestError> {
+        let invalid = |message: String| {
+            ManifestError::InvalidValue(section.to_string(), name.to_string(), message)
+        };
+
+        let mut level = None;
+        let mut priority = 0;
+        let mut check_cfg = Vec::new();
+
+        for field in fields {
+            match field {
+                LintField::Level(value) => level = Some(value),
+                LintField::Priority(number) => {
+                    priority = number
+                        .parse()
+                        .map_err(|_| invalid(format!("invalid priority {}", number)))?;
+                }
+                LintField::CheckCfg(values) => {
+                    check_cfg = values.into_iter().map(str::to_string).collect()
+                }
+            }
+        }
+
+        let level = level.ok_or_else(|| invalid("missing lint level".to_string()))?;
+        let level = level
+            .parse()
+            .map_err(|_| invalid(format!("unknown lint level {}", level)))?;
+
+        Ok(Lint {
+            tool: tool.to_string(),
+            name: name.to_string(),
+            level,
+            priority,
+            check_cfg,
+        })
+    }
+}
+
+/// A field of a lint entry, as written in the manifest.
+pub(crate) enum LintField<'a> {
+    /// `level = "..."`, or the whole entry in the `name = "level"` shorthand
+    Level(&'a str),
+    /// `priority = ...`
+    Priority(&'a str),
+    /// `check-cfg = [...]`
+    CheckCfg(Vec<&'a str>),
+}
+
+/// Builds the compiler arguments equivalent to a set of lints.
+///
+/// Lints are ordered by priority and then by name, so that lints with a higher
+/// priority come later and override lower ones, e.g. a `deny` of `clippy::all`
+/// at priority `-1` followed by an `allow` of a single clippy lint.
+///
+/// # Arguments
+///
+/// * `lints` - The lints to convert
+///
+/// # Returns
+///
+/// The argument list, e.g. `["-D", "clippy::all", "-A", "clippy::module_name_repetitions"]`
+pub fn lint_args<'a>(lints: impl IntoIterator<Item = &'a Lint>) -> Vec<String> {
+    let mut lints: Vec<&Lint> = lints.into_iter().collect();
+    lints.sort_by(|a, b| {
+        a.priority
+            .cmp(&b.priority)
+            .then_with(|| a.qualified_name().cmp(&b.qualified_name()))
+    });
+
+    let mut args = Vec::new();
+    for lint in lints {
+        args.push(lint.level.flag().to_string());
+        args.push(lint.qualified_name());
+        for cfg in &lint.check_cfg {
+            args.push("--check-cfg".to_string());
+            args.push(cfg.clone());
+        }
+    }
+    args
+}
+
+/// Returns the contents of a quoted or literal string.
+fn string_content(item: pest::iterators::Pair<'_, Rule>) -> &str {
+    item.into_inner().next().unwrap().as_str()
+}
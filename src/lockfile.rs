@@ -0,0 +1,392 @@
+//! Parsing of `Cargo.lock` files and consistency checks against manifests.
+
+use crate::{Dependency, Manifest, ManifestError};
+use pest::Parser;
+use pest_derive::Parser;
+use semver::{Version, VersionReq};
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+
+/// The parser for `Cargo.lock` files.
+#[derive(Parser)]
+#[grammar = "lockfile.pest"]
+pub struct LockfileParser;
+
+/// A `[[package]]` entry of a lockfile.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LockedPackage {
+    /// Package name
+    pub name: String,
+    /// Exact locked version
+    pub version: String,
+    /// Package source, `None` for path and workspace packages
+    pub source: Option<String>,
+    /// Registry checksum
+    pub checksum: Option<String>,
+    /// Dependencies as written in the lockfile (`name`, `name version` or `name version (source)`)
+    pub dependencies: Vec<String>,
+}
+
+/// A parsed `Cargo.lock` file.
+#[derive(Debug, Default)]
+pub struct Lockfile {
+    /// Lockfile format version, absent for the oldest format
+    version: Option<u32>,
+    /// Locked packages in file order
+    packages: Vec<LockedPackage>,
+}
+
+/// A problem found while checking manifests against a lockfile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LockIssue {
+    /// No locked version satisfies the manifest requirement
+    Unsatisfied {
+        package: String,
+        dependency: String,
+        requirement: String,
+        locked: Vec<String>,
+    },
+    /// The dependency has no entry in the lockfile
+    MissingFromLock { package: String, dependency: String },
+    /// The lockfile entry comes from a different source than the manifest asks for
+    SourceMismatch {
+        package: String,
+        dependency: String,
+        expected: String,
+        locked: String,
+    },
+    /// The lockfile entry is not needed by any of the manifests
+    StaleEntry { name: String, version: String },
+}
+
+impl fmt::Display for LockIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LockIssue::Unsatisfied {
+                package,
+                dependency,
+                requirement,
+                locked,
+            } => write!(
+                f,
+                "{}: {} requires {} but Cargo.lock has {}",
+                package,
+                dependency,
+                requirement,
+                locked.join(", ")
+            ),
+            LockIssue::MissingFromLock {
+                package,
+                dependency,
+            } => write!(f, "{}: {} is missing from Cargo.lock", package, dependency),
+            LockIssue::SourceMismatch {
+                package,
+                dependency,
+                expected,
+                locked,
+            } => write!(
+                f,
+                "{}: {} should come from {} but Cargo.lock has {}",
+                package, dependency, expected, locked
+            ),
+            LockIssue::StaleEntry { name, version } => {
+                write!(f, "{} {} is locked but no longer used", name, version)
+            }
+        }
+    }
+}
+
+impl Lockfile {
+    /// Parses the contents of a `Cargo.lock` file.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The lockfile content as a string
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing either a parsed `Lockfile` or a `ManifestError`
+    pub fn parse(input: &str) -> Result<Self, ManifestError> {
+        let mut lockfile = Lockfile::default();
+        let parsed = LockfileParser::parse(Rule::lockfile, input)
+            .map_err(|e| ManifestError::ParseError(e.to_string()))?
+            .next()
+            .unwrap();
+
+        for item in parsed.into_inner() {
+            match item.as_rule() {
+                Rule::lock_version => {
+                    let number = item.into_inner().next().unwrap().as_str();
+                    lockfile.version = number.parse().ok();
+                }
+                Rule::package_entry => lockfile.packages.push(parse_package_entry(item)?),
+                _ => {}
+            }
+        }
+
+        Ok(lockfile)
+    }
+
+    /// Returns the lockfile format version, if the file declares one.
+    pub fn version(&self) -> Option<u32> {
+        self.version
+    }
+
+    /// Retrieves an iterator over the locked packages.
+    pub fn packages(&self) -> impl Iterator<Item = &LockedPackage> {
+        self.packages.iter()
+    }
+
+    /// Checks the manifests of a package or of every workspace member
+    /// against this lockfile.
+    ///
+    /// All members sharing the lockfile must be passed at once, otherwise
+    /// entries used only by the missing members are reported as stale.
+    ///
+    /// # Arguments
+    ///
+    /// * `manifests` - The manifests sharing this lockfile
+    ///
+    /// # Returns
+    ///
+    /// The list of problems found, empty when the lockfile is up to date
+    pub fn check(&self, manifests: &[Manifest]) -> Vec<LockIssue> {
+        let members: HashSet<&str> = manifests.iter().map(|m| m.package_name()).collect();
+        let is_member =
+            |entry: &LockedPackage| entry.source.is_none() && members.contains(entry.name.as_str());
+
+        let mut issues = Vec::new();
+        let mut reachable: HashSet<usize> = HashSet::new();
+        let mut queue = VecDeque::new();
+
+        for (index, entry) in self.packages.iter().enumerate() {
+            if is_member(entry) {
+                reachable.insert(index);
+            }
+        }
+
+        for manifest in manifests {
+            for dependency in manifest.dependencies() {
+                let candidates: Vec<usize> = self
+                    .packages
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, entry)| entry.name == dependency.package_name())
+                    .map(|(index, _)| index)
+                    .collect();
+
+                if candidates.is_empty() {
+                    issues.push(LockIssue::MissingFromLock {
+                        package: manifest.package_name().to_string(),
+                        dependency: dependency.name.clone(),
+                    });
+                    continue;
+                }
+
+                let same_source: Vec<usize> = candidates
+                    .iter()
+                    .copied()
+                    .filter(|&index| {
+                        source_matches(dependency, self.packages[index].source.as_deref())
+                    })
+                    .collect();
+
+                if same_source.is_empty() {
+                    let entry = &self.packages[candidates[0]];
+                    issues.push(LockIssue::SourceMismatch {
+                        package: manifest.package_name().to_string(),
+                        dependency: dependency.name.clone(),
+                        expected: describe_expected_source(dependency),
+                        locked: entry.source.clone().unwrap_or_else(|| "a path".to_string()),
+                    });
+                    queue.extend(candidates);
+                    continue;
+                }
+
+                let satisfying: Vec<usize> = match requirement(dependency) {
+                    Some(req) => same_source
+                        .iter()
+                        .copied()
+                        .filter(|&index| {
+                            Version::parse(&self.packages[index].version)
+                                .map(|version| req.matches(&version))
+                                .unwrap_or(false)
+                        })
+                        .collect(),
+                    None => same_source.clone(),
+                };
+
+                if satisfying.is_empty() {
+                    issues.push(LockIssue::Unsatisfied {
+                        package: manifest.package_name().to_string(),
+                        dependency: dependency.name.clone(),
+                        requirement: dependency.version.clone().unwrap_or_default(),
+                        locked: same_source
+                            .iter()
+                            .map(|&index| self.packages[index].version.clone())
+                            .collect(),
+                    });
+                    queue.extend(same_source);
+                } else {
+                    queue.extend(satisfying);
+                }
+            }
+        }
+
+        while let Some(index) = queue.pop_front() {
+            if !reachable.insert(index) {
+                continue;
+            }
+            for reference in &self.packages[index].dependencies {
+                queue.extend(self.resolve_reference(reference));
+            }
+        }
+
+        for (index, entry) in self.packages.iter().enumerate() {
+            if !reachable.contains(&index) {
+                issues.push(LockIssue::StaleEntry {
+                    name: entry.name.clone(),
+                    version: entry.version.clone(),
+                });
+            }
+        }
+
+        issues
+    }
+
+    /// Finds the locked package a manifest dependency resolves to: the highest
+    /// version from the requested source that satisfies the requirement.
+    ///
+    /// # Returns
+    ///
+    /// The index of the package among `packages()`, or `None` if no entry fits
+    pub(crate) fn resolve_dependency(&self, dependency: &Dependency) -> Option<usize> {
+        let requirement = requirement(dependency);
+        self.packages
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| {
+                entry.name == dependency.package_name()
+                    && source_matches(dependency, entry.source.as_deref())
+            })
+            .filter_map(|(index, entry)| Some((index, Version::parse(&entry.version).ok()?)))
+            .filter(|(_, version)| requirement.as_ref().is_none_or(|req| req.matches(version)))
+            .max_by(|(_, a), (_, b)| a.cmp(b))
+            .map(|(index, _)| index)
+    }
+
+    /// Resolves a `name [version [(source)]]` reference to matching package indices.
+    pub(crate) fn resolve_reference(&self, reference: &str) -> Vec<usize> {
+        let mut parts = reference.split_whitespace();
+        let name = parts.next().unwrap_or_default();
+        let version = parts.next();
+
+        self.packages
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| {
+                entry.name == name && version.is_none_or(|version| entry.version == version)
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+}
+
+/// Parses a `[[package]]` entry of the lockfile.
+fn parse_package_entry(item: pest::iterators::Pair<Rule>) -> Result<LockedPackage, ManifestError> {
+    let mut package = LockedPackage::default();
+
+    for field in item.into_inner() {
+        let mut inner = field.into_inner();
+        let key = inner.next().unwrap().as_str().trim_matches('"');
+        let value = inner.next().unwrap();
+
+        match (key, value.as_rule()) {
+            ("name", Rule::string) => package.name = string_value(value),
+            ("version", Rule::string) => package.version = string_value(value),
+            ("source", Rule::string) => package.source = Some(string_value(value)),
+            ("checksum", Rule::string) => package.checksum = Some(string_value(value)),
+            ("dependencies", Rule::string_array) => {
+                package.dependencies = value.into_inner().map(string_value).collect()
+            }
+            _ => {}
+        }
+    }
+
+    if package.name.is_empty() || package.version.is_empty() {
+        return Err(ManifestError::ParseError(
+            "Lockfile package entry without name or version".to_string(),
+        ));
+    }
+
+    Ok(package)
+}
+
+/// Returns the contents of a quoted lockfile string.
+fn string_value(item: pest::iterators::Pair<Rule>) -> String {
+    item.into_inner().next().unwrap().as_str().to_string()
+}
+
+/// Returns the parsed version requirement of a dependency, if it has one
+/// that can be checked against the lockfile.
+pub(crate) fn requirement(dependency: &Dependency) -> Option<VersionReq> {
+    if dependency.workspace {
+        return None;
+    }
+    VersionReq::parse(dependency.version.as_deref()?).ok()
+}
+
+/// Checks whether a locked source is compatible with the source requested by the manifest.
+pub(crate) fn source_matches(dependency: &Dependency, source: Option<&str>) -> bool {
+    if dependency.workspace {
+        return true;
+    }
+
+    match (&dependency.path, &dependency.git, source) {
+        (Some(_), _, source) => source.is_none(),
+        (None, Some(url), Some(source)) => git_source_matches(dependency, url, source),
+        (None, None, Some(source)) => {
+            source.starts_with("registry+") || source.starts_with("sparse+")
+        }
+        (None, _, None) => false,
+    }
+}
+
+/// Checks whether a `git+URL?ref#commit` source points at the requested repository and reference.
+fn git_source_matches(dependency: &Dependency, url: &str, source: &str) -> bool {
+    let Some(source) = source.strip_prefix("git+") else {
+        return false;
+    };
+    let (location, commit) = source.split_once('#').unwrap_or((source, ""));
+    let (locked_url, query) = location.split_once('?').unwrap_or((location, ""));
+
+    if normalize_git_url(locked_url) != normalize_git_url(url) {
+        return false;
+    }
+
+    match (&dependency.rev, &dependency.tag, &dependency.branch) {
+        (Some(rev), _, _) => query == format!("rev={}", rev) || commit.starts_with(rev.as_str()),
+        (None, Some(tag), _) => query == format!("tag={}", tag),
+        (None, None, Some(branch)) => query == format!("branch={}", branch),
+        (None, None, None) => query.is_empty(),
+    }
+}
+
+/// Strips the parts of a git URL that do not identify a different repository.
+pub(crate) fn normalize_git_url(url: &str) -> &str {
+    let url = url.trim_end_matches('/');
+    url.strip_suffix(".git").unwrap_or(url)
+}
+
+/// Describes the source a dependency is expected to come from.
+pub(crate) fn describe_expected_source(dependency: &Dependency) -> String {
+    if let Some(path) = &dependency.path {
+        format!("path {}", path)
+    } else if let Some(git) = &dependency.git {
+        format!("git {}", git)
+    } else if let Some(registry) = &dependency.registry {
+        format!("registry {}", registry)
+    } else {
+        "crates.io".to_string()
+    }
+}
@@ -0,0 +1,725 @@
+//! Language server for `Cargo.toml`, spoken over an `lsp_server::Connection`.
+//!
+//! Diagnostics come from `Manifest::parse` and the default `Linter`, formatting
+//! from `format_manifest`. Completion, hover and go-to-definition only look at
+//! the lines around the cursor, so they keep working while a half-typed manifest
+//! does not parse.
+
+use crate::query::{ARRAY_TABLES, DEPENDENCY_TABLES};
+use crate::{format_manifest, Diagnostic, Linter, Manifest, ManifestError};
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, DidSaveTextDocument,
+    Notification as _, PublishDiagnostics,
+};
+use lsp_types::request::{Completion, Formatting, GotoDefinition, HoverRequest, Request as _};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, DiagnosticSeverity,
+    DocumentFormattingParams, Documentation, GotoDefinitionParams, GotoDefinitionResponse, Hover,
+    HoverContents, HoverParams, HoverProviderCapability, Location, MarkupContent, MarkupKind,
+    NumberOrString, OneOf, Position, PublishDiagnosticsParams, Range, ServerCapabilities,
+    TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit, Uri,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Name reported as the source of published diagnostics.
+const SOURCE: &str = "manifest_parser_rs";
+
+/// Name of the manifest in a package directory.
+const MANIFEST_NAME: &str = "Cargo.toml";
+
+/// Tables offered after `[`, with their documentation.
+#[rustfmt::skip]
+const TABLES: &[(&str, &str)] = &[
+    ("package", "The package being built: its name, version and metadata."),
+    ("lib", "Settings of the library target."),
+    ("bin", "Settings of a binary target, one `[[bin]]` table per binary."),
+    ("example", "Settings of an example target, one `[[example]]` table each."),
+    ("test", "Settings of an integration test target, one `[[test]]` table each."),
+    ("bench", "Settings of a benchmark target, one `[[bench]]` table each."),
+    ("features", "Features, each listing the features and optional dependencies it enables."),
+    ("dependencies", "Dependencies of every target of the package."),
+    ("dev-dependencies", "Dependencies of examples, tests and benchmarks only."),
+    ("build-dependencies", "Dependencies of the build script only."),
+    ("target", "Platform-specific tables, e.g. `[target.'cfg(unix)'.dependencies]`."),
+    ("badges", "Badges shown on the registry page."),
+    ("lints", "Lint levels per tool, or `workspace = true` to inherit them."),
+    ("lints.rust", "Levels of `rustc` lints."),
+    ("lints.clippy", "Levels of Clippy lints."),
+    ("lints.rustdoc", "Levels of rustdoc lints."),
+    ("profile", "Compiler settings of a build profile."),
+    ("profile.dev", "Compiler settings of `cargo build`."),
+    ("profile.release", "Compiler settings of `cargo build --release`."),
+    ("profile.test", "Compiler settings of `cargo test`."),
+    ("profile.bench", "Compiler settings of `cargo bench`."),
+    ("workspace", "Makes this manifest the root of a workspace."),
+    ("workspace.package", "Package keys that members inherit with `key.workspace = true`."),
+    ("workspace.dependencies", "Dependencies that members inherit with `workspace = true`."),
+    ("workspace.lints", "Lints that members inherit with `workspace = true` in `[lints]`."),
+    ("workspace.metadata", "Workspace settings of external tools, ignored by Cargo."),
+    ("package.metadata", "Package settings of external tools, ignored by Cargo."),
+    ("patch", "Overrides of dependencies from a source, e.g. `[patch.crates-io]`."),
+    ("patch.crates-io", "Overrides of dependencies from crates.io."),
+    ("replace", "Overrides of exact package versions, superseded by `[patch]`."),
+];
+
+/// Keys of `[package]` and `[workspace.package]`.
+#[rustfmt::skip]
+const PACKAGE_KEYS: &[(&str, &str)] = &[
+    ("name", "The name of the package, used to refer to it from other packages."),
+    ("version", "The SemVer version of the package."),
+    ("authors", "The authors of the package."),
+    ("edition", "The Rust edition the package is compiled with, e.g. `\"2021\"`."),
+    ("rust-version", "The oldest Rust version the package supports."),
+    ("description", "A short description shown on the registry."),
+    ("documentation", "URL of the package documentation."),
+    ("readme", "Path of the package's README file."),
+    ("homepage", "URL of the package homepage."),
+    ("repository", "URL of the package source repository."),
+    ("license", "The SPDX license expression of the package."),
+    ("license-file", "Path of a license file, for licenses without an SPDX identifier."),
+    ("keywords", "Up to five keywords the registry is searched by."),
+    ("categories", "Registry categories the package belongs to."),
+    ("workspace", "Path of the workspace root, if it is not a parent directory."),
+    ("build", "Path of the build script, or `false` to disable it."),
+    ("links", "Name of the native library the package links."),
+    ("exclude", "Files left out of the published package."),
+    ("include", "Files put in the published package, leaving out every other file."),
+    ("publish", "Whether, or to which registries, the package may be published."),
+    ("default-run", "The binary `cargo run` runs by default."),
+    ("autobins", "Whether binary targets are discovered automatically."),
+    ("autoexamples", "Whether example targets are discovered automatically."),
+    ("autotests", "Whether test targets are discovered automatically."),
+    ("autobenches", "Whether benchmark targets are discovered automatically."),
+    ("resolver", "The dependency resolver version."),
+];
+
+/// Keys of `[lib]`, `[[bin]]`, `[[example]]`, `[[test]]` and `[[bench]]`.
+#[rustfmt::skip]
+const TARGET_KEYS: &[(&str, &str)] = &[
+    ("name", "The name of the target."),
+    ("path", "Path of the target's source file."),
+    ("test", "Whether the target is tested by default."),
+    ("doctest", "Whether documentation examples are tested."),
+    ("bench", "Whether the target is benchmarked by default."),
+    ("doc", "Whether the target is documented by default."),
+    ("proc-macro", "Whether the library is a procedural macro."),
+    ("harness", "Whether the libtest harness is used."),
+    ("edition", "The Rust edition of the target."),
+    ("crate-type", "The crate types to generate, e.g. `[\"cdylib\"]`."),
+    ("required-features", "Features that must be enabled to build the target."),
+];
+
+/// Keys of `[profile.<name>]`.
+#[rustfmt::skip]
+const PROFILE_KEYS: &[(&str, &str)] = &[
+    ("opt-level", "The optimization level, `0` to `3`, `\"s\"` or `\"z\"`."),
+    ("debug", "How much debug information is generated."),
+    ("split-debuginfo", "Whether debug information is put in a separate file."),
+    ("strip", "What is stripped from binaries."),
+    ("debug-assertions", "Whether `debug_assert!` and `cfg(debug_assertions)` are enabled."),
+    ("overflow-checks", "Whether integer overflow panics."),
+    ("lto", "Link-time optimization, `false`, `true`, `\"thin\"` or `\"off\"`."),
+    ("panic", "The panic strategy, `\"unwind\"` or `\"abort\"`."),
+    ("incremental", "Whether incremental compilation is enabled."),
+    ("codegen-units", "How many code generation units a crate is split into."),
+    ("rpath", "Whether rpath is enabled."),
+    ("inherits", "The profile a custom profile starts from."),
+];
+
+/// Keys of `[workspace]`.
+#[rustfmt::skip]
+const WORKSPACE_KEYS: &[(&str, &str)] = &[
+    ("members", "Paths of the member packages, with `*` and `?` globs."),
+    ("exclude", "Paths left out of the workspace."),
+    ("default-members", "The members commands run on when none is selected."),
+    ("resolver", "The dependency resolver version of the workspace."),
+];
+
+/// Keys of the inline tables of dependency and patch tables.
+#[rustfmt::skip]
+const DEPENDENCY_KEYS: &[(&str, &str)] = &[
+    ("version", "The version requirement of the dependency."),
+    ("git", "URL of the git repository the dependency is fetched from."),
+    ("branch", "The git branch to track."),
+    ("tag", "The git tag to check out."),
+    ("rev", "The git revision to check out."),
+    ("path", "Local path of the dependency's package directory."),
+    ("registry", "Name of the registry the dependency is fetched from."),
+    ("package", "The real name of a renamed dependency."),
+    ("workspace", "Inherits the dependency from `[workspace.dependencies]`."),
+    ("optional", "Makes the dependency an optional feature."),
+    ("default-features", "Whether the dependency's default features are enabled."),
+    ("features", "Features of the dependency to enable."),
+];
+
+/// Keys of the inline tables of `[lints.<tool>]` tables.
+#[rustfmt::skip]
+const LINT_KEYS: &[(&str, &str)] = &[
+    ("level", "The lint level: `allow`, `warn`, `deny` or `forbid`."),
+    ("priority", "Order among lints of a tool; higher priorities are applied later."),
+];
+
+/// Kinds of tables whose keys the server knows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TableKind {
+    /// `[package]` and `[workspace.package]`
+    Package,
+    /// `[lib]`, `[[bin]]`, `[[example]]`, `[[test]]` and `[[bench]]`
+    Target,
+    /// `[profile.<name>]`
+    Profile,
+    /// `[workspace]`
+    Workspace,
+    /// Dependency and patch tables, whose inline tables the keys belong to
+    Dependencies,
+    /// `[lints.<tool>]` tables, whose inline tables the keys belong to
+    Lints,
+}
+
+impl TableKind {
+    /// Returns the known keys with their documentation.
+    fn keys(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            TableKind::Package => PACKAGE_KEYS,
+            TableKind::Target => TARGET_KEYS,
+            TableKind::Profile => PROFILE_KEYS,
+            TableKind::Workspace => WORKSPACE_KEYS,
+            TableKind::Dependencies => DEPENDENCY_KEYS,
+            TableKind::Lints => LINT_KEYS,
+        }
+    }
+
+    /// Checks whether the keys belong to the inline tables of entries rather than the entries.
+    fn is_inline(self) -> bool {
+        matches!(self, TableKind::Dependencies | TableKind::Lints)
+    }
+}
+
+/// Serves a language server session until the client shuts it down.
+///
+/// # Arguments
+///
+/// * `connection` - The connection to the client, e.g. `Connection::stdio()`
+///
+/// # Returns
+///
+/// A `Result` that is `Ok` after a clean shutdown, or a `ManifestError::Protocol`
+/// if the client broke the protocol or went away
+pub fn serve(connection: &Connection) -> Result<(), ManifestError> {
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec!["[".to_string(), "{".to_string()]),
+            ..CompletionOptions::default()
+        }),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        ..ServerCapabilities::default()
+    };
+    let capabilities = serde_json::to_value(capabilities).map_err(protocol_error)?;
+    connection
+        .initialize(capabilities)
+        .map_err(protocol_error)?;
+
+    let mut server = Server::default();
+    for message in &connection.receiver {
+        let reply = match message {
+            Message::Request(request) => {
+                if connection
+                    .handle_shutdown(&request)
+                    .map_err(protocol_error)?
+                {
+                    return Ok(());
+                }
+                Some(Message::Response(server.handle(request)))
+            }
+            Message::Notification(notification) => {
+                server.notify(notification).map(Message::Notification)
+            }
+            Message::Response(_) => None,
+        };
+        if let Some(reply) = reply {
+            connection.sender.send(reply).map_err(protocol_error)?;
+        }
+    }
+    Ok(())
+}
+
+/// Wraps a failure of the connection.
+fn protocol_error(error: impl std::fmt::Display) -> ManifestError {
+    ManifestError::Protocol(error.to_string())
+}
+
+/// The documents the client has open.
+#[derive(Debug, Default)]
+struct Server {
+    /// Text of every open document
+    documents: HashMap<Uri, String>,
+}
+
+impl Server {
+    /// Answers a request.
+    fn handle(&self, request: Request) -> Response {
+        match request.method.as_str() {
+            Completion::METHOD => respond(request, |params: CompletionParams| {
+                let position = params.text_document_position;
+                let text = self.text(&position.text_document.uri)?;
+                Some(completions(&text, position.position))
+            }),
+            HoverRequest::METHOD => respond(request, |params: HoverParams| {
+                let position = params.text_document_position_params;
+                hover(&self.text(&position.text_document.uri)?, position.position)
+            }),
+            GotoDefinition::METHOD => respond(request, |params: GotoDefinitionParams| {
+                let position = params.text_document_position_params;
+                self.definition(&position.text_document.uri, position.position)
+                    .map(GotoDefinitionResponse::Scalar)
+            }),
+            Formatting::METHOD => respond(request, |params: DocumentFormattingParams| {
+                let text = self.text(&params.text_document.uri)?;
+                let formatted = format_manifest(&text).ok()?;
+                let range = Range::new(Position::new(0, 0), position(&text, text.len()));
+                match formatted == text {
+                    true => Some(Vec::new()),
+                    false => Some(vec![TextEdit::new(range, formatted)]),
+                }
+            }),
+            method => Response::new_err(
+                request.id,
+                ErrorCode::MethodNotFound as i32,
+                format!("Unknown method {}", method),
+            ),
+        }
+    }
+
+    /// Tracks the open documents.
+    ///
+    /// # Returns
+    ///
+    /// The diagnostics to publish for the document the notification is about, if any
+    fn notify(&mut self, notification: Notification) -> Option<Notification> {
+        let uri = match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: lsp_types::DidOpenTextDocumentParams = extract(notification)?;
+                let document = params.text_document;
+                self.documents.insert(document.uri.clone(), document.text);
+                document.uri
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: lsp_types::DidChangeTextDocumentParams = extract(notification)?;
+                let text = params.content_changes.into_iter().last()?.text;
+                self.documents
+                    .insert(params.text_document.uri.clone(), text);
+                params.text_document.uri
+            }
+            DidSaveTextDocument::METHOD => {
+                let params: lsp_types::DidSaveTextDocumentParams = extract(notification)?;
+                params.text_document.uri
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: lsp_types::DidCloseTextDocumentParams = extract(notification)?;
+                self.documents.remove(&params.text_document.uri);
+                params.text_document.uri
+            }
+            _ => return None,
+        };
+
+        let diagnostics = self
+            .documents
+            .get(&uri)
+            .map_or(Vec::new(), |text| diagnostics(text));
+        let params = PublishDiagnosticsParams::new(uri, diagnostics, None);
+        Some(Notification::new(
+            PublishDiagnostics::METHOD.to_string(),
+            params,
+        ))
+    }
+
+    /// Returns the text of an open document, or of the file on disk.
+    fn text(&self, uri: &Uri) -> Option<String> {
+        match self.documents.get(uri) {
+            Some(text) => Some(text.clone()),
+            None => fs::read_to_string(uri_path(uri)?).ok(),
+        }
+    }
+
+    /// Finds where the value under the cursor comes from: the package directory of
+    /// a path dependency, or the workspace entry an inherited value is taken from.
+    fn definition(&self, uri: &Uri, position: Position) -> Option<Location> {
+        let text = self.text(uri)?;
+        let (table, line, _) = context(&text, position);
+        let (key, _) = line_key(line)?;
+        let (name, field) = key.split_once('.').unwrap_or((key, ""));
+        let inherited = field == "workspace" || field_value(line, "workspace") == Some("true");
+
+        match table_kind(&table) {
+            Some(TableKind::Dependencies) => {
+                if let Some(path) = field_value(line, "path") {
+                    let file = uri_path(uri)?.parent()?.join(path).join(MANIFEST_NAME);
+                    let file = file_uri(&fs::canonicalize(file).ok()?)?;
+                    return Some(Location::new(file, Range::default()));
+                }
+                if inherited {
+                    return self.workspace_entry(uri, "workspace.dependencies", Some(name));
+                }
+                None
+            }
+            Some(TableKind::Package) if inherited => {
+                self.workspace_entry(uri, "workspace.package", Some(name))
+            }
+            _ if table == "lints" && key == "workspace" => {
+                self.workspace_entry(uri, "workspace.lints", None)
+            }
+            _ => None,
+        }
+    }
+
+    /// Locates an entry, or with no `key` the first table, of the workspace root
+    /// that `uri` belongs to.
+    ///
+    /// `table` also matches the tables below it, e.g. `workspace.lints.rust` for
+    /// `workspace.lints`.
+    fn workspace_entry(&self, uri: &Uri, table: &str, key: Option<&str>) -> Option<Location> {
+        let file = uri_path(uri)?;
+        for dir in file.parent()?.ancestors() {
+            let Some(root) = file_uri(&dir.join(MANIFEST_NAME)) else {
+                continue;
+            };
+            let Some(text) = self.text(&root) else {
+                continue;
+            };
+            if find(&text, "workspace", None).is_none() {
+                continue;
+            }
+            return find(&text, table, key).map(|range| Location::new(root, range));
+        }
+        None
+    }
+}
+
+/// Answers a request with the result of `handler`, or an error if its parameters
+/// do not match the method.
+fn respond<P: DeserializeOwned, R: Serialize>(
+    request: Request,
+    handler: impl FnOnce(P) -> R,
+) -> Response {
+    match serde_json::from_value(request.params) {
+        Ok(params) => Response::new_ok(request.id, handler(params)),
+        Err(e) => Response::new_err(request.id, ErrorCode::InvalidParams as i32, e.to_string()),
+    }
+}
+
+/// Reads the parameters of a notification, `None` if they do not match its method.
+fn extract<P: DeserializeOwned>(notification: Notification) -> Option<P> {
+    serde_json::from_value(notification.params).ok()
+}
+
+/// Checks a document like `check` does.
+fn diagnostics(text: &str) -> Vec<lsp_types::Diagnostic> {
+    let found = match Manifest::parse(text) {
+        Ok(manifest) => Linter::new().check(&manifest, text),
+        Err(e) => vec![Diagnostic::from_error(&e, text)],
+    };
+    found
+        .into_iter()
+        .map(|diagnostic| lsp_types::Diagnostic {
+            range: diagnostic.span.map_or(Range::default(), |span| {
+                Range::new(position(text, span.start), position(text, span.end))
+            }),
+            severity: Some(match diagnostic.is_error() {
+                true => DiagnosticSeverity::ERROR,
+                false => DiagnosticSeverity::WARNING,
+            }),
+            code: Some(NumberOrString::String(diagnostic.rule)),
+            source: Some(SOURCE.to_string()),
+            message: diagnostic.message,
+            ..lsp_types::Diagnostic::default()
+        })
+        .collect()
+}
+
+/// Offers table names after `[`, keys at the start of an entry and the keys of
+/// dependency and lint inline tables after `{`.
+fn completions(text: &str, position: Position) -> Vec<CompletionItem> {
+    let (table, line, column) = context(text, position);
+    let before = line[..column].trim_start();
+    if let Some(name) = before.strip_prefix('[') {
+        let array = name.starts_with('[');
+        return TABLES
+            .iter()
+            .filter(|(name, _)| !array || ARRAY_TABLES.contains(name))
+            .map(|&(name, doc)| item(name, doc, CompletionItemKind::MODULE))
+            .collect();
+    }
+    key_kind(&table, before).map_or(Vec::new(), |kind| {
+        kind.keys()
+            .iter()
+            .map(|&(key, doc)| item(key, doc, CompletionItemKind::PROPERTY))
+            .collect()
+    })
+}
+
+/// Builds a completion item with Markdown documentation.
+fn item(label: &str, doc: &str, kind: CompletionItemKind) -> CompletionItem {
+    CompletionItem {
+        label: label.to_string(),
+        kind: Some(kind),
+        documentation: Some(Documentation::MarkupContent(markdown(doc))),
+        ..CompletionItem::default()
+    }
+}
+
+/// Explains the table header or key under the cursor.
+fn hover(text: &str, position: Position) -> Option<Hover> {
+    let (table, line, column) = context(text, position);
+    let table = header(line).unwrap_or(table);
+    let (start, end) = word(line, column)?;
+    let range = Some(Range::new(
+        Position::new(position.line, utf16_len(&line[..start])),
+        Position::new(position.line, utf16_len(&line[..end])),
+    ));
+
+    let doc = if header(line).is_some() {
+        let name = TABLES
+            .iter()
+            .filter(|(name, _)| table == *name || table.starts_with(&format!("{}.", name)))
+            .max_by_key(|(name, _)| name.len())?;
+        format!("**[{}]**\n\n{}", name.0, name.1)
+    } else {
+        let key = &line[start..end];
+        let kind = key_kind(&table, &line[..start])?;
+        let (_, doc) = kind.keys().iter().find(|(name, _)| *name == key)?;
+        format!("**{}**\n\n{}", key, doc)
+    };
+    Some(Hover {
+        contents: HoverContents::Markup(markdown(&doc)),
+        range,
+    })
+}
+
+/// Wraps text as Markdown.
+fn markdown(text: &str) -> MarkupContent {
+    MarkupContent {
+        kind: MarkupKind::Markdown,
+        value: text.to_string(),
+    }
+}
+
+/// Returns the kind of a table by the name in its header.
+fn table_kind(table: &str) -> Option<TableKind> {
+    let last = table.rsplit('.').next().unwrap_or(table);
+    match table {
+        "package" | "workspace.package" => Some(TableKind::Package),
+        "lib" => Some(TableKind::Target),
+        "workspace" => Some(TableKind::Workspace),
+        _ if ARRAY_TABLES.contains(&table) => Some(TableKind::Target),
+        _ if table.starts_with("profile.") => Some(TableKind::Profile),
+        _ if table.starts_with("lints.") || table.starts_with("workspace.lints.") => {
+            Some(TableKind::Lints)
+        }
+        _ if DEPENDENCY_TABLES.contains(&last) || table.starts_with("patch.") => {
+            Some(TableKind::Dependencies)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the kind of table whose keys belong after `before`, the start of a
+/// line: at the start of an entry, or inside its inline table for dependencies
+/// and lints.
+fn key_kind(table: &str, before: &str) -> Option<TableKind> {
+    let kind = table_kind(table)?;
+    let at_key = match kind.is_inline() {
+        true => before.contains('{'),
+        false => !before.contains('='),
+    };
+    at_key.then_some(kind)
+}
+
+/// Finds the table of the cursor's line, the line itself and the cursor's byte
+/// offset in it.
+fn context(text: &str, position: Position) -> (String, &str, usize) {
+    let mut table = String::new();
+    let mut current = "";
+    for (number, line) in text.lines().enumerate() {
+        if number == position.line as usize {
+            current = line;
+            break;
+        }
+        if let Some(name) = header(line) {
+            table = name;
+        }
+    }
+    let column = current
+        .char_indices()
+        .scan(0, |units, (index, c)| {
+            let before = *units;
+            *units += c.len_utf16() as u32;
+            Some((index, before))
+        })
+        .find(|&(_, units)| units >= position.character)
+        .map_or(current.len(), |(index, _)| index);
+    (table, current, column)
+}
+
+/// Returns the table name of a `[header]` or `[[header]]` line without spaces
+/// around its dots.
+fn header(line: &str) -> Option<String> {
+    let name = line.trim_start().strip_prefix('[')?;
+    let name = name.strip_prefix('[').unwrap_or(name);
+    let name = &name[..name.find(']').unwrap_or(name.len())];
+    let parts: Vec<&str> = name.split('.').map(str::trim).collect();
+    Some(parts.join("."))
+}
+
+/// Returns the key of a `key = value` line with its byte offset.
+fn line_key(line: &str) -> Option<(&str, usize)> {
+    if header(line).is_some() || line.trim_start().starts_with('#') {
+        return None;
+    }
+    let key = line[..line.find('=')?].trim();
+    let start = line.find(key)?;
+    (!key.is_empty()).then_some((key, start))
+}
+
+/// Returns the value of `field = value` within a line, without quotes.
+fn field_value<'a>(line: &'a str, field: &str) -> Option<&'a str> {
+    let mut offset = 0;
+    while let Some(index) = line[offset..].find(field) {
+        let start = offset + index;
+        offset = start + field.len();
+        let before = line[..start].chars().next_back();
+        if before.is_some_and(is_key_char) || before == Some('.') {
+            continue;
+        }
+        let Some(value) = line[offset..].trim_start().strip_prefix('=') else {
+            continue;
+        };
+        let value = value.trim_start();
+        return match value.strip_prefix('"') {
+            Some(quoted) => Some(&quoted[..quoted.find('"')?]),
+            None => value.split([',', '}', ' ']).next(),
+        };
+    }
+    None
+}
+
+/// Returns the byte range of the key-like word at a byte offset of a line.
+fn word(line: &str, column: usize) -> Option<(usize, usize)> {
+    let start = line[..column]
+        .char_indices()
+        .rev()
+        .take_while(|&(_, c)| is_key_char(c))
+        .last()
+        .map_or(column, |(index, _)| index);
+    let end = line[column..]
+        .char_indices()
+        .find(|&(_, c)| !is_key_char(c))
+        .map_or(line.len(), |(index, _)| column + index);
+    (start < end).then_some((start, end))
+}
+
+/// Checks whether a character may be part of a bare key.
+fn is_key_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+/// Locates an entry of a table, or with no `key` the header of the table or of
+/// the first table below it.
+fn find(text: &str, table: &str, key: Option<&str>) -> Option<Range> {
+    let mut current = None;
+    for (number, line) in text.lines().enumerate() {
+        if let Some(name) = header(line) {
+            let inside = name == table || name.starts_with(&format!("{}.", table));
+            if inside && key.is_none() {
+                let start = line.find('[')?;
+                let end = line.rfind(']')? + 1;
+                return Some(line_range(number, line, start, end));
+            }
+            current = Some(name);
+            continue;
+        }
+        let Some((found, start)) = line_key(line) else {
+            continue;
+        };
+        let name = found.split('.').next().unwrap_or(found).trim();
+        if current.as_deref() == Some(table) && Some(name) == key {
+            return Some(line_range(number, line, start, start + name.len()));
+        }
+    }
+    None
+}
+
+/// Converts a byte range of a line into an LSP range.
+fn line_range(number: usize, line: &str, start: usize, end: usize) -> Range {
+    Range::new(
+        Position::new(number as u32, utf16_len(&line[..start])),
+        Position::new(number as u32, utf16_len(&line[..end])),
+    )
+}
+
+/// Converts a byte offset of a text into an LSP position, which counts UTF-16 code units.
+fn position(text: &str, offset: usize) -> Position {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    Position::new(
+        before.matches('\n').count() as u32,
+        utf16_len(&before[line_start..]),
+    )
+}
+
+/// Counts the UTF-16 code units of a text.
+fn utf16_len(text: &str) -> u32 {
+    text.encode_utf16().count() as u32
+}
+
+/// Returns the path of a `file:` URI.
+fn uri_path(uri: &Uri) -> Option<PathBuf> {
+    let path = uri.as_str().strip_prefix("file://")?;
+    let path = path.strip_prefix("localhost").unwrap_or(path);
+    let mut bytes = Vec::new();
+    let mut rest = path.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        match (byte, tail.get(..2)) {
+            (b'%', Some(hex)) => {
+                bytes.push(u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?);
+                rest = &tail[2..];
+            }
+            _ => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    let path = String::from_utf8(bytes).ok()?;
+    // `file:///C:/dir` names `C:/dir` on Windows
+    match cfg!(windows) && path.get(2..3) == Some(":") {
+        true => Some(PathBuf::from(&path[1..])),
+        false => Some(PathBuf::from(path)),
+    }
+}
+
+/// Returns the `file:` URI of an absolute path.
+fn file_uri(path: &Path) -> Option<Uri> {
+    let path = path.to_str()?.replace('\\', "/");
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    Uri::from_str(&uri).ok()
+}
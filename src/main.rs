@@ -1,8 +1,23 @@
 use anyhow::Result;
-use clap::{Parser, Subcommand};
-use manifest_parser_rs::Manifest;
+use clap::{Parser, Subcommand, ValueEnum};
+use indexmap::IndexMap;
+use lsp_server::Connection;
+use manifest_parser_rs::query::is_wildcard_query;
+use manifest_parser_rs::{
+    add_dependency, apply_fixes, apply_patches, format_manifest, lint_args, merge_manifests,
+    remove_dependency, render_tree, serve, set_value, to_github_annotations, to_sarif,
+    unified_diff, unset_value, workspace_members, CargoFormat, CfgSet, Dependency, DependencyGraph,
+    DependencyKind, Diagnostic, Formats, LintLevel, Linter, Lockfile, Manifest, ManifestCache,
+    ManifestError, Override, TreeOptions, Value, Watcher,
+};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
 
 /// Manifest Parser CLI - A tool for parsing and inspecting manifest files
 #[derive(Parser)]
@@ -23,6 +38,9 @@ use std::path::PathBuf;
 \x1b[1mEXAMPLES:\x1b[0m
     manifest_parser_rs parse Cargo.toml
     manifest_parser_rs get-by-key Cargo.toml package version
+    manifest_parser_rs check --recursive .
+    manifest_parser_rs query Cargo.toml 'dependencies.*.version'
+    manifest_parser_rs set Cargo.toml package.version 1.2.0
 
 \x1b[1mSUPPORT:\x1b[0m
     Official Repo: https://github.com/Official-Echo/manifest_parser_rs
@@ -34,6 +52,15 @@ use std::path::PathBuf;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// Find every Cargo.toml below directory arguments instead of the one in them
+    #[arg(short, long, global = true)]
+    recursive: bool,
+    /// Keep parsed manifests in DIR and reuse them while their content is unchanged
+    #[arg(long, global = true, value_name = "DIR")]
+    cache: Option<PathBuf>,
+    /// Size limit of the cache in MiB; the least recently used manifests are removed first
+    #[arg(long, global = true, value_name = "MIB", default_value_t = 64)]
+    cache_size: u64,
 }
 
 #[derive(Subcommand)]
@@ -41,34 +68,298 @@ enum Commands {
     /// Parse and display the contents of a manifest file
     #[command(visible_alias = "p")]
     Parse {
-        /// Path to the manifest file to parse
-        #[arg(value_name = "FILE", help_heading = "ARGUMENTS")]
-        file: PathBuf,
+        /// Manifests to parse, `-` for stdin or directories holding a Cargo.toml
+        #[arg(value_name = "FILE", help_heading = "ARGUMENTS", required = true)]
+        files: Vec<PathBuf>,
+        /// How to print the parsed manifest
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+        /// Parse again whenever a manifest or workspace member changes
+        #[arg(long)]
+        watch: bool,
     },
 
     /// Extract a specific value by section and key
-    #[command(visible_alias = "get")]
+    #[command(
+        visible_alias = "get",
+        override_usage = "manifest_parser_rs get-by-key [OPTIONS] <FILE>... <SECTION> <KEY>"
+    )]
     GetByKey {
-        /// Path to the manifest file
-        #[arg(value_name = "FILE", help_heading = "ARGUMENTS")]
-        file: PathBuf,
-        /// Section name to search in
-        #[arg(value_name = "SECTION")]
-        section: String,
-        /// Key to look up
-        #[arg(value_name = "KEY")]
-        key: String,
+        /// Manifests to read, `-` for stdin or directories holding a Cargo.toml,
+        /// followed by the section name to search in and the key to look up
+        #[arg(value_names = ["FILE", "SECTION", "KEY"], help_heading = "ARGUMENTS", num_args = 3.., required = true)]
+        args: Vec<String>,
+        /// How to print the value
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
     },
 
     /// Get all key-value pairs from a section
     #[command(visible_alias = "section")]
     GetBySection {
-        /// Path to the manifest file
-        #[arg(value_name = "FILE", help_heading = "ARGUMENTS")]
-        file: PathBuf,
+        /// Manifests to read, `-` for stdin or directories holding a Cargo.toml
+        #[arg(value_name = "FILE", help_heading = "ARGUMENTS", required = true)]
+        files: Vec<PathBuf>,
         /// Section to display
         #[arg(value_name = "SECTION")]
         section: String,
+        /// How to print the section
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+
+    /// Find values by path, e.g. `dependencies.*.version`
+    #[command(visible_alias = "q")]
+    Query {
+        /// Manifests to query, `-` for stdin or directories holding a Cargo.toml
+        #[arg(value_name = "FILE", help_heading = "ARGUMENTS", required = true)]
+        files: Vec<PathBuf>,
+        /// Dotted path with `*` wildcards and `[n]` indexes
+        #[arg(value_name = "PATH", help_heading = "ARGUMENTS")]
+        path: String,
+        /// How to print the values
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+
+    /// Set a value by path, adding the entry or table if needed
+    #[command(visible_alias = "s")]
+    Set {
+        /// Path to the manifest file, or `-` to edit stdin to stdout
+        #[arg(value_name = "FILE", help_heading = "ARGUMENTS")]
+        file: PathBuf,
+        /// Dotted path of the entry, e.g. `package.version`
+        #[arg(value_name = "PATH", help_heading = "ARGUMENTS")]
+        path: String,
+        /// New value in TOML syntax; text that is not TOML, or a version such as 1.70, is stored as a string
+        #[arg(value_name = "VALUE", help_heading = "ARGUMENTS")]
+        value: String,
+        /// Print the change as a diff instead of writing the file
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Remove an entry or a whole table by path
+    #[command(visible_alias = "u")]
+    Unset {
+        /// Path to the manifest file, or `-` to edit stdin to stdout
+        #[arg(value_name = "FILE", help_heading = "ARGUMENTS")]
+        file: PathBuf,
+        /// Dotted path of the entry or table, e.g. `profile.release`
+        #[arg(value_name = "PATH", help_heading = "ARGUMENTS")]
+        path: String,
+        /// Print the change as a diff instead of writing the file
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Add a dependency, or update it if it is already declared
+    #[command(visible_alias = "dep")]
+    Add {
+        /// Path to the manifest file, or `-` to edit stdin to stdout
+        #[arg(value_name = "FILE", help_heading = "ARGUMENTS")]
+        file: PathBuf,
+        /// The dependency and its version requirement, e.g. `serde@1.0`
+        #[arg(value_name = "DEP", help_heading = "ARGUMENTS")]
+        dependency: String,
+        /// Add to `[dev-dependencies]`
+        #[arg(long, conflicts_with = "build")]
+        dev: bool,
+        /// Add to `[build-dependencies]`
+        #[arg(long)]
+        build: bool,
+        /// Features to enable, separated by commas
+        #[arg(long, value_delimiter = ',')]
+        features: Vec<String>,
+        /// Mark the dependency as optional
+        #[arg(long)]
+        optional: bool,
+        /// Print the change as a diff instead of writing the file
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Remove a dependency
+    #[command(visible_alias = "remove")]
+    Rm {
+        /// Path to the manifest file, or `-` to edit stdin to stdout
+        #[arg(value_name = "FILE", help_heading = "ARGUMENTS")]
+        file: PathBuf,
+        /// The key the dependency is declared under
+        #[arg(value_name = "DEP", help_heading = "ARGUMENTS")]
+        dependency: String,
+        /// Remove from `[dev-dependencies]`
+        #[arg(long, conflicts_with = "build")]
+        dev: bool,
+        /// Remove from `[build-dependencies]`
+        #[arg(long)]
+        build: bool,
+        /// Print the change as a diff instead of writing the file
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Check that Cargo.lock is up to date with one or more manifests
+    #[command(visible_alias = "lock")]
+    CheckLock {
+        /// Manifests sharing the lockfile, e.g. every workspace member
+        #[arg(value_name = "FILE", help_heading = "ARGUMENTS", required = true)]
+        files: Vec<PathBuf>,
+        /// Lockfile to check, defaults to Cargo.lock next to the first manifest
+        #[arg(long, value_name = "LOCKFILE")]
+        lockfile: Option<PathBuf>,
+    },
+
+    /// Print workspace members in the order they have to be published
+    #[command(visible_alias = "order")]
+    PublishOrder {
+        /// Manifests of every workspace member
+        #[arg(value_name = "FILE", help_heading = "ARGUMENTS", required = true)]
+        files: Vec<PathBuf>,
+    },
+
+    /// Show the dependency tree of a package or workspace
+    #[command(visible_alias = "t")]
+    Tree {
+        /// Manifests of the package or of every workspace member
+        #[arg(value_name = "FILE", help_heading = "ARGUMENTS", required = true)]
+        files: Vec<PathBuf>,
+        /// Lockfile to resolve dependencies with, defaults to Cargo.lock next to the first manifest
+        #[arg(long, value_name = "LOCKFILE")]
+        lockfile: Option<PathBuf>,
+        /// Show only this workspace member
+        #[arg(short, long, value_name = "NAME")]
+        package: Option<String>,
+        /// Kinds of dependencies to show
+        #[arg(short, long, value_enum, value_delimiter = ',', default_values_t = [EdgeKind::Normal, EdgeKind::Build, EdgeKind::Dev])]
+        edges: Vec<EdgeKind>,
+        /// Levels to show below the roots
+        #[arg(long, value_name = "DEPTH")]
+        depth: Option<usize>,
+        /// Show the packages depending on this package
+        #[arg(short, long, value_name = "NAME")]
+        invert: Option<String>,
+        /// Show only packages locked in more than one version and what depends on them
+        #[arg(short, long, conflicts_with = "invert")]
+        duplicates: bool,
+        /// Only show dependencies that apply to this target triple
+        #[arg(long, value_name = "TRIPLE")]
+        target: Option<String>,
+    },
+
+    /// List the dependencies of manifests of any supported format
+    #[command(visible_alias = "ls")]
+    Deps {
+        /// Manifests to read, `-` for stdin or directories holding a Cargo.toml
+        #[arg(value_name = "FILE", help_heading = "ARGUMENTS", required = true)]
+        files: Vec<PathBuf>,
+        /// Format of the manifests, detected from their file names by default
+        #[arg(long, value_name = "ID")]
+        manifest_format: Option<String>,
+        /// How to print the packages
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+
+    /// Show which dependencies are patched or replaced and which patches are unused
+    #[command(visible_alias = "patch")]
+    Patches {
+        /// Workspace root manifest holding the patches, followed by member manifests
+        #[arg(value_name = "FILE", help_heading = "ARGUMENTS", required = true)]
+        files: Vec<PathBuf>,
+        /// Fail when a patch or replacement is not used
+        #[arg(long)]
+        deny_unused: bool,
+    },
+
+    /// Print the compiler flags equivalent to the [lints] tables
+    #[command(visible_alias = "lints")]
+    LintFlags {
+        /// Manifests to read, `-` for stdin or directories holding a Cargo.toml
+        #[arg(value_name = "FILE", help_heading = "ARGUMENTS", required = true)]
+        files: Vec<PathBuf>,
+        /// Workspace root manifest to take lints from with `[lints] workspace = true`
+        #[arg(long, value_name = "ROOT")]
+        workspace: Option<PathBuf>,
+    },
+
+    /// Check a manifest for common problems
+    #[command(visible_alias = "c")]
+    Check {
+        /// Manifests to check, `-` for stdin or directories holding a Cargo.toml
+        #[arg(value_name = "FILE", help_heading = "ARGUMENTS", required = true)]
+        files: Vec<PathBuf>,
+        /// Disable a rule
+        #[arg(short = 'A', long = "allow", value_name = "RULE")]
+        allow: Vec<String>,
+        /// Report a rule as a warning
+        #[arg(short = 'W', long = "warn", value_name = "RULE")]
+        warn: Vec<String>,
+        /// Report a rule as an error
+        #[arg(short = 'D', long = "deny", value_name = "RULE")]
+        deny: Vec<String>,
+        /// Apply automatic fixes to the file
+        #[arg(long)]
+        fix: bool,
+        /// How to print diagnostics
+        #[arg(long, value_enum, default_value_t = MessageFormat::Text)]
+        message_format: MessageFormat,
+        /// Check again whenever a manifest or workspace member changes
+        #[arg(long)]
+        watch: bool,
+    },
+
+    /// Rewrite manifests into the canonical layout
+    #[command(visible_alias = "format")]
+    Fmt {
+        /// Manifests to format in place, or `-` to format stdin to stdout
+        #[arg(value_name = "FILE", help_heading = "ARGUMENTS", required = true)]
+        files: Vec<PathBuf>,
+        /// Only check the layout, failing if a manifest would change
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Show what changed between two versions of a manifest
+    #[command(visible_alias = "d")]
+    Diff {
+        /// The old manifest
+        #[arg(value_name = "OLD", help_heading = "ARGUMENTS")]
+        old: PathBuf,
+        /// The new manifest
+        #[arg(value_name = "NEW", help_heading = "ARGUMENTS")]
+        new: PathBuf,
+        /// How to print the changes
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+
+    /// Merge two versions of a manifest with their common ancestor, e.g. as a git merge driver
+    #[command(visible_alias = "m")]
+    Merge {
+        /// The common ancestor (`%O` in a git merge driver)
+        #[arg(value_name = "BASE", help_heading = "ARGUMENTS")]
+        base: PathBuf,
+        /// Our version, overwritten with the merge result (`%A`)
+        #[arg(value_name = "OURS", help_heading = "ARGUMENTS")]
+        ours: PathBuf,
+        /// Their version (`%B`)
+        #[arg(value_name = "THEIRS", help_heading = "ARGUMENTS")]
+        theirs: PathBuf,
+        /// Print the result instead of overwriting OURS
+        #[arg(long)]
+        stdout: bool,
+    },
+
+    /// Run a language server for Cargo.toml over stdin and stdout
+    #[command(visible_alias = "language-server")]
+    Lsp,
+
+    /// Remove every manifest from a parse cache
+    ClearCache {
+        /// The cache directory given to --cache
+        #[arg(value_name = "DIR", help_heading = "ARGUMENTS")]
+        dir: PathBuf,
     },
 
     /// Show information about the authors
@@ -76,50 +367,606 @@ enum Commands {
     Authors,
 }
 
+/// Output formats of reported diagnostics
+#[derive(Clone, Copy, ValueEnum)]
+enum MessageFormat {
+    /// One diagnostic per line
+    Text,
+    /// A SARIF 2.1.0 log for code scanning
+    Sarif,
+    /// GitHub workflow annotations
+    Github,
+}
+
+/// Kinds of dependencies in a tree
+#[derive(Clone, Copy, ValueEnum)]
+enum EdgeKind {
+    /// `[dependencies]`
+    Normal,
+    /// `[build-dependencies]`
+    Build,
+    /// `[dev-dependencies]`
+    Dev,
+}
+
+/// Output formats of structured results
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    /// Human readable text
+    Text,
+    /// JSON
+    Json,
+    /// YAML
+    Yaml,
+    /// TOML
+    Toml,
+}
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    if let Some(dir) = &cli.cache {
+        let cache =
+            ManifestCache::open(dir)?.with_max_size(cli.cache_size.saturating_mul(1024 * 1024));
+        CACHE.set(cache).expect("the cache is opened once");
+    }
 
     match &cli.command {
-        Commands::Parse { file } => {
-            let content = fs::read_to_string(file)
-                .map_err(|e| anyhow::anyhow!("Failed to read file {}: {}", file.display(), e))?;
+        Commands::Parse {
+            files,
+            format,
+            watch,
+        } => {
+            let inputs = expand_inputs(files, cli.recursive)?;
+            let parse = |inputs: &[PathBuf]| -> Result<()> {
+                let (parsed, failures) =
+                    process_inputs(inputs, |_, content| parse_manifest(&content))?;
 
-            let manifest = Manifest::parse(&content)
-                .map_err(|e| anyhow::anyhow!("Failed to parse manifest: {}", e))?;
+                match format {
+                    OutputFormat::Text => {
+                        for (name, manifest) in &parsed {
+                            if inputs.len() == 1 {
+                                println!("Parsed manifest sections:");
+                            } else {
+                                println!("Parsed manifest sections of {}:", name);
+                            }
+                            for section in manifest.sections() {
+                                println!("- {}", section);
+                            }
+                        }
+                    }
+                    _ => print_results(parsed, inputs.len(), *format)?,
+                }
+                finish(inputs.len(), failures)
+            };
+            run_or_watch(inputs, *watch, parse)?;
+        }
+        Commands::GetByKey { args, format } => {
+            // Clap allows only the last positional argument to repeat, so the
+            // manifests are everything before the section and key
+            let [files @ .., section, key] = args.as_slice() else {
+                unreachable!("clap requires at least three arguments");
+            };
+            let files: Vec<PathBuf> = files.iter().map(PathBuf::from).collect();
+            let inputs = expand_inputs(&files, cli.recursive)?;
+            let (values, failures) = process_inputs(&inputs, |_, content| {
+                let value = parse_manifest(&content)?
+                    .get_by_key(section, key)?
+                    .to_string();
+                Ok(BTreeMap::from([(key.clone(), value)]))
+            })?;
+
+            match format {
+                OutputFormat::Text => {
+                    for (name, value) in &values {
+                        if inputs.len() > 1 {
+                            print!("{}: ", name);
+                        }
+                        println!("{} = {}", key, value[key]);
+                    }
+                }
+                _ => print_results(values, inputs.len(), *format)?,
+            }
+            finish(inputs.len(), failures)?;
+        }
+        Commands::GetBySection {
+            files,
+            section,
+            format,
+        } => {
+            let inputs = expand_inputs(files, cli.recursive)?;
+            let (sections, failures) = process_inputs(&inputs, |_, content| {
+                Ok(parse_manifest(&content)?.get_by_section(section)?.clone())
+            })?;
 
-            println!("Parsed manifest sections:");
-            for section in manifest.sections() {
-                println!("- {}", section);
+            match format {
+                OutputFormat::Text => {
+                    for (name, section_map) in &sections {
+                        if inputs.len() == 1 {
+                            println!("Values in section [{}]:", section);
+                        } else {
+                            println!("Values in section [{}] of {}:", section, name);
+                        }
+                        for (key, value) in section_map {
+                            println!("{} = {}", key, value);
+                        }
+                    }
+                }
+                _ => print_results(sections, inputs.len(), *format)?,
             }
+            finish(inputs.len(), failures)?;
         }
-        Commands::GetByKey { file, section, key } => {
-            let content = fs::read_to_string(file)
-                .map_err(|e| anyhow::anyhow!("Failed to read file {}: {}", file.display(), e))?;
+        Commands::CheckLock { files, lockfile } => {
+            let inputs = expand_inputs(files, cli.recursive)?;
+            let manifests = read_manifests(&inputs)?;
 
-            let manifest = Manifest::parse(&content)
-                .map_err(|e| anyhow::anyhow!("Failed to parse manifest: {}", e))?;
+            let lock_path = lockfile
+                .clone()
+                .unwrap_or_else(|| inputs[0].with_file_name("Cargo.lock"));
+            let content = read_input(&lock_path)?;
+            let lock = Lockfile::parse(&content)
+                .map_err(|e| anyhow::anyhow!("Failed to parse lockfile: {}", e))?;
 
-            match manifest.get_by_key(section, key) {
-                Ok(value) => println!("{} = {}", key, value),
-                Err(e) => println!("Error: {}", e),
+            let issues = lock.check(&manifests);
+            for issue in &issues {
+                println!("- {}", issue);
             }
+            if !issues.is_empty() {
+                anyhow::bail!(
+                    "{} is out of date ({} problems)",
+                    lock_path.display(),
+                    issues.len()
+                );
+            }
+            println!("{} is up to date", lock_path.display());
         }
-        Commands::GetBySection { file, section } => {
-            let content = fs::read_to_string(file)
-                .map_err(|e| anyhow::anyhow!("Failed to read file {}: {}", file.display(), e))?;
+        Commands::PublishOrder { files } => {
+            let manifests = read_manifests(&expand_inputs(files, cli.recursive)?)?;
+            let graph = DependencyGraph::new(&manifests);
 
-            let manifest = Manifest::parse(&content)
-                .map_err(|e| anyhow::anyhow!("Failed to parse manifest: {}", e))?;
+            for member in graph.topological_order()? {
+                println!("{}", member);
+            }
+        }
+        Commands::Tree {
+            files,
+            lockfile,
+            package,
+            edges,
+            depth,
+            invert,
+            duplicates,
+            target,
+        } => {
+            let inputs = expand_inputs(files, cli.recursive)?;
+            let manifests = read_manifests(&inputs)?;
+
+            let lock = match lockfile {
+                Some(path) => Some(read_input(path)?),
+                None => fs::read_to_string(inputs[0].with_file_name("Cargo.lock")).ok(),
+            };
+            let lock = lock
+                .map(|content| Lockfile::parse(&content))
+                .transpose()
+                .map_err(|e| anyhow::anyhow!("Failed to parse lockfile: {}", e))?;
+
+            let target = match target {
+                Some(triple) => Some(CfgSet::for_target(triple).ok_or_else(|| {
+                    let known: Vec<_> = CfgSet::bundled_targets().collect();
+                    anyhow::anyhow!(
+                        "Unknown target {}, known targets: {}",
+                        triple,
+                        known.join(", ")
+                    )
+                })?),
+                None => None,
+            };
+            let options = TreeOptions {
+                kinds: edges
+                    .iter()
+                    .map(|edge| match edge {
+                        EdgeKind::Normal => DependencyKind::Normal,
+                        EdgeKind::Build => DependencyKind::Build,
+                        EdgeKind::Dev => DependencyKind::Dev,
+                    })
+                    .collect(),
+                depth: *depth,
+                invert: invert.clone(),
+                duplicates: *duplicates,
+                target,
+                package: package.clone(),
+            };
+
+            print!("{}", render_tree(&manifests, lock.as_ref(), &options)?);
+        }
+        Commands::Deps {
+            files,
+            manifest_format,
+            format,
+        } => {
+            let formats = Formats::new();
+            let forced = manifest_format
+                .as_deref()
+                .map(|id| formats.get(id))
+                .transpose()?;
+            let inputs = expand_inputs(files, cli.recursive)?;
+            let (packages, failures) = process_inputs(&inputs, |file, content| {
+                // Manifests with names no format claims are read as Cargo.toml
+                let format = forced
+                    .or_else(|| formats.detect(file))
+                    .unwrap_or(&CargoFormat);
+                format
+                    .parse(&content)
+                    .map_err(|e| anyhow::anyhow!("Failed to parse manifest: {}", e))
+            })?;
+
+            match format {
+                OutputFormat::Text => {
+                    for (name, package) in &packages {
+                        if inputs.len() > 1 {
+                            println!("{}:", name);
+                        }
+                        for dependency in &package.dependencies {
+                            println!("{}", dependency);
+                        }
+                    }
+                }
+                _ => print_results(packages, inputs.len(), *format)?,
+            }
+            finish(inputs.len(), failures)?;
+        }
+        Commands::Patches { files, deny_unused } => {
+            let manifests = read_manifests(&expand_inputs(files, cli.recursive)?)?;
+            let patches: Vec<_> = manifests[0].patches().cloned().collect();
+            let replacements: Vec<_> = manifests[0].replacements().cloned().collect();
+            let report = apply_patches(
+                manifests
+                    .iter()
+                    .flat_map(|manifest| manifest.dependencies()),
+                &patches,
+                &replacements,
+            );
 
-            match manifest.get_by_section(section) {
-                Ok(section_map) => {
-                    println!("Values in section [{}]:", section);
-                    for (key, value) in section_map {
-                        println!("{} = {}", key, value);
+            for patched in &report.dependencies {
+                let origin = match patched.overridden_by {
+                    Some(Override::Patch(index)) => format!("patch.{}", patches[index].source),
+                    Some(Override::Replace(index)) => format!(
+                        "replace {}:{}",
+                        replacements[index].package, replacements[index].version
+                    ),
+                    None => continue,
+                };
+                let dependency = &patched.dependency;
+                let target = dependency
+                    .path
+                    .as_ref()
+                    .or(dependency.git.as_ref())
+                    .or(dependency.registry.as_ref())
+                    .map(String::as_str)
+                    .unwrap_or("crates.io");
+                println!("{} -> {} ({})", dependency.name, target, origin);
+            }
+            for &index in &report.unused_patches {
+                let patch = &patches[index];
+                println!(
+                    "unused: patch.{} {}",
+                    patch.source,
+                    patch.replacement.package_name()
+                );
+            }
+            for &index in &report.unused_replacements {
+                let replacement = &replacements[index];
+                println!(
+                    "unused: replace {}:{}",
+                    replacement.package, replacement.version
+                );
+            }
+
+            let unused = report.unused_patches.len() + report.unused_replacements.len();
+            if *deny_unused && unused > 0 {
+                anyhow::bail!("{} unused patches", unused);
+            }
+        }
+        Commands::LintFlags { files, workspace } => {
+            let root = workspace.as_deref().map(read_manifest).transpose()?;
+            let inputs = expand_inputs(files, cli.recursive)?;
+            let (flags, failures) = process_inputs(&inputs, |_, content| {
+                let manifest = parse_manifest(&content)?;
+                let args = match &root {
+                    Some(root) if manifest.inherits_workspace_lints() => {
+                        lint_args(root.workspace_lints())
                     }
+                    _ => lint_args(manifest.lints()),
+                };
+                Ok(args.join(" "))
+            })?;
+
+            for (name, args) in &flags {
+                if inputs.len() > 1 {
+                    println!("{}: {}", name, args);
+                } else {
+                    println!("{}", args);
                 }
-                Err(e) => println!("Error: {}", e),
             }
+            finish(inputs.len(), failures)?;
+        }
+        Commands::Check {
+            files,
+            allow,
+            warn,
+            deny,
+            fix,
+            message_format,
+            watch,
+        } => {
+            let linter = || -> Result<Linter> {
+                let mut linter = Linter::new();
+                for (rules, level) in [
+                    (allow, LintLevel::Allow),
+                    (warn, LintLevel::Warn),
+                    (deny, LintLevel::Deny),
+                ] {
+                    for rule in rules {
+                        linter.set_level(rule, level)?;
+                    }
+                }
+                Ok(linter)
+            };
+            linter()?;
+
+            let inputs = expand_inputs(files, cli.recursive)?;
+            let check = |inputs: &[PathBuf]| -> Result<()> {
+                let (checked, failures) = process_inputs(inputs, |file, mut content| {
+                    let linter = linter()?;
+                    let mut diagnostics = match parse_cached(&content) {
+                        Ok(manifest) => linter.check(&manifest, &content),
+                        Err(e) => vec![Diagnostic::from_error(&e, &content)],
+                    };
+
+                    if *fix {
+                        if is_stdin(file) {
+                            anyhow::bail!("--fix cannot write back to stdin");
+                        }
+                        // Overlapping fixes are deferred, so repeat until nothing changes
+                        let original = content.clone();
+                        for _ in 0..MAX_FIX_PASSES {
+                            let fixed = apply_fixes(&content, &diagnostics);
+                            if fixed == content {
+                                break;
+                            }
+                            let manifest = Manifest::parse(&fixed).map_err(|e| {
+                                anyhow::anyhow!("Failed to parse fixed manifest: {}", e)
+                            })?;
+                            content = fixed;
+                            diagnostics = linter.check(&manifest, &content);
+                        }
+                        if content != original {
+                            write_output(file, &content)?;
+                        }
+                    }
+                    Ok(diagnostics)
+                })?;
+
+                match message_format {
+                    MessageFormat::Text => {
+                        for (path, diagnostics) in &checked {
+                            for diagnostic in diagnostics {
+                                println!("{}: {}", path, diagnostic);
+                            }
+                        }
+                    }
+                    MessageFormat::Sarif => println!(
+                        "{}",
+                        to_sarif(
+                            checked
+                                .iter()
+                                .map(|(path, diagnostics)| (path.as_str(), &diagnostics[..]))
+                        )
+                    ),
+                    MessageFormat::Github => {
+                        for (path, diagnostics) in &checked {
+                            print!("{}", to_github_annotations(path, diagnostics));
+                        }
+                    }
+                }
+                finish(inputs.len(), failures)?;
+
+                let errors = checked
+                    .iter()
+                    .flat_map(|(_, diagnostics)| diagnostics)
+                    .filter(|d| d.is_error())
+                    .count();
+                match checked.as_slice() {
+                    [(path, _)] if errors > 0 => anyhow::bail!("{} errors in {}", errors, path),
+                    _ if errors > 0 => {
+                        anyhow::bail!("{} errors in {} manifests", errors, checked.len())
+                    }
+                    _ => {}
+                }
+                Ok(())
+            };
+            run_or_watch(inputs, *watch, check)?;
+        }
+        Commands::Fmt { files, check } => {
+            let inputs = expand_inputs(files, cli.recursive)?;
+            let (formatted, failures) = process_inputs(&inputs, |file, content| {
+                let formatted = format_manifest(&content).map_err(|e| {
+                    anyhow::anyhow!("Failed to parse manifest {}: {}", display_name(file), e)
+                })?;
+                let changed = formatted != content;
+
+                if *check {
+                    if changed {
+                        println!("{} is not formatted", display_name(file));
+                    }
+                } else if is_stdin(file) {
+                    print!("{}", formatted);
+                } else if changed {
+                    write_output(file, &formatted)?;
+                    println!("formatted {}", display_name(file));
+                }
+                Ok(changed)
+            })?;
+            finish(inputs.len(), failures)?;
+
+            let unformatted = formatted.iter().filter(|(_, changed)| *changed).count();
+            if *check && unformatted > 0 {
+                anyhow::bail!("{} manifests are not formatted", unformatted);
+            }
+        }
+        Commands::Query {
+            files,
+            path,
+            format,
+        } => {
+            let wildcard = is_wildcard_query(path)?;
+            let inputs = expand_inputs(files, cli.recursive)?;
+            let (found, failures) = process_inputs(&inputs, |_, content| {
+                let matches = parse_manifest(&content)?.query(path)?;
+                if matches.is_empty() {
+                    anyhow::bail!("No value matches {}", path);
+                }
+                Ok(matches)
+            })?;
+
+            match format {
+                OutputFormat::Text => {
+                    for (name, matches) in &found {
+                        let prefix = if inputs.len() == 1 {
+                            String::new()
+                        } else {
+                            format!("{}: ", name)
+                        };
+                        for found in matches {
+                            match &found.value {
+                                Value::String(string) if !wildcard => {
+                                    println!("{}{}", prefix, string)
+                                }
+                                value if !wildcard => println!("{}{}", prefix, value),
+                                value => println!("{}{} = {}", prefix, found.path, value),
+                            }
+                        }
+                    }
+                }
+                _ => {
+                    let found = found
+                        .into_iter()
+                        .map(|(name, matches)| {
+                            let values: IndexMap<String, Value> = matches
+                                .into_iter()
+                                .map(|found| (found.path, found.value))
+                                .collect();
+                            (name, values)
+                        })
+                        .collect();
+                    print_results(found, inputs.len(), *format)?;
+                }
+            }
+            finish(inputs.len(), failures)?;
+        }
+        Commands::Set {
+            file,
+            path,
+            value,
+            dry_run,
+        } => {
+            let value = set_argument(value);
+            edit_file(file, *dry_run, |source| set_value(source, path, &value))?;
+        }
+        Commands::Unset {
+            file,
+            path,
+            dry_run,
+        } => {
+            edit_file(file, *dry_run, |source| unset_value(source, path))?;
+        }
+        Commands::Add {
+            file,
+            dependency,
+            dev,
+            build,
+            features,
+            optional,
+            dry_run,
+        } => {
+            let Some((name, version)) = dependency.split_once('@') else {
+                anyhow::bail!("Missing version requirement, e.g. {}@1.0", dependency);
+            };
+            let mut dependency = Dependency::new(name, dependency_kind(*dev, *build));
+            dependency.version = Some(version.to_string());
+            dependency.features = features.clone();
+            dependency.optional = *optional;
+            edit_file(file, *dry_run, |source| add_dependency(source, &dependency))?;
+        }
+        Commands::Rm {
+            file,
+            dependency,
+            dev,
+            build,
+            dry_run,
+        } => {
+            let table = dependency_kind(*dev, *build).table();
+            edit_file(file, *dry_run, |source| {
+                remove_dependency(source, dependency, table)
+            })?;
+        }
+        Commands::Diff { old, new, format } => {
+            // Both sides are read on their own, since the same manifest may be given twice
+            if is_stdin(old) && is_stdin(new) {
+                anyhow::bail!("stdin can only be read once");
+            }
+            let changes = read_manifest(old)?.diff(&read_manifest(new)?);
+
+            match format {
+                OutputFormat::Text if changes.is_empty() => println!("No changes"),
+                OutputFormat::Text => {
+                    for change in &changes {
+                        println!("{}", change);
+                    }
+                }
+                OutputFormat::Toml => {
+                    print_structured(&BTreeMap::from([("changes", &changes)]), *format)?
+                }
+                _ => print_structured(&changes, *format)?,
+            }
+        }
+        Commands::Merge {
+            base,
+            ours,
+            theirs,
+            stdout,
+        } => {
+            let mut contents = Vec::new();
+            for file in [base, ours, theirs] {
+                contents.push(read_input(file)?);
+            }
+            let merge = merge_manifests(&contents[0], &contents[1], &contents[2])
+                .map_err(|e| anyhow::anyhow!("Failed to parse manifest: {}", e))?;
+
+            if *stdout {
+                print!("{}", merge.manifest);
+            } else {
+                write_output(ours, &merge.manifest)?;
+            }
+            for conflict in &merge.conflicts {
+                eprintln!("conflict: {}", conflict);
+            }
+            if !merge.is_clean() {
+                anyhow::bail!("{} conflicts", merge.conflicts.len());
+            }
+        }
+        Commands::Lsp => {
+            let (connection, io_threads) = Connection::stdio();
+            serve(&connection)?;
+            drop(connection);
+            io_threads.join()?;
+        }
+        Commands::ClearCache { dir } => {
+            // Opening the cache creates its directory, which a mistyped path should not
+            if !dir.is_dir() {
+                anyhow::bail!("Cache directory {} does not exist", dir.display());
+            }
+            let removed = ManifestCache::open(dir)?.clear()?;
+            println!("Removed {} cached manifests", removed);
         }
         Commands::Authors => {
             println!("Manifest Parser");
@@ -131,3 +978,317 @@ fn main() -> Result<()> {
 
     Ok(())
 }
+
+/// Prints a value as JSON, YAML or TOML.
+fn print_structured<T: Serialize>(value: &T, format: OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(value)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(value)?),
+        OutputFormat::Toml => print!("{}", toml::to_string(value)?),
+        OutputFormat::Text => unreachable!("text output is printed by each command"),
+    }
+    Ok(())
+}
+
+/// Applies an edit to a manifest file, or prints it as a diff with `dry_run`.
+fn edit_file(
+    file: &PathBuf,
+    dry_run: bool,
+    edit: impl FnOnce(&str) -> Result<String, ManifestError>,
+) -> Result<()> {
+    let file = &expand_inputs(std::slice::from_ref(file), false)?[0];
+    let content = read_input(file)?;
+    let edited = edit(&content)?;
+
+    if dry_run {
+        print!("{}", unified_diff(&display_name(file), &content, &edited));
+    } else {
+        write_output(file, &edited)?;
+    }
+    Ok(())
+}
+
+/// Picks the dependency table selected by `--dev` and `--build`.
+fn dependency_kind(dev: bool, build: bool) -> DependencyKind {
+    match (dev, build) {
+        (true, _) => DependencyKind::Dev,
+        (_, true) => DependencyKind::Build,
+        _ => DependencyKind::Normal,
+    }
+}
+
+/// How many times `check --fix` re-runs the linter to apply deferred fixes.
+const MAX_FIX_PASSES: usize = 10;
+
+/// The file argument that stands for stdin, or for stdout when writing.
+const STDIN: &str = "-";
+
+/// The manifest looked for in directory arguments.
+const MANIFEST_NAME: &str = "Cargo.toml";
+
+/// The parse cache given with `--cache`, shared by every input of a command.
+static CACHE: OnceLock<ManifestCache> = OnceLock::new();
+
+/// Expands manifest arguments into the manifests to process.
+///
+/// `-` is kept for stdin and a directory stands for the `Cargo.toml` in it, or
+/// with `recursive` for every `Cargo.toml` below it.
+fn expand_inputs(files: &[PathBuf], recursive: bool) -> Result<Vec<PathBuf>> {
+    let mut inputs = Vec::new();
+    for file in files {
+        if file.is_dir() && recursive {
+            let mut found = Vec::new();
+            find_manifests(file, &mut found)?;
+            found.sort();
+            inputs.extend(found);
+        } else if file.is_dir() {
+            inputs.push(file.join(MANIFEST_NAME));
+        } else {
+            inputs.push(file.clone());
+        }
+    }
+    if inputs.iter().filter(|file| is_stdin(file)).count() > 1 {
+        anyhow::bail!("stdin can only be read once");
+    }
+    // A manifest named twice, e.g. by overlapping directories, is processed once
+    let mut seen = HashSet::new();
+    inputs.retain(|file| seen.insert(file.clone()));
+    Ok(inputs)
+}
+
+/// Collects every `Cargo.toml` below `dir`, skipping hidden and `target` directories.
+fn find_manifests(dir: &Path, found: &mut Vec<PathBuf>) -> Result<()> {
+    let entries = fs::read_dir(dir)
+        .map_err(|e| anyhow::anyhow!("Failed to read directory {}: {}", dir.display(), e))?;
+    for entry in entries {
+        let path = entry?.path();
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("");
+        if path.is_dir() {
+            if !name.starts_with('.') && name != "target" {
+                find_manifests(&path, found)?;
+            }
+        } else if name == MANIFEST_NAME {
+            found.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Checks whether a file argument stands for stdin.
+fn is_stdin(file: &Path) -> bool {
+    file.as_os_str() == STDIN
+}
+
+/// Returns the name of a file argument for messages.
+fn display_name(file: &Path) -> String {
+    if is_stdin(file) {
+        "<stdin>".to_string()
+    } else {
+        file.display().to_string()
+    }
+}
+
+/// Reads a file argument, or stdin for `-`.
+fn read_input(file: &Path) -> Result<String> {
+    if is_stdin(file) {
+        let mut content = String::new();
+        io::stdin()
+            .read_to_string(&mut content)
+            .map_err(|e| anyhow::anyhow!("Failed to read stdin: {}", e))?;
+        Ok(content)
+    } else {
+        fs::read_to_string(file)
+            .map_err(|e| anyhow::anyhow!("Failed to read file {}: {}", file.display(), e))
+    }
+}
+
+/// Writes a file argument, or stdout for `-`.
+fn write_output(file: &Path, content: &str) -> Result<()> {
+    if is_stdin(file) {
+        print!("{}", content);
+        Ok(())
+    } else {
+        fs::write(file, content)
+            .map_err(|e| anyhow::anyhow!("Failed to write file {}: {}", file.display(), e))
+    }
+}
+
+/// Parses manifest content, naming the failure for the command line.
+fn parse_manifest(content: &str) -> Result<Manifest> {
+    parse_cached(content).map_err(|e| anyhow::anyhow!("Failed to parse manifest: {}", e))
+}
+
+/// Parses manifest content through the `--cache` if there is one.
+fn parse_cached(content: &str) -> Result<Manifest, ManifestError> {
+    match CACHE.get() {
+        Some(cache) => cache.parse(content),
+        None => Manifest::parse(content),
+    }
+}
+
+/// Reads and parses the single manifest named by a file argument.
+fn read_manifest(file: &Path) -> Result<Manifest> {
+    let file = &expand_inputs(&[file.to_path_buf()], false)?[0];
+    parse_manifest(&read_input(file)?)
+}
+
+/// Reads the value given to `set`.
+///
+/// Text that is not TOML is a string, and so is a version such as `1.70`, which
+/// TOML would read as the float `1.7`.
+fn set_argument(text: &str) -> Value {
+    let is_version = text.split('.').count() > 1
+        && text
+            .split('.')
+            .all(|part| !part.is_empty() && part.bytes().all(|byte| byte.is_ascii_digit()));
+    match Value::parse_toml(text) {
+        Some(value) if !is_version => value,
+        _ => Value::String(text.to_string()),
+    }
+}
+
+/// Reads and parses every manifest in `inputs`, failing on the first that does not parse.
+fn read_manifests(inputs: &[PathBuf]) -> Result<Vec<Manifest>> {
+    let (manifests, failures) = process_inputs(inputs, |_, content| parse_manifest(&content))?;
+    if let Some((name, e)) = failures.into_iter().next() {
+        anyhow::bail!("{}: {}", name, e);
+    }
+    Ok(manifests
+        .into_iter()
+        .map(|(_, manifest)| manifest)
+        .collect())
+}
+
+/// Runs `task` on the content of every input in parallel.
+///
+/// # Returns
+///
+/// The results of the inputs that succeeded and the errors of those that failed,
+/// each in input order and named by `display_name`
+#[allow(clippy::type_complexity)]
+fn process_inputs<T: Send>(
+    inputs: &[PathBuf],
+    task: impl Fn(&Path, String) -> Result<T> + Sync,
+) -> Result<(Vec<(String, T)>, Vec<(String, anyhow::Error)>)> {
+    let stdin = match inputs.iter().any(|file| is_stdin(file)) {
+        true => Some(read_input(Path::new(STDIN))?),
+        false => None,
+    };
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<Result<T>>>> = Mutex::new(inputs.iter().map(|_| None).collect());
+    let workers = thread::available_parallelism()
+        .map_or(1, |workers| workers.get())
+        .min(inputs.len());
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(file) = inputs.get(index) else {
+                    break;
+                };
+                let content = match &stdin {
+                    Some(content) if is_stdin(file) => Ok(content.clone()),
+                    _ => read_input(file),
+                };
+                let result = content.and_then(|content| task(file, content));
+                results.lock().unwrap()[index] = Some(result);
+            });
+        }
+    });
+
+    let (mut succeeded, mut failed) = (Vec::new(), Vec::new());
+    for (file, result) in inputs.iter().zip(results.into_inner().unwrap()) {
+        match result.expect("every input is processed") {
+            Ok(value) => succeeded.push((display_name(file), value)),
+            Err(e) => failed.push((display_name(file), e)),
+        }
+    }
+    Ok((succeeded, failed))
+}
+
+/// Prints per-input results as JSON, YAML or TOML, keyed by input name in the
+/// order the inputs were given when there are several inputs.
+fn print_results<T: Serialize>(
+    results: Vec<(String, T)>,
+    inputs: usize,
+    format: OutputFormat,
+) -> Result<()> {
+    match results.as_slice() {
+        [] if inputs == 1 => Ok(()),
+        [(_, value)] if inputs == 1 => print_structured(value, format),
+        _ => print_structured(&results.into_iter().collect::<IndexMap<_, _>>(), format),
+    }
+}
+
+/// Runs `command` on the inputs once, or with `watch` until interrupted.
+///
+/// While watching, the workspace members of the inputs are added to them, and
+/// after each burst of changes `command` runs again on just the changed manifests.
+/// Failures are printed instead of ending the watch.
+fn run_or_watch(
+    inputs: Vec<PathBuf>,
+    watch: bool,
+    command: impl Fn(&[PathBuf]) -> Result<()>,
+) -> Result<()> {
+    if !watch {
+        return command(&inputs);
+    }
+    if inputs.iter().any(|file| is_stdin(file)) {
+        anyhow::bail!("--watch cannot read stdin");
+    }
+
+    let mut watcher = Watcher::new();
+    let mut run = inputs;
+    loop {
+        // Follow members added since the last run, e.g. by editing `members`
+        for file in &run {
+            watcher.watch(file.clone());
+        }
+        let mut index = 0;
+        while let Some(file) = run.get(index).cloned() {
+            if let Ok(manifest) = read_manifest(&file) {
+                let root = match file.parent() {
+                    Some(dir) if !dir.as_os_str().is_empty() => dir,
+                    _ => Path::new("."),
+                };
+                for member in workspace_members(&manifest, root) {
+                    if watcher.watch(member.clone()) {
+                        run.push(member);
+                    }
+                }
+            }
+            index += 1;
+        }
+
+        if let Err(e) = command(&run) {
+            eprintln!("Error: {}", e);
+        }
+        eprintln!(
+            "Watching {} manifests for changes...",
+            watcher.files().count()
+        );
+        run = watcher.wait();
+        let names: Vec<String> = run.iter().map(|file| display_name(file)).collect();
+        eprintln!("\nChanged: {}", names.join(", "));
+    }
+}
+
+/// Reports the inputs that failed and fails if there were any.
+///
+/// With a single input its error is returned unchanged.
+fn finish(inputs: usize, failures: Vec<(String, anyhow::Error)>) -> Result<()> {
+    if inputs == 1 {
+        return failures.into_iter().next().map_or(Ok(()), |(_, e)| Err(e));
+    }
+    for (name, e) in &failures {
+        eprintln!("Error: {}: {}", name, e);
+    }
+    if !failures.is_empty() {
+        anyhow::bail!("{} of {} manifests failed", failures.len(), inputs);
+    }
+    Ok(())
+}
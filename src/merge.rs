@@ -0,0 +1,362 @@
+//! Three-way merge of manifests by table and key.
+
+use crate::formatter::{Document, Item, Table};
+use crate::{Edit, ManifestError};
+use std::fmt;
+
+/// An entry that both sides changed in different ways.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// The header of the table holding the entry, e.g. `[dependencies]`
+    pub table: String,
+    /// The key of the entry
+    pub key: String,
+    /// The entry in the common ancestor, if it had one
+    pub base: Option<String>,
+    /// The entry on our side, if it was kept
+    pub ours: Option<String>,
+    /// The entry on their side, if it was kept
+    pub theirs: Option<String>,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let side = |entry: &Option<String>| entry.clone().unwrap_or_else(|| "removed".to_string());
+        write!(
+            f,
+            "{} {}: ours {}, theirs {}",
+            self.table,
+            self.key,
+            side(&self.ours),
+            side(&self.theirs)
+        )
+    }
+}
+
+/// The result of merging two manifests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Merge {
+    /// The merged manifest in the layout of our version, with conflict markers around conflicting entries
+    pub manifest: String,
+    /// The entries that could not be merged
+    pub conflicts: Vec<Conflict>,
+}
+
+impl Merge {
+    /// Checks whether the merge succeeded without conflicts.
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// Merges two manifests that diverged from a common ancestor.
+///
+/// Entries are matched by table and key rather than by line, so two sides adding
+/// different dependencies merge cleanly, while both sides changing the same entry
+/// in different ways is a conflict. The merged manifest keeps the text of our
+/// version wherever the merge left it alone: entries taken from their version
+/// are written in the layout of `format_manifest`, new entries are added at the
+/// end of their table and new tables at the end of the manifest.
+///
+/// # Arguments
+///
+/// * `base` - The common ancestor
+/// * `ours` - Our version
+/// * `theirs` - Their version
+///
+/// # Returns
+///
+/// A `Result` containing either the `Merge` or a `ManifestError` if a version does not parse
+pub fn merge_manifests(base: &str, ours: &str, theirs: &str) -> Result<Merge, ManifestError> {
+    let source = ours;
+    let base = Document::parse(base)?;
+    let ours = Document::parse(ours)?;
+    let theirs = Document::parse(theirs)?;
+    let (base_tables, our_tables, their_tables) = (
+        identify(&base.tables),
+        identify(&ours.tables),
+        identify(&theirs.tables),
+    );
+
+    let mut ids: Vec<&TableId> = Vec::new();
+    for (id, _) in our_tables.iter().chain(&their_tables).chain(&base_tables) {
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+
+    let mut conflicts = Vec::new();
+    let mut edits = Vec::new();
+    let mut added = Vec::new();
+    for id in ids {
+        let (base_table, our_table, their_table) = (
+            find_table(&base_tables, id),
+            find_table(&our_tables, id),
+            find_table(&their_tables, id),
+        );
+
+        let entries = merge_entries(base_table, our_table, their_table, &id.0, &mut conflicts);
+        let present = *pick(
+            &base_table.is_some(),
+            &our_table.is_some(),
+            &their_table.is_some(),
+        )
+        .unwrap_or(&true);
+        let keep = present || !entries.is_empty();
+        match our_table {
+            Some(table) if keep => edits.extend(edit_entries(source, table, &entries)),
+            Some(table) => edits.push(remove_table(source, &ours.tables, table)),
+            None if keep => {
+                let mut table = their_table.or(base_table).unwrap().clone();
+                table.entries = entries;
+                added.push(table);
+            }
+            None => {}
+        }
+    }
+
+    // Edits are applied back to front so that earlier offsets stay valid
+    let mut manifest = source.to_string();
+    edits.sort_by_key(|edit| (edit.start, edit.end));
+    for edit in edits.into_iter().rev() {
+        manifest.replace_range(edit.start..edit.end, &edit.replacement);
+    }
+    if !added.is_empty() {
+        if !manifest.is_empty() && !manifest.ends_with('\n') {
+            manifest.push('\n');
+        }
+        if !manifest.is_empty() {
+            manifest.push('\n');
+        }
+        manifest.push_str(
+            &Document {
+                tables: added,
+                footer: Vec::new(),
+            }
+            .render(),
+        );
+    }
+
+    Ok(Merge {
+        manifest,
+        conflicts,
+    })
+}
+
+/// Builds the edits turning the entries of one of our tables into the merged entries.
+fn edit_entries(source: &str, table: &Table, merged: &[Item]) -> Vec<Edit> {
+    let mut edits = Vec::new();
+    let mut insert_at = line_end(source, table.header.end);
+
+    for entry in &table.entries {
+        match merged.iter().find(|item| item.key == entry.key) {
+            None => edits.push(Edit {
+                start: comments_start(source, entry.start),
+                end: next_line(source, entry.end),
+                replacement: String::new(),
+            }),
+            Some(item) => {
+                if item.text != entry.text {
+                    edits.push(Edit {
+                        start: entry.start,
+                        end: line_end(source, entry.end),
+                        replacement: entry_text(item),
+                    });
+                }
+                insert_at = line_end(source, entry.end);
+            }
+        }
+    }
+
+    let mut new_entries = String::new();
+    for item in merged {
+        if table.entries.iter().all(|entry| entry.key != item.key) {
+            new_entries.push('\n');
+            for comment in &item.comments {
+                new_entries.push_str(comment);
+                new_entries.push('\n');
+            }
+            new_entries.push_str(&entry_text(item));
+        }
+    }
+    if !new_entries.is_empty() {
+        edits.push(Edit {
+            start: insert_at,
+            end: insert_at,
+            replacement: new_entries,
+        });
+    }
+    edits
+}
+
+/// Builds the edit removing one of our tables along with its comments.
+fn remove_table(source: &str, tables: &[Table], table: &Table) -> Edit {
+    let start = comments_start(source, table.header.start);
+    let next = tables
+        .iter()
+        .map(|other| other.header.start)
+        .filter(|other| *other > table.header.start)
+        .min();
+    let (start, end) = match next {
+        Some(next) => (start, comments_start(source, next)),
+        None => {
+            // The last table also takes the blank lines separating it from the one before
+            let before = source[..start].trim_end().len();
+            let start = if before == 0 {
+                0
+            } else {
+                next_line(source, before)
+            };
+            let last = table
+                .entries
+                .last()
+                .map_or(table.header.end, |entry| entry.end);
+            (start, next_line(source, last))
+        }
+    };
+    Edit {
+        start,
+        end,
+        replacement: String::new(),
+    }
+}
+
+/// Renders an entry with its end-of-line comment.
+fn entry_text(item: &Item) -> String {
+    match &item.trailing {
+        Some(comment) => format!("{} {}", item.text, comment),
+        None => item.text.clone(),
+    }
+}
+
+/// Returns the offset of the line break ending the line at `offset`, or the end of the source.
+fn line_end(source: &str, offset: usize) -> usize {
+    source[offset..]
+        .find('\n')
+        .map_or(source.len(), |end| offset + end)
+}
+
+/// Returns the offset of the line after the one at `offset`.
+fn next_line(source: &str, offset: usize) -> usize {
+    (line_end(source, offset) + 1).min(source.len())
+}
+
+/// Returns the start of the comment lines directly above the line at `offset`,
+/// or the start of that line if there are none.
+fn comments_start(source: &str, offset: usize) -> usize {
+    let mut start = source[..offset].rfind('\n').map_or(0, |end| end + 1);
+    while start > 0 {
+        let previous = source[..start - 1].rfind('\n').map_or(0, |end| end + 1);
+        if !source[previous..start].trim_start().starts_with('#') {
+            break;
+        }
+        start = previous;
+    }
+    start
+}
+
+/// Identifies a table by its header and, for arrays of tables such as `[[bin]]`,
+/// how many tables with the same header precede it.
+type TableId = (String, usize);
+
+/// Pairs every table with its identity.
+fn identify(tables: &[Table]) -> Vec<(TableId, &Table)> {
+    let mut identified: Vec<(TableId, &Table)> = Vec::new();
+    for table in tables {
+        let header = &table.header.text;
+        let index = identified
+            .iter()
+            .filter(|((other, _), _)| other == header)
+            .count();
+        identified.push(((header.clone(), index), table));
+    }
+    identified
+}
+
+/// Finds the table with the given identity.
+fn find_table<'a>(tables: &[(TableId, &'a Table)], id: &TableId) -> Option<&'a Table> {
+    tables
+        .iter()
+        .find(|(other, _)| other == id)
+        .map(|(_, table)| *table)
+}
+
+/// Finds the entry with the given key in a table.
+fn find_entry<'a>(table: Option<&'a Table>, key: &str) -> Option<&'a Item> {
+    table.and_then(|table| table.entries.iter().find(|entry| entry.key == key))
+}
+
+/// Merges the entries of one table, recording conflicts.
+fn merge_entries(
+    base: Option<&Table>,
+    ours: Option<&Table>,
+    theirs: Option<&Table>,
+    header: &str,
+    conflicts: &mut Vec<Conflict>,
+) -> Vec<Item> {
+    let mut keys: Vec<&str> = Vec::new();
+    for table in [ours, theirs, base].into_iter().flatten() {
+        for entry in &table.entries {
+            if !keys.contains(&entry.key.as_str()) {
+                keys.push(&entry.key);
+            }
+        }
+    }
+
+    let mut merged = Vec::new();
+    for key in keys {
+        let (base_entry, our_entry, their_entry) = (
+            find_entry(base, key),
+            find_entry(ours, key),
+            find_entry(theirs, key),
+        );
+        let text = |entry: Option<&Item>| entry.map(|entry| entry.text.clone());
+
+        match pick(&text(base_entry), &text(our_entry), &text(their_entry)) {
+            Some(choice) if *choice == text(our_entry) => merged.extend(our_entry.cloned()),
+            Some(_) => merged.extend(their_entry.cloned()),
+            None => {
+                let conflict = Conflict {
+                    table: header.to_string(),
+                    key: key.to_string(),
+                    base: text(base_entry),
+                    ours: text(our_entry),
+                    theirs: text(their_entry),
+                };
+                let mut item = our_entry.or(their_entry).unwrap().clone();
+                item.text = markers(&conflict);
+                item.comments.clear();
+                item.trailing = None;
+                merged.push(item);
+                conflicts.push(conflict);
+            }
+        }
+    }
+    merged
+}
+
+/// Chooses the side that changed, or `None` if both changed in different ways.
+fn pick<'a, T: PartialEq>(base: &'a T, ours: &'a T, theirs: &'a T) -> Option<&'a T> {
+    if ours == theirs || theirs == base {
+        Some(ours)
+    } else if ours == base {
+        Some(theirs)
+    } else {
+        None
+    }
+}
+
+/// Renders a conflict between git-style conflict markers.
+fn markers(conflict: &Conflict) -> String {
+    let side = |entry: &Option<String>| {
+        entry
+            .as_ref()
+            .map(|entry| format!("{}\n", entry))
+            .unwrap_or_default()
+    };
+    format!(
+        "<<<<<<< ours\n{}=======\n{}>>>>>>> theirs",
+        side(&conflict.ours),
+        side(&conflict.theirs)
+    )
+}
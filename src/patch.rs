@@ -0,0 +1,174 @@
+//! `[patch.<source>]` and legacy `[replace]` tables and their effect on dependencies.
+
+use crate::lockfile::normalize_git_url;
+use crate::Dependency;
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+
+/// The source key of patches that apply to crates.io dependencies.
+pub const CRATES_IO: &str = "crates-io";
+
+/// An entry of a `[patch.<source>]` table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PatchEntry {
+    /// The patched source, `crates-io`, a registry name or a git URL
+    pub source: String,
+    /// The replacement, named after the patched crate
+    pub replacement: Dependency,
+}
+
+impl PatchEntry {
+    /// Checks whether the patch applies to a dependency, i.e. the dependency
+    /// comes from the patched source and names the patched crate.
+    ///
+    /// The version of the patched crate is not known from the manifest alone,
+    /// so a patch whose version does not satisfy the requirement still counts
+    /// as applying.
+    pub fn applies_to(&self, dependency: &Dependency) -> bool {
+        if dependency.package_name() != self.replacement.package_name() || dependency.path.is_some()
+        {
+            return false;
+        }
+
+        match (&dependency.git, &dependency.registry) {
+            (Some(git), _) => normalize_git_url(git) == normalize_git_url(&self.source),
+            (None, Some(registry)) => *registry == self.source,
+            (None, None) => self.source == CRATES_IO,
+        }
+    }
+}
+
+/// An entry of the legacy `[replace]` table, keyed by `name:version`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplaceEntry {
+    /// Name of the replaced package
+    pub package: String,
+    /// Exact version of the replaced package
+    pub version: String,
+    /// The replacement
+    pub replacement: Dependency,
+}
+
+impl ReplaceEntry {
+    /// Checks whether the entry replaces a dependency, i.e. the dependency is
+    /// a registry dependency whose requirement allows the replaced version.
+    pub fn applies_to(&self, dependency: &Dependency) -> bool {
+        if dependency.package_name() != self.package
+            || dependency.path.is_some()
+            || dependency.git.is_some()
+        {
+            return false;
+        }
+
+        let version = Version::parse(&self.version);
+        let requirement = VersionReq::parse(dependency.version.as_deref().unwrap_or("*"));
+        match (version, requirement) {
+            (Ok(version), Ok(requirement)) => requirement.matches(&version),
+            _ => false,
+        }
+    }
+}
+
+/// Where the effective source of a dependency comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Override {
+    /// The index of the patch in the list given to `apply_patches`
+    Patch(usize),
+    /// The index of the replacement in the list given to `apply_patches`
+    Replace(usize),
+}
+
+/// A dependency after patches and replacements were applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchedDependency {
+    /// The dependency with its source swapped for the override's
+    pub dependency: Dependency,
+    /// The override that changed the source, if any
+    pub overridden_by: Option<Override>,
+}
+
+/// The result of applying patches and replacements to a set of dependencies.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PatchReport {
+    /// Every dependency, patched or not, in input order
+    pub dependencies: Vec<PatchedDependency>,
+    /// Indices of patches that applied to no dependency
+    pub unused_patches: Vec<usize>,
+    /// Indices of replacements that applied to no dependency
+    pub unused_replacements: Vec<usize>,
+}
+
+/// Applies patches and replacements to a set of dependencies.
+///
+/// Patches take precedence over replacements, and the first matching entry of
+/// each wins.
+///
+/// # Arguments
+///
+/// * `dependencies` - The dependencies to patch, e.g. of every workspace member
+/// * `patches` - The patches of the workspace root
+/// * `replacements` - The replacements of the workspace root
+///
+/// # Returns
+///
+/// The patched dependencies along with the entries that were never used
+pub fn apply_patches<'a>(
+    dependencies: impl IntoIterator<Item = &'a Dependency>,
+    patches: &[PatchEntry],
+    replacements: &[ReplaceEntry],
+) -> PatchReport {
+    let mut used_patches = vec![false; patches.len()];
+    let mut used_replacements = vec![false; replacements.len()];
+    let mut report = PatchReport::default();
+
+    for dependency in dependencies {
+        let overridden_by =
+            if let Some(index) = patches.iter().position(|p| p.applies_to(dependency)) {
+                used_patches[index] = true;
+                Some(Override::Patch(index))
+            } else if let Some(index) = replacements.iter().position(|r| r.applies_to(dependency)) {
+                used_replacements[index] = true;
+                Some(Override::Replace(index))
+            } else {
+                None
+            };
+
+        let dependency = match overridden_by {
+            Some(Override::Patch(index)) => with_source(dependency, &patches[index].replacement),
+            Some(Override::Replace(index)) => {
+                with_source(dependency, &replacements[index].replacement)
+            }
+            None => dependency.clone(),
+        };
+        report.dependencies.push(PatchedDependency {
+            dependency,
+            overridden_by,
+        });
+    }
+
+    report.unused_patches = unused(&used_patches);
+    report.unused_replacements = unused(&used_replacements);
+    report
+}
+
+/// Returns a copy of `dependency` that takes its source from `replacement`.
+fn with_source(dependency: &Dependency, replacement: &Dependency) -> Dependency {
+    Dependency {
+        git: replacement.git.clone(),
+        branch: replacement.branch.clone(),
+        tag: replacement.tag.clone(),
+        rev: replacement.rev.clone(),
+        path: replacement.path.clone(),
+        registry: replacement.registry.clone(),
+        ..dependency.clone()
+    }
+}
+
+/// Returns the indices of the entries that were not used.
+fn unused(used: &[bool]) -> Vec<usize> {
+    used.iter()
+        .enumerate()
+        .filter(|(_, &used)| !used)
+        .map(|(index, _)| index)
+        .collect()
+}
@@ -0,0 +1,531 @@
+//! Queries over the manifest as a tree of values.
+//!
+//! A query is a dotted path such as `package.metadata.docs.rs.features` or
+//! `target.*.dependencies.openssl`. Each segment is a bare key, a quoted key
+//! (`'cfg(unix)'` or `"cfg(unix)"`), or `*` to match every key of a table or
+//! every element of an array. Segments may be followed by array indexes,
+//! `[0]` for one element or `[*]` for all of them.
+
+use crate::{cfg, Manifest, ManifestError};
+use indexmap::IndexMap;
+use serde::Serialize;
+use std::fmt;
+
+/// Tables that Cargo always writes as arrays of tables, e.g. `[[bin]]`.
+pub(crate) const ARRAY_TABLES: [&str; 4] = ["bin", "example", "test", "bench"];
+
+/// Tables whose string entries are shorthand for `{ version = "..." }`.
+pub(crate) const DEPENDENCY_TABLES: [&str; 3] =
+    ["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// A value of the manifest tree.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Value {
+    /// A basic or literal string
+    String(String),
+    /// An integer
+    Integer(i64),
+    /// A floating point number
+    Float(f64),
+    /// `true` or `false`
+    Boolean(bool),
+    /// An array, or an array of tables
+    Array(Vec<Value>),
+    /// A table, an inline table, or the whole manifest, with keys in source order
+    Table(IndexMap<String, Value>),
+}
+
+impl Value {
+    /// Retrieves the string if the value is one.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(string) => Some(string),
+            _ => None,
+        }
+    }
+
+    /// Parses a value written in inline TOML syntax, e.g. `"1.0"`, `true` or `["a", "b"]`.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The value as it would appear after `key =`
+    ///
+    /// # Returns
+    ///
+    /// The parsed `Value`, or `None` if the text is not a single inline TOML value
+    pub fn parse_toml(text: &str) -> Option<Value> {
+        let mut parser = ValueParser { rest: text.trim() };
+        parser.value().filter(|_| parser.rest.trim().is_empty())
+    }
+
+    /// Parses a value as written in a manifest, falling back to a string for
+    /// text that is not valid inline TOML.
+    pub(crate) fn parse(raw: &str) -> Value {
+        Value::parse_toml(raw)
+            .unwrap_or_else(|| Value::String(raw.trim().trim_matches('"').to_string()))
+    }
+}
+
+impl fmt::Display for Value {
+    /// Writes the value in inline TOML syntax.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::String(string) => write!(f, "\"{}\"", escape(string)),
+            Value::Integer(integer) => write!(f, "{}", integer),
+            Value::Float(float) => write!(f, "{:?}", float),
+            Value::Boolean(boolean) => write!(f, "{}", boolean),
+            Value::Array(values) => {
+                let values: Vec<String> = values.iter().map(Value::to_string).collect();
+                write!(f, "[{}]", values.join(", "))
+            }
+            Value::Table(table) if table.is_empty() => write!(f, "{{}}"),
+            Value::Table(table) => {
+                let entries: Vec<String> = table
+                    .iter()
+                    .map(|(key, value)| format!("{} = {}", quote_key(key), value))
+                    .collect();
+                write!(f, "{{ {} }}", entries.join(", "))
+            }
+        }
+    }
+}
+
+/// A value found by a query along with its concrete path.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QueryMatch {
+    /// The path of the value with wildcards resolved, e.g. `dependencies.serde.version`
+    pub path: String,
+    /// The value at that path
+    pub value: Value,
+}
+
+/// One step of a query path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Segment {
+    /// A key of a table
+    Key(String),
+    /// An element of an array
+    Index(usize),
+    /// Every key of a table or element of an array
+    Wildcard,
+}
+
+impl Manifest {
+    /// Builds the tree of values described by the manifest.
+    ///
+    /// Dotted table names become nested tables, tables such as `[[bin]]` become
+    /// arrays, and version-only dependencies like `serde = "1"` are expanded to
+    /// `{ version = "1" }` so that every dependency has the same shape.
+    ///
+    /// # Returns
+    ///
+    /// The root `Value::Table` of the manifest
+    pub fn to_value(&self) -> Value {
+        let mut root = IndexMap::new();
+        for (section, entries) in &self.raw_values {
+            let path = split_table_name(section);
+            let expand = holds_dependencies(&path);
+
+            let Some((name, parents)) = path.split_last() else {
+                continue;
+            };
+            let Some(parent) = nested_table(&mut root, parents) else {
+                continue;
+            };
+            // A table written after its subtables, as `[a]` after `[a.b]`, keeps their place
+            let index = parent.get_index_of(name.as_str());
+            let value = match self.array_tables.get(section) {
+                Some(tables) => Value::Array(
+                    tables
+                        .iter()
+                        .map(|entries| Value::Table(table_value(entries, expand)))
+                        .collect(),
+                ),
+                None => {
+                    let mut table = table_value(entries, expand);
+                    if let Some(Value::Table(existing)) = parent.shift_remove(name.as_str()) {
+                        table.extend(existing);
+                    }
+                    if path.len() == 1 && ARRAY_TABLES.contains(&name.as_str()) {
+                        Value::Array(vec![Value::Table(table)])
+                    } else {
+                        Value::Table(table)
+                    }
+                }
+            };
+            match index {
+                Some(index) => parent.shift_insert(index, name.clone(), value),
+                None => parent.insert(name.clone(), value),
+            };
+        }
+        Value::Table(root)
+    }
+
+    /// Finds the values at a dotted path, e.g. `dependencies.*.version`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The query, see the [module documentation](crate::query)
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing either the matching values in document order, which is
+    /// empty if nothing matches, or a `ManifestError` if the query is not well formed
+    pub fn query(&self, path: &str) -> Result<Vec<QueryMatch>, ManifestError> {
+        let segments = parse_query(path)?;
+        let mut matches = Vec::new();
+        collect(&self.to_value(), &segments, String::new(), &mut matches);
+        Ok(matches)
+    }
+}
+
+/// Checks whether the entries of a table are dependency specs: those of a
+/// dependency table, its `[target.<platform>.*]` and `[workspace.*]` forms, and
+/// of a `[patch.<source>]` table, but not of tables nested deeper such as
+/// `[package.metadata.dependencies]` or `[patch.crates-io.foo]`.
+fn holds_dependencies(path: &[String]) -> bool {
+    let path: Vec<&str> = path.iter().map(String::as_str).collect();
+    match path.as_slice() {
+        [table] | ["target", _, table] | ["workspace", table] => DEPENDENCY_TABLES.contains(table),
+        ["patch", _] => true,
+        _ => false,
+    }
+}
+
+/// Serializes the manifest as the tree built by `Manifest::to_value`, so that
+/// `[package]`, dependencies, features, targets and profiles come out as nested maps
+/// and arrays in any serde format.
+impl Serialize for Manifest {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_value().serialize(serializer)
+    }
+}
+
+/// Checks whether a query has wildcards and may therefore match several values.
+///
+/// # Arguments
+///
+/// * `path` - The query
+///
+/// # Returns
+///
+/// A `Result` containing either whether the query has a wildcard or a `ManifestError`
+/// if the query is not well formed
+pub fn is_wildcard_query(path: &str) -> Result<bool, ManifestError> {
+    Ok(parse_query(path)?.contains(&Segment::Wildcard))
+}
+
+/// Parses the entries of a table as written, expanding version-only dependencies
+/// like `serde = "1"` to `{ version = "1" }` if `expand` is set.
+fn table_value(entries: &IndexMap<String, String>, expand: bool) -> IndexMap<String, Value> {
+    entries
+        .iter()
+        .map(|(key, raw)| {
+            let value = match Value::parse(raw) {
+                Value::String(version) if expand => Value::Table(IndexMap::from([(
+                    "version".to_string(),
+                    Value::String(version),
+                )])),
+                value => value,
+            };
+            (key.clone(), value)
+        })
+        .collect()
+}
+
+/// Finds the table at a path of keys, creating missing tables along the way.
+fn nested_table<'a>(
+    table: &'a mut IndexMap<String, Value>,
+    keys: &[String],
+) -> Option<&'a mut IndexMap<String, Value>> {
+    let Some((key, rest)) = keys.split_first() else {
+        return Some(table);
+    };
+    match table
+        .entry(key.clone())
+        .or_insert_with(|| Value::Table(IndexMap::new()))
+    {
+        Value::Table(child) => nested_table(child, rest),
+        _ => None,
+    }
+}
+
+/// Walks the tree along the query, collecting every value at its end.
+fn collect(value: &Value, segments: &[Segment], path: String, matches: &mut Vec<QueryMatch>) {
+    let Some((segment, rest)) = segments.split_first() else {
+        matches.push(QueryMatch {
+            path,
+            value: value.clone(),
+        });
+        return;
+    };
+
+    let join = |key: &str| {
+        if path.is_empty() {
+            quote_key(key)
+        } else {
+            format!("{}.{}", path, quote_key(key))
+        }
+    };
+    match (segment, value) {
+        (Segment::Key(key), Value::Table(table)) => {
+            if let Some(child) = table.get(key) {
+                collect(child, rest, join(key), matches);
+            }
+        }
+        (Segment::Wildcard, Value::Table(table)) => {
+            for (key, child) in table {
+                collect(child, rest, join(key), matches);
+            }
+        }
+        (Segment::Index(index), Value::Array(values)) => {
+            if let Some(child) = values.get(*index) {
+                collect(child, rest, format!("{}[{}]", path, index), matches);
+            }
+        }
+        (Segment::Wildcard, Value::Array(values)) => {
+            for (index, child) in values.iter().enumerate() {
+                collect(child, rest, format!("{}[{}]", path, index), matches);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Splits a query into its segments.
+pub(crate) fn parse_query(query: &str) -> Result<Vec<Segment>, ManifestError> {
+    let invalid = |reason: &str| ManifestError::InvalidQuery(query.to_string(), reason.to_string());
+    let mut segments = Vec::new();
+    let mut rest = query.trim();
+    if rest.is_empty() {
+        return Err(invalid("empty path"));
+    }
+
+    loop {
+        if let Some(quoted) = rest.strip_prefix(['\'', '"']) {
+            let quote = rest.chars().next().unwrap();
+            let end = quoted
+                .find(quote)
+                .ok_or_else(|| invalid("unterminated quoted key"))?;
+            segments.push(Segment::Key(quoted[..end].to_string()));
+            rest = &quoted[end + 1..];
+        } else if let Some(after) = rest.strip_prefix('*') {
+            segments.push(Segment::Wildcard);
+            rest = after;
+        } else {
+            let end = rest.find(|c| !is_bare_key_char(c)).unwrap_or(rest.len());
+            if end == 0 {
+                return Err(invalid("expected a key"));
+            }
+            segments.push(Segment::Key(rest[..end].to_string()));
+            rest = &rest[end..];
+        }
+
+        while let Some(index) = rest.strip_prefix('[') {
+            let end = index
+                .find(']')
+                .ok_or_else(|| invalid("unterminated index"))?;
+            segments.push(match index[..end].trim() {
+                "*" => Segment::Wildcard,
+                number => Segment::Index(number.parse().map_err(|_| invalid("expected an index"))?),
+            });
+            rest = &index[end + 1..];
+        }
+
+        match rest.strip_prefix('.') {
+            Some(after) => rest = after,
+            None if rest.is_empty() => return Ok(segments),
+            None => return Err(invalid("expected `.` between keys")),
+        }
+    }
+}
+
+/// Splits a table name such as `target.'cfg(unix)'.dependencies` into its keys.
+pub(crate) fn split_table_name(name: &str) -> Vec<String> {
+    let mut keys = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+    for c in name.chars() {
+        match (quote, c) {
+            (None, '.') => keys.push(cfg::unquote_key(&std::mem::take(&mut current))),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                current.push(c);
+            }
+            (Some(open), _) if open == c => {
+                quote = None;
+                current.push(c);
+            }
+            _ => current.push(c),
+        }
+    }
+    keys.push(cfg::unquote_key(&current));
+    keys
+}
+
+/// Checks whether a character may appear in a bare key.
+fn is_bare_key_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+/// Writes a key bare if possible and quoted otherwise.
+pub(crate) fn quote_key(key: &str) -> String {
+    if !key.is_empty() && key.chars().all(is_bare_key_char) {
+        key.to_string()
+    } else if !key.contains('\'') {
+        format!("'{}'", key)
+    } else {
+        format!("\"{}\"", escape(key))
+    }
+}
+
+/// Escapes a string for a basic TOML string.
+fn escape(string: &str) -> String {
+    string
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+}
+
+/// A recursive descent parser for inline TOML values.
+struct ValueParser<'a> {
+    /// The text that is left to parse
+    rest: &'a str,
+}
+
+impl ValueParser<'_> {
+    /// Parses one value.
+    fn value(&mut self) -> Option<Value> {
+        self.skip_whitespace();
+        match self.rest.chars().next()? {
+            '"' => self.basic_string().map(Value::String),
+            '\'' => self.literal_string().map(Value::String),
+            '[' => self.array(),
+            '{' => self.inline_table(),
+            _ => self.scalar(),
+        }
+    }
+
+    /// Parses a `"..."` string, resolving escapes.
+    fn basic_string(&mut self) -> Option<String> {
+        let mut string = String::new();
+        let mut chars = self.rest.char_indices().skip(1);
+        while let Some((index, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.rest = &self.rest[index + 1..];
+                    return Some(string);
+                }
+                '\\' => string.push(match chars.next()?.1 {
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    other => other,
+                }),
+                _ => string.push(c),
+            }
+        }
+        None
+    }
+
+    /// Parses a `'...'` string.
+    fn literal_string(&mut self) -> Option<String> {
+        let end = self.rest[1..].find('\'')? + 1;
+        let string = self.rest[1..end].to_string();
+        self.rest = &self.rest[end + 1..];
+        Some(string)
+    }
+
+    /// Parses a `[...]` array.
+    fn array(&mut self) -> Option<Value> {
+        self.rest = &self.rest[1..];
+        let mut values = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if let Some(rest) = self.rest.strip_prefix(']') {
+                self.rest = rest;
+                return Some(Value::Array(values));
+            }
+            values.push(self.value()?);
+            self.skip_whitespace();
+            match self.rest.strip_prefix(',') {
+                Some(rest) => self.rest = rest,
+                None if self.rest.starts_with(']') => {}
+                None => return None,
+            }
+        }
+    }
+
+    /// Parses a `{ key = value, ... }` table.
+    fn inline_table(&mut self) -> Option<Value> {
+        self.rest = &self.rest[1..];
+        let mut table = IndexMap::new();
+        loop {
+            self.skip_whitespace();
+            if let Some(rest) = self.rest.strip_prefix('}') {
+                self.rest = rest;
+                return Some(Value::Table(table));
+            }
+            let key = self.key()?;
+            self.skip_whitespace();
+            self.rest = self.rest.strip_prefix('=')?;
+            table.insert(key, self.value()?);
+            self.skip_whitespace();
+            match self.rest.strip_prefix(',') {
+                Some(rest) => self.rest = rest,
+                None if self.rest.starts_with('}') => {}
+                None => return None,
+            }
+        }
+    }
+
+    /// Parses a bare or quoted key of an inline table.
+    fn key(&mut self) -> Option<String> {
+        match self.rest.chars().next()? {
+            '"' => self.basic_string(),
+            '\'' => self.literal_string(),
+            _ => {
+                let end = self.rest.find(|c| !is_bare_key_char(c))?;
+                if end == 0 {
+                    return None;
+                }
+                let key = self.rest[..end].to_string();
+                self.rest = &self.rest[end..];
+                Some(key)
+            }
+        }
+    }
+
+    /// Parses a boolean or a number.
+    fn scalar(&mut self) -> Option<Value> {
+        let end = self
+            .rest
+            .find(|c: char| c == ',' || c == ']' || c == '}' || c.is_whitespace())
+            .unwrap_or(self.rest.len());
+        let (text, rest) = self.rest.split_at(end);
+        let number = text.replace('_', "");
+        let value = match text {
+            "true" => Value::Boolean(true),
+            "false" => Value::Boolean(false),
+            _ => match number.parse() {
+                Ok(integer) => Value::Integer(integer),
+                Err(_) => Value::Float(number.parse().ok()?),
+            },
+        };
+        self.rest = rest;
+        Some(value)
+    }
+
+    /// Skips whitespace and comments between the parts of a value.
+    fn skip_whitespace(&mut self) {
+        loop {
+            self.rest = self.rest.trim_start();
+            match self.rest.strip_prefix('#') {
+                Some(comment) => self.rest = comment.find('\n').map_or("", |end| &comment[end..]),
+                None => return,
+            }
+        }
+    }
+}
@@ -0,0 +1,152 @@
+//! Exports diagnostics as SARIF and as GitHub workflow annotations.
+
+use crate::diagnostic::Diagnostic;
+use crate::LintLevel;
+use serde_json::{json, Value};
+
+/// The SARIF version produced by `to_sarif`.
+pub const SARIF_VERSION: &str = "2.1.0";
+
+/// The schema of the SARIF version produced by `to_sarif`.
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Renders diagnostics as a SARIF 2.1.0 log with a single run.
+///
+/// # Arguments
+///
+/// * `files` - Pairs of a file path, used as the artifact URI, and the diagnostics reported for it
+///
+/// # Returns
+///
+/// The SARIF log as pretty-printed JSON
+pub fn to_sarif<'a>(files: impl IntoIterator<Item = (&'a str, &'a [Diagnostic])>) -> String {
+    let mut rules: Vec<&str> = Vec::new();
+    let mut results = Vec::new();
+
+    for (path, diagnostics) in files {
+        for diagnostic in diagnostics {
+            let rule_index = match rules.iter().position(|rule| *rule == diagnostic.rule) {
+                Some(index) => index,
+                None => {
+                    rules.push(&diagnostic.rule);
+                    rules.len() - 1
+                }
+            };
+            results.push(sarif_result(path, diagnostic, rule_index));
+        }
+    }
+
+    let log = json!({
+        "$schema": SARIF_SCHEMA,
+        "version": SARIF_VERSION,
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                    "rules": rules.iter().map(|id| json!({ "id": id })).collect::<Vec<_>>(),
+                }
+            },
+            "results": results,
+        }]
+    });
+    serde_json::to_string_pretty(&log).unwrap()
+}
+
+/// Builds the SARIF result of a single diagnostic.
+fn sarif_result(path: &str, diagnostic: &Diagnostic, rule_index: usize) -> Value {
+    let level = match diagnostic.level {
+        LintLevel::Allow => "note",
+        LintLevel::Warn => "warning",
+        LintLevel::Deny | LintLevel::Forbid => "error",
+    };
+    let mut location = json!({ "artifactLocation": { "uri": path } });
+    if let Some(span) = diagnostic.span {
+        location["region"] = json!({
+            "startLine": span.line,
+            "startColumn": span.column,
+            "byteOffset": span.start,
+            "byteLength": span.end - span.start,
+        });
+    }
+
+    let mut result = json!({
+        "ruleId": diagnostic.rule,
+        "ruleIndex": rule_index,
+        "level": level,
+        "message": { "text": diagnostic.message },
+        "locations": [{ "physicalLocation": location }],
+    });
+    if let Some(fix) = &diagnostic.fix {
+        let replacements: Vec<Value> = fix
+            .edits
+            .iter()
+            .map(|edit| {
+                json!({
+                    "deletedRegion": {
+                        "byteOffset": edit.start,
+                        "byteLength": edit.end - edit.start,
+                    },
+                    "insertedContent": { "text": edit.replacement },
+                })
+            })
+            .collect();
+        result["fixes"] = json!([{
+            "description": { "text": fix.description },
+            "artifactChanges": [{
+                "artifactLocation": { "uri": path },
+                "replacements": replacements,
+            }],
+        }]);
+    }
+    result
+}
+
+/// Renders diagnostics as GitHub workflow commands, e.g.
+/// `::error file=Cargo.toml,line=3,col=1,title=wildcard-requirement::...`.
+///
+/// # Arguments
+///
+/// * `path` - The file the diagnostics were reported for
+/// * `diagnostics` - The diagnostics to render
+///
+/// # Returns
+///
+/// One annotation per line
+pub fn to_github_annotations(path: &str, diagnostics: &[Diagnostic]) -> String {
+    let mut annotations = String::new();
+    for diagnostic in diagnostics {
+        let command = match diagnostic.level {
+            LintLevel::Allow => "notice",
+            LintLevel::Warn => "warning",
+            LintLevel::Deny | LintLevel::Forbid => "error",
+        };
+        let mut properties = vec![format!("file={}", escape_property(path))];
+        if let Some(span) = diagnostic.span {
+            properties.push(format!("line={}", span.line));
+            properties.push(format!("col={}", span.column));
+        }
+        properties.push(format!("title={}", escape_property(&diagnostic.rule)));
+
+        annotations.push_str(&format!(
+            "::{} {}::{}\n",
+            command,
+            properties.join(","),
+            escape_data(&diagnostic.message)
+        ));
+    }
+    annotations
+}
+
+/// Escapes the message of a workflow command.
+fn escape_data(data: &str) -> String {
+    data.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escapes a property value of a workflow command.
+fn escape_property(value: &str) -> String {
+    escape_data(value).replace(':', "%3A").replace(',', "%2C")
+}
@@ -0,0 +1,345 @@
+//! Dependency trees of packages and workspaces, rendered like `cargo tree`.
+//!
+//! Workspace members come from their manifests and every other package from the
+//! lockfile, so no registry or `cargo` is needed. Without a lockfile, external
+//! dependencies are shown as leaves with their version requirement.
+
+use crate::cfg::CfgSet;
+use crate::lockfile::describe_expected_source;
+use crate::{Dependency, DependencyKind, Lockfile, Manifest, ManifestError};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Sources of packages shown without a source, i.e. crates.io.
+const DEFAULT_SOURCES: [&str; 2] = [
+    "registry+https://github.com/rust-lang/crates.io-index",
+    "sparse+https://index.crates.io/",
+];
+
+/// The order of the dependency groups below a package.
+const KIND_ORDER: [DependencyKind; 3] = [
+    DependencyKind::Normal,
+    DependencyKind::Build,
+    DependencyKind::Dev,
+];
+
+/// What a dependency tree shows.
+#[derive(Debug, Clone)]
+pub struct TreeOptions {
+    /// Kinds of dependencies to follow
+    pub kinds: Vec<DependencyKind>,
+    /// Levels shown below the roots, unlimited if `None`
+    pub depth: Option<usize>,
+    /// Show the packages that depend on this package instead of the workspace members
+    pub invert: Option<String>,
+    /// Show only packages locked in more than one version, inverted
+    pub duplicates: bool,
+    /// Only follow dependencies of workspace members that apply to this target
+    pub target: Option<CfgSet>,
+    /// Show only this workspace member instead of all of them
+    pub package: Option<String>,
+}
+
+impl Default for TreeOptions {
+    fn default() -> Self {
+        TreeOptions {
+            kinds: KIND_ORDER.to_vec(),
+            depth: None,
+            invert: None,
+            duplicates: false,
+            target: None,
+            package: None,
+        }
+    }
+}
+
+/// A package of the tree.
+#[derive(Debug)]
+struct Node {
+    /// Package name
+    name: String,
+    /// Name, version and source as shown in the tree
+    label: String,
+    /// Whether the package is a workspace member
+    member: bool,
+}
+
+/// A dependency between two packages of the tree.
+#[derive(Debug, PartialEq, Eq)]
+struct Edge {
+    /// Index of the dependent package
+    from: usize,
+    /// Index of the package depended upon
+    to: usize,
+    /// The table the dependency was declared in, `Normal` for locked packages
+    kind: DependencyKind,
+}
+
+/// The packages reachable from the workspace members.
+#[derive(Debug, Default)]
+struct Graph {
+    /// Members first, in the order the manifests were given
+    nodes: Vec<Node>,
+    /// Dependencies between the nodes
+    edges: Vec<Edge>,
+}
+
+/// Renders the dependency tree of a package or workspace.
+///
+/// # Arguments
+///
+/// * `manifests` - The manifests of the package or of every workspace member
+/// * `lockfile` - The lockfile shared by the manifests, if there is one
+/// * `options` - What to show
+///
+/// # Returns
+///
+/// A `Result` containing either the tree, one root per paragraph, or a
+/// `ManifestError::UnknownPackage` if a package named in `options` is not in it
+pub fn render_tree(
+    manifests: &[Manifest],
+    lockfile: Option<&Lockfile>,
+    options: &TreeOptions,
+) -> Result<String, ManifestError> {
+    let graph = Graph::build(manifests, lockfile, options.target.as_ref());
+    let named = |name: &str| -> Result<Vec<usize>, ManifestError> {
+        let nodes: Vec<usize> = (0..graph.nodes.len())
+            .filter(|&index| graph.nodes[index].name == name)
+            .collect();
+        match nodes.is_empty() {
+            true => Err(ManifestError::UnknownPackage(name.to_string())),
+            false => Ok(nodes),
+        }
+    };
+
+    let (mut roots, inverted) = if options.duplicates {
+        (graph.duplicates(), true)
+    } else if let Some(name) = &options.invert {
+        (named(name)?, true)
+    } else if let Some(name) = &options.package {
+        (named(name)?, false)
+    } else {
+        let members = (0..graph.nodes.len()).filter(|&index| graph.nodes[index].member);
+        (members.collect(), false)
+    };
+    if inverted {
+        roots.sort_by(|&a, &b| graph.nodes[a].label.cmp(&graph.nodes[b].label));
+    }
+
+    let mut tree = String::new();
+    for root in roots {
+        if !tree.is_empty() {
+            tree.push('\n');
+        }
+        let mut printed = HashSet::new();
+        graph.render(
+            root,
+            inverted,
+            options,
+            &mut Vec::new(),
+            &mut printed,
+            &mut tree,
+        );
+    }
+    Ok(tree)
+}
+
+impl Graph {
+    /// Builds the graph of the workspace members and the packages they depend on.
+    fn build(manifests: &[Manifest], lockfile: Option<&Lockfile>, target: Option<&CfgSet>) -> Self {
+        let mut graph = Graph::default();
+        let mut members = HashMap::new();
+        for manifest in manifests {
+            let name = manifest.package_name().to_string();
+            let version = manifest.get_by_key("package", "version").unwrap_or("0.0.0");
+            members.insert(name.clone(), graph.nodes.len());
+            graph.nodes.push(Node {
+                label: format!("{} v{}", name, version),
+                name,
+                member: true,
+            });
+        }
+
+        let packages: Vec<_> = lockfile.map_or(Vec::new(), |lock| lock.packages().collect());
+        let mut locked: HashMap<usize, usize> = HashMap::new();
+        let mut unlocked: HashMap<String, usize> = HashMap::new();
+        let mut queue = VecDeque::new();
+        let mut lock_node = |graph: &mut Graph, index: usize, queue: &mut VecDeque<usize>| {
+            let package = packages[index];
+            if package.source.is_none() {
+                if let Some(&member) = members.get(&package.name) {
+                    return member;
+                }
+            }
+            *locked.entry(index).or_insert_with(|| {
+                queue.push_back(index);
+                let source = match package.source.as_deref() {
+                    Some(source) if !DEFAULT_SOURCES.contains(&source) => format!(" ({})", source),
+                    _ => String::new(),
+                };
+                graph.add(
+                    &package.name,
+                    format!("{} v{}{}", package.name, package.version, source),
+                )
+            })
+        };
+
+        for (from, manifest) in manifests.iter().enumerate() {
+            for dependency in manifest.dependencies() {
+                if target.is_some_and(|cfg| !dependency.applies_to(cfg)) {
+                    continue;
+                }
+                let member = members
+                    .get(dependency.package_name())
+                    .filter(|_| dependency.path.is_some() || dependency.workspace);
+                let resolved = lockfile.and_then(|lock| lock.resolve_dependency(dependency));
+                let to = match (member, resolved) {
+                    (Some(&member), _) => member,
+                    (None, Some(index)) => lock_node(&mut graph, index, &mut queue),
+                    (None, None) => {
+                        let label = unlocked_label(dependency);
+                        *unlocked
+                            .entry(label.clone())
+                            .or_insert_with(|| graph.add(dependency.package_name(), label))
+                    }
+                };
+                graph.connect(from, to, dependency.kind);
+            }
+        }
+
+        while let Some(index) = queue.pop_front() {
+            let from = lock_node(&mut graph, index, &mut queue);
+            for reference in &packages[index].dependencies {
+                let resolved =
+                    lockfile.and_then(|lock| lock.resolve_reference(reference).first().copied());
+                if let Some(dependency) = resolved {
+                    let to = lock_node(&mut graph, dependency, &mut queue);
+                    graph.connect(from, to, DependencyKind::Normal);
+                }
+            }
+        }
+        graph
+    }
+
+    /// Adds a package that is not a workspace member.
+    fn add(&mut self, name: &str, label: String) -> usize {
+        self.nodes.push(Node {
+            name: name.to_string(),
+            label,
+            member: false,
+        });
+        self.nodes.len() - 1
+    }
+
+    /// Adds a dependency unless it is already there.
+    fn connect(&mut self, from: usize, to: usize, kind: DependencyKind) {
+        let edge = Edge { from, to, kind };
+        if !self.edges.contains(&edge) {
+            self.edges.push(edge);
+        }
+    }
+
+    /// Returns the packages that are not members and share their name with another package.
+    fn duplicates(&self) -> Vec<usize> {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for node in self.nodes.iter().filter(|node| !node.member) {
+            *counts.entry(&node.name).or_default() += 1;
+        }
+        (0..self.nodes.len())
+            .filter(|&index| {
+                !self.nodes[index].member && counts[self.nodes[index].name.as_str()] > 1
+            })
+            .collect()
+    }
+
+    /// Returns the dependencies of a package of one kind, or its dependents
+    /// when `inverted`, ordered by label.
+    fn neighbours(&self, node: usize, kind: DependencyKind, inverted: bool) -> Vec<usize> {
+        let mut neighbours: Vec<usize> = self
+            .edges
+            .iter()
+            .filter(|edge| edge.kind == kind)
+            .filter_map(|edge| match inverted {
+                false if edge.from == node => Some(edge.to),
+                true if edge.to == node => Some(edge.from),
+                _ => None,
+            })
+            .collect();
+        neighbours.sort_by(|&a, &b| self.nodes[a].label.cmp(&self.nodes[b].label));
+        neighbours.dedup();
+        neighbours
+    }
+
+    /// Renders a package and, unless it was shown before, the packages below it.
+    ///
+    /// `levels` holds, for the package and each of its ancestors below the root,
+    /// whether it is the last among its siblings.
+    fn render(
+        &self,
+        node: usize,
+        inverted: bool,
+        options: &TreeOptions,
+        levels: &mut Vec<bool>,
+        printed: &mut HashSet<usize>,
+        tree: &mut String,
+    ) {
+        let groups: Vec<(DependencyKind, Vec<usize>)> = KIND_ORDER
+            .iter()
+            .filter(|kind| options.kinds.contains(kind))
+            .map(|&kind| (kind, self.neighbours(node, kind, inverted)))
+            .filter(|(_, neighbours)| !neighbours.is_empty())
+            .collect();
+
+        tree.push_str(&branch(levels));
+        tree.push_str(&self.nodes[node].label);
+        if !printed.insert(node) && !groups.is_empty() {
+            tree.push_str(" (*)\n");
+            return;
+        }
+        tree.push('\n');
+        if options.depth.is_some_and(|depth| levels.len() >= depth) {
+            return;
+        }
+
+        for (kind, neighbours) in groups {
+            if kind != DependencyKind::Normal {
+                tree.push_str(&format!("{}[{}]\n", indent(levels), kind));
+            }
+            for (i, &neighbour) in neighbours.iter().enumerate() {
+                levels.push(i + 1 == neighbours.len());
+                self.render(neighbour, inverted, options, levels, printed, tree);
+                levels.pop();
+            }
+        }
+    }
+}
+
+/// Describes a dependency that has no lockfile entry by its requirement and source.
+fn unlocked_label(dependency: &Dependency) -> String {
+    if dependency.workspace {
+        return format!("{} (workspace)", dependency.package_name());
+    }
+    let requirement = dependency.version.as_deref().unwrap_or("*");
+    match describe_expected_source(dependency).as_str() {
+        "crates.io" => format!("{} {}", dependency.package_name(), requirement),
+        source => format!("{} {} ({})", dependency.package_name(), requirement, source),
+    }
+}
+
+/// Draws the lines leading to a package.
+fn branch(levels: &[bool]) -> String {
+    match levels.split_last() {
+        Some((&last, ancestors)) => {
+            let tip = if last { "└── " } else { "├── " };
+            format!("{}{}", indent(ancestors), tip)
+        }
+        None => String::new(),
+    }
+}
+
+/// Draws the lines passing by the packages below the given levels.
+fn indent(levels: &[bool]) -> String {
+    levels
+        .iter()
+        .map(|&last| if last { "    " } else { "│   " })
+        .collect()
+}
@@ -0,0 +1,223 @@
+//! Polling file watcher behind `--watch`, and the workspace members it follows.
+//!
+//! Files are compared by modification time and size, so no platform notification
+//! API is needed. A burst of writes, such as an editor saving through a temporary
+//! file, is reported as one change once the files have been quiet for a while.
+
+use crate::query::Value;
+use crate::Manifest;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// Name of the manifest in a member directory.
+const MANIFEST_NAME: &str = "Cargo.toml";
+
+/// What a file looked like when it was last polled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Stamp {
+    /// Last modification time
+    modified: SystemTime,
+    /// Size in bytes
+    len: u64,
+}
+
+impl Stamp {
+    /// Reads the stamp of a file, `None` if it does not exist.
+    fn read(file: &Path) -> Option<Self> {
+        let metadata = fs::metadata(file).ok()?;
+        Some(Stamp {
+            modified: metadata.modified().ok()?,
+            len: metadata.len(),
+        })
+    }
+}
+
+/// Watches files for changes by polling them.
+#[derive(Debug, Clone)]
+pub struct Watcher {
+    /// Watched files and their stamps, `None` while a file is missing
+    stamps: BTreeMap<PathBuf, Option<Stamp>>,
+    /// Time between polls while waiting for a change
+    interval: Duration,
+    /// Quiet time after a change before it is reported
+    debounce: Duration,
+}
+
+impl Default for Watcher {
+    fn default() -> Self {
+        Watcher {
+            stamps: BTreeMap::new(),
+            interval: Duration::from_millis(100),
+            debounce: Duration::from_millis(200),
+        }
+    }
+}
+
+impl Watcher {
+    /// Creates a watcher of no files, polling every 100ms with a 200ms debounce.
+    pub fn new() -> Self {
+        Watcher::default()
+    }
+
+    /// Sets the time between polls and the quiet time before a change is reported.
+    pub fn with_timing(mut self, interval: Duration, debounce: Duration) -> Self {
+        self.interval = interval;
+        self.debounce = debounce;
+        self
+    }
+
+    /// Starts watching a file as it is now.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the file was not watched yet
+    pub fn watch(&mut self, file: PathBuf) -> bool {
+        if self.stamps.contains_key(&file) {
+            return false;
+        }
+        let stamp = Stamp::read(&file);
+        self.stamps.insert(file, stamp);
+        true
+    }
+
+    /// Retrieves the watched files in path order.
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.stamps.keys().map(PathBuf::as_path)
+    }
+
+    /// Checks every watched file once without waiting.
+    ///
+    /// # Returns
+    ///
+    /// The files that were modified, created or removed since the last poll
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        for (file, stamp) in &mut self.stamps {
+            let current = Stamp::read(file);
+            if current != *stamp {
+                *stamp = current;
+                changed.push(file.clone());
+            }
+        }
+        changed
+    }
+
+    /// Blocks until watched files change and have stayed unchanged for the debounce time.
+    ///
+    /// # Returns
+    ///
+    /// Every file that changed during the burst, in path order
+    pub fn wait(&mut self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        while changed.is_empty() {
+            thread::sleep(self.interval);
+            changed = self.poll();
+        }
+        loop {
+            thread::sleep(self.debounce);
+            let more = self.poll();
+            if more.is_empty() {
+                break;
+            }
+            changed.extend(more);
+        }
+        changed.sort();
+        changed.dedup();
+        changed
+    }
+}
+
+/// Finds the manifests of the members of a workspace.
+///
+/// `members` and `exclude` of `[workspace]` are resolved against `root`, with
+/// `*` and `?` matching within one path component.
+///
+/// # Arguments
+///
+/// * `manifest` - The workspace root manifest
+/// * `root` - The directory of the root manifest
+///
+/// # Returns
+///
+/// The `Cargo.toml` of every existing member, in path order
+pub fn workspace_members(manifest: &Manifest, root: &Path) -> Vec<PathBuf> {
+    let patterns = |key: &str| -> Vec<String> {
+        match manifest.query(&format!("workspace.{}", key)) {
+            Ok(matches) => match matches.into_iter().next().map(|found| found.value) {
+                Some(Value::Array(values)) => values
+                    .iter()
+                    .filter_map(|value| value.as_str().map(str::to_string))
+                    .collect(),
+                _ => Vec::new(),
+            },
+            Err(_) => Vec::new(),
+        }
+    };
+    let excluded: Vec<PathBuf> = patterns("exclude")
+        .iter()
+        .flat_map(|pattern| expand(root, pattern))
+        .collect();
+
+    let mut members: Vec<PathBuf> = patterns("members")
+        .iter()
+        .flat_map(|pattern| expand(root, pattern))
+        .filter(|dir| !excluded.contains(dir))
+        .map(|dir| dir.join(MANIFEST_NAME))
+        .filter(|file| file.is_file())
+        .collect();
+    members.sort();
+    members.dedup();
+    members
+}
+
+/// Expands a member pattern into the directories it names.
+fn expand(root: &Path, pattern: &str) -> Vec<PathBuf> {
+    let mut dirs = vec![root.to_path_buf()];
+    for component in pattern.split('/').filter(|c| !c.is_empty() && *c != ".") {
+        if !component.contains(['*', '?']) {
+            dirs = dirs.into_iter().map(|dir| dir.join(component)).collect();
+            continue;
+        }
+        let mut matched = Vec::new();
+        for dir in dirs {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let name = entry.file_name();
+                if path.is_dir() && matches(component, &name.to_string_lossy()) {
+                    matched.push(path);
+                }
+            }
+        }
+        dirs = matched;
+    }
+    dirs
+}
+
+/// Matches a name against a pattern where `*` is any text and `?` any character.
+fn matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    // Lengths of the name prefixes the pattern prefix matches so far
+    let mut prefixes = vec![0];
+    for &token in &pattern {
+        let mut next = Vec::new();
+        for &prefix in &prefixes {
+            match token {
+                '*' => next.extend(prefix..=name.len()),
+                '?' if prefix < name.len() => next.push(prefix + 1),
+                c if name.get(prefix) == Some(&c) => next.push(prefix + 1),
+                _ => {}
+            }
+        }
+        next.sort_unstable();
+        next.dedup();
+        prefixes = next;
+    }
+    prefixes.contains(&name.len())
+}
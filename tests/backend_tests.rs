@@ -0,0 +1,118 @@
+use anyhow::Result;
+use manifest_parser_rs::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CORPUS: [(&str, &str); 4] = [
+        ("basic", include_str!("corpus/basic.toml")),
+        ("full", include_str!("corpus/full.toml")),
+        ("crlf", include_str!("corpus/crlf.toml")),
+        ("unusual", include_str!("corpus/unusual.toml")),
+    ];
+
+    /// Checks that both backends build the same manifest, in the same order, or fail with the same message.
+    fn assert_same(name: &str, input: &str) {
+        let pest = Manifest::parse_with(input, Backend::Pest);
+        let handwritten = Manifest::parse_with(input, Backend::Handwritten);
+        match (pest, handwritten) {
+            // The debug output also shows the order of sections and keys
+            (Ok(pest), Ok(handwritten)) => assert_eq!(
+                format!("{:?}", pest),
+                format!("{:?}", handwritten),
+                "{}: {:?}",
+                name,
+                input
+            ),
+            (Err(pest), Err(handwritten)) => {
+                assert_eq!(
+                    pest.to_string(),
+                    handwritten.to_string(),
+                    "{}: {:?}",
+                    name,
+                    input
+                )
+            }
+            (pest, handwritten) => panic!(
+                "{}: {:?}\npest: {:?}\nhandwritten: {:?}",
+                name,
+                input,
+                pest.map(|_| ()),
+                handwritten.map(|_| ())
+            ),
+        }
+    }
+
+    #[test]
+    fn corpus_parses_the_same() -> Result<()> {
+        for (name, input) in CORPUS {
+            let manifest = Manifest::parse_with(input, Backend::Handwritten)?;
+            assert_eq!(manifest, Manifest::parse(input)?, "{}", name);
+        }
+
+        let full = Manifest::parse_with(CORPUS[1].1, Backend::Handwritten)?;
+        assert_eq!(full.package_name(), "full");
+        assert_eq!(full.dependencies().count(), 16);
+        assert_eq!(full.patches().count(), 2);
+        assert_eq!(full.replacements().count(), 1);
+        assert_eq!(full.lints().count(), 4);
+        assert_eq!(full.workspace_lints().count(), 1);
+        assert_eq!(
+            full.key_span("profile.release", "opt-level"),
+            Manifest::parse(CORPUS[1].1)?.key_span("profile.release", "opt-level")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn errors_are_the_same() -> Result<()> {
+        let invalid = [
+            "",
+            "[package]\nname = \"x\"\n",
+            "[package]\nname = \"x\"\nversion = \"1.0\"\n",
+            "[package]\nname = \"x\"\nversion = \"1.0.0\"\n[dependencies]\nserde = 1\n",
+            "[package]\nname = \"x\"\nversion = \"1.0.0\"\n[unknown]\na = 1\n",
+            "[package]\nname = \"x\"\nversion = \"1.0.0\"\n[target.'cfg(unix'.dependencies]\n",
+            "[package]\nname = \"x\"\nversion = \"1.0.0\"\n[lints.rust]\na = \"loud\"\n",
+            "[package]\nname = \"x\"\nversion = \"1.0.0\"\n[replace]\n\"rand\" = { path = \"r\" }\n",
+        ];
+        for input in invalid {
+            assert!(Manifest::parse_with(input, Backend::Handwritten).is_err());
+            assert_same("invalid", input);
+        }
+        Ok(())
+    }
+
+    /// Deletes the character at every chosen position of every corpus manifest,
+    /// and inserts each of a few syntax characters there.
+    fn check_mutations(chosen: impl Fn(usize) -> bool) {
+        for (name, input) in CORPUS {
+            for (index, _) in input.char_indices().filter(|(index, _)| chosen(*index)) {
+                let mut deleted = input.to_string();
+                deleted.remove(index);
+                assert_same(name, &deleted);
+
+                for inserted in [' ', '\n', '#', '"', '[', '=', '.', '-', '0'] {
+                    let mut mutated = input.to_string();
+                    mutated.insert(index, inserted);
+                    assert_same(name, &mutated);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn mutations_parse_the_same() -> Result<()> {
+        // A fixed sixteenth of the positions, spread by a multiplicative hash
+        check_mutations(|index| (index as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 60 == 0);
+        Ok(())
+    }
+
+    #[test]
+    #[ignore = "mutates every position, run with `make mutations`"]
+    fn all_mutations_parse_the_same() -> Result<()> {
+        check_mutations(|_| true);
+        Ok(())
+    }
+}
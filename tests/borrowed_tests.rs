@@ -0,0 +1,127 @@
+use anyhow::Result;
+use manifest_parser_rs::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"[package]
+name = "app"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+serde = { version = "1.0", features = ["derive"] }
+anyhow = "1.0"
+
+[target.'cfg(unix)'.dependencies]
+libc = "0.2"
+
+[patch.crates-io]
+serde = { path = "../serde" }
+
+[replace]
+"rand:0.8.5" = { path = "../rand" }
+
+[lints.clippy]
+all = { level = "deny", priority = -1 }
+
+[[bin]]
+name = "first"
+
+[[bin]]
+name = "app"
+path = "src/main.rs"
+
+[profile.release]
+lto = true
+"#;
+
+    /// Checks that a slice points into the input rather than into a copy.
+    fn borrows(input: &str, slice: &str) -> bool {
+        let range = input.as_bytes().as_ptr_range();
+        range.contains(&slice.as_ptr())
+    }
+
+    #[test]
+    fn values_borrow_the_input() -> Result<()> {
+        let manifest = BorrowedManifest::parse(MANIFEST)?;
+        assert_eq!(manifest.package_name(), "app");
+        assert_eq!(manifest.get_by_key("package", "edition")?, "2021");
+        assert_eq!(manifest.get_by_key("dependencies", "anyhow")?, "1.0");
+        assert_eq!(manifest.get_by_key("bin", "name")?, "app");
+        assert_eq!(
+            manifest.get_by_key("replace", "rand:0.8.5")?,
+            "{ path = \"../rand\" }"
+        );
+        assert!(borrows(MANIFEST, manifest.get_by_key("package", "name")?));
+        assert!(borrows(
+            MANIFEST,
+            manifest.get_by_key("profile.release", "lto")?
+        ));
+        for section in manifest.sections() {
+            for (key, value) in manifest.get_by_section(section)? {
+                assert!(borrows(MANIFEST, value), "{}.{}", section, key);
+            }
+        }
+
+        assert!(matches!(
+            manifest.get_by_key("package", "license"),
+            Err(ManifestError::MissingKey(_, _))
+        ));
+        assert!(matches!(
+            manifest.get_by_section("workspace"),
+            Err(ManifestError::MissingSection(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn matches_owned_manifest() -> Result<()> {
+        let borrowed = BorrowedManifest::parse(MANIFEST)?;
+        let owned = Manifest::parse(MANIFEST)?;
+
+        let mut sections: Vec<&str> = borrowed.sections().collect();
+        let mut owned_sections: Vec<&str> = owned.sections().map(String::as_str).collect();
+        sections.sort();
+        owned_sections.sort();
+        assert_eq!(sections, owned_sections);
+        for section in sections {
+            let entries = borrowed.get_by_section(section)?;
+            let owned_entries = owned.get_by_section(section)?;
+            assert_eq!(entries.len(), owned_entries.len(), "{}", section);
+            for (key, value) in entries {
+                assert_eq!(owned_entries[*key], *value, "{}.{}", section, key);
+                assert_eq!(
+                    borrowed.key_span(section, key),
+                    owned.key_span(section, key)
+                );
+            }
+            assert_eq!(borrowed.section_span(section), owned.section_span(section));
+        }
+
+        let converted = borrowed.into_owned()?;
+        assert_eq!(converted.dependencies().count(), 3);
+        assert_eq!(converted.patches().count(), 1);
+        assert_eq!(converted.lints().count(), 1);
+        assert_eq!(converted.to_value(), owned.to_value());
+        assert_eq!(converted, owned);
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_what_owned_rejects() -> Result<()> {
+        let invalid = [
+            MANIFEST.replace("version = \"0.1.0\"", "version = \"0.1\""),
+            MANIFEST.replace("cfg(unix)", "cfg(unix"),
+            MANIFEST.replace("level = \"deny\"", "level = \"loud\""),
+            MANIFEST.replace("rand:0.8.5", "rand"),
+            format!("{}\n[patch.crates-io.foo]\ncolour = \"blue\"\n", MANIFEST),
+        ];
+        for source in &invalid {
+            assert!(Manifest::parse(source).is_err());
+            assert!(BorrowedManifest::parse(source).is_err(), "{}", source);
+        }
+        Ok(())
+    }
+}
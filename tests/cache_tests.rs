@@ -0,0 +1,161 @@
+use anyhow::Result;
+use manifest_parser_rs::*;
+use std::path::PathBuf;
+use std::process::Command;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FULL: &str = include_str!("corpus/full.toml");
+    const BASIC: &str = include_str!("corpus/basic.toml");
+
+    /// Creates an empty cache directory unique to the calling test.
+    fn cache_dir(test: &str) -> Result<PathBuf> {
+        let dir = std::env::temp_dir().join(format!(
+            "manifest_parser_rs-cache-{}-{}",
+            test,
+            std::process::id()
+        ));
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir)?;
+        }
+        Ok(dir)
+    }
+
+    fn entry_path(cache: &ManifestCache, content: &str) -> PathBuf {
+        cache
+            .dir()
+            .join(format!("{}.json", ManifestCache::key(content)))
+    }
+
+    #[test]
+    fn reuses_parse_results() -> Result<()> {
+        let dir = cache_dir("reuse")?;
+        let cache = ManifestCache::open(&dir)?;
+
+        let parsed = cache.parse(FULL)?;
+        assert!(entry_path(&cache, FULL).exists());
+        let cached = cache.get(FULL).expect("the manifest is cached");
+        assert_eq!(
+            format!("{:?}", cached),
+            format!("{:?}", Manifest::parse(FULL)?)
+        );
+        assert_eq!(cached, parsed);
+        assert_eq!(cached.lints().count(), 4);
+        assert_eq!(
+            cached.key_span("profile.release", "lto"),
+            parsed.key_span("profile.release", "lto")
+        );
+
+        // A hit does not parse again, so it returns whatever was stored for the content
+        cache.insert(BASIC, &Manifest::parse(FULL)?)?;
+        assert_eq!(cache.parse(BASIC)?.package_name(), "full");
+
+        // Manifests that do not parse are not cached
+        assert!(cache.parse("[package]\nname = \"x\"\n").is_err());
+        assert_eq!(std::fs::read_dir(&dir)?.count(), 2);
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn invalidation() -> Result<()> {
+        let dir = cache_dir("invalidation")?;
+        let cache = ManifestCache::open(&dir)?;
+        cache.parse(BASIC)?;
+
+        // Any change to the content is another key
+        let edited = BASIC.replace("0.1.0", "0.1.1");
+        assert_ne!(ManifestCache::key(&edited), ManifestCache::key(BASIC));
+        assert!(cache.get(&edited).is_none());
+
+        // Entries that cannot be read are dropped
+        std::fs::write(entry_path(&cache, BASIC), "{\"version\":")?;
+        assert!(cache.get(BASIC).is_none());
+        assert!(!entry_path(&cache, BASIC).exists());
+        assert_eq!(cache.parse(BASIC)?.package_name(), "basic");
+
+        assert!(cache.remove(BASIC)?);
+        assert!(!cache.remove(BASIC)?);
+        cache.parse(BASIC)?;
+        cache.parse(FULL)?;
+        std::fs::write(dir.join("package.json"), "{}")?;
+        std::fs::write(dir.join("notes.tmp"), "")?;
+        assert_eq!(cache.clear()?, 2);
+        assert_eq!(std::fs::read_dir(&dir)?.count(), 2);
+        assert_eq!(cache.size(), 0);
+        assert!(cache.get(FULL).is_none());
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn size_limit() -> Result<()> {
+        let dir = cache_dir("size")?;
+        let cache = ManifestCache::open(&dir)?;
+        cache.parse(FULL)?;
+        let entry = cache.size();
+        assert_eq!(entry, std::fs::metadata(entry_path(&cache, FULL))?.len());
+
+        let cache = ManifestCache::open(&dir)?.with_max_size(entry * 3);
+        assert_eq!(cache.size(), entry);
+        let versions: Vec<String> = (0..10)
+            .map(|patch| FULL.replace("1.2.3-beta.1", &format!("1.2.{}", patch)))
+            .collect();
+        for content in &versions {
+            cache.parse(content)?;
+            assert!(cache.size() <= entry * 3);
+        }
+        assert!(cache.get(versions.last().unwrap()).is_some());
+        assert!(cache.get(&versions[0]).is_none());
+
+        let total: u64 = std::fs::read_dir(&dir)?
+            .map(|item| Ok(item?.metadata()?.len()))
+            .sum::<Result<u64>>()?;
+        assert_eq!(total, cache.size());
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn cli_cache() -> Result<()> {
+        let dir = cache_dir("cli")?;
+        let cache = dir.to_str().unwrap();
+        let run = |args: &[&str]| -> Result<String> {
+            let output = Command::new(env!("CARGO_BIN_EXE_manifest_parser_rs"))
+                .args(args)
+                .output()?;
+            assert!(output.status.success(), "{:?}", output);
+            Ok(String::from_utf8(output.stdout)?)
+        };
+        let basic = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/corpus/basic.toml");
+        let full = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/corpus/full.toml");
+        let args = ["--cache", cache, "parse", "--format", "json", basic, full];
+
+        let first = run(&args)?;
+        assert_eq!(std::fs::read_dir(&dir)?.count(), 2);
+        assert_eq!(run(&args)?, first);
+        assert_eq!(
+            run(&args[2..])?,
+            first,
+            "the cache does not change the output"
+        );
+
+        std::fs::write(dir.join("package.json"), "{}")?;
+        assert_eq!(
+            run(&["clear-cache", cache])?,
+            "Removed 2 cached manifests\n"
+        );
+        assert_eq!(std::fs::read_dir(&dir)?.count(), 1);
+        assert!(dir.join("package.json").exists());
+        std::fs::remove_dir_all(&dir)?;
+
+        let output = Command::new(env!("CARGO_BIN_EXE_manifest_parser_rs"))
+            .args(["clear-cache", cache])
+            .output()?;
+        assert!(!output.status.success());
+        assert!(!dir.exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,142 @@
+use anyhow::Result;
+use manifest_parser_rs::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod expressions {
+        use super::*;
+
+        #[test]
+        fn parse_nested_expression() -> Result<()> {
+            let expr = CfgExpr::parse(r#"cfg(all(unix, not(target_arch = "wasm32")))"#)?;
+
+            assert_eq!(
+                expr,
+                CfgExpr::All(vec![
+                    CfgExpr::Name("unix".to_string()),
+                    CfgExpr::Not(Box::new(CfgExpr::KeyValue(
+                        "target_arch".to_string(),
+                        "wasm32".to_string()
+                    ))),
+                ])
+            );
+            assert_eq!(
+                expr.to_string(),
+                r#"all(unix, not(target_arch = "wasm32"))"#
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn invalid_expressions() {
+            let inputs = [
+                "cfg()",
+                "cfg(unix",
+                "all(unix)",
+                r#"cfg(target_os = linux)"#,
+                "cfg(not(unix, windows))",
+            ];
+
+            for input in inputs {
+                assert!(CfgExpr::parse(input).is_err(), "Should fail: {}", input);
+            }
+        }
+
+        #[test]
+        fn evaluate_against_bundled_targets() -> Result<()> {
+            let expr = CfgExpr::parse(r#"cfg(any(windows, target_os = "macos"))"#)?;
+            let linux = CfgSet::for_target("x86_64-unknown-linux-gnu").unwrap();
+            let windows = CfgSet::for_target("x86_64-pc-windows-msvc").unwrap();
+            let mac = CfgSet::for_target("aarch64-apple-darwin").unwrap();
+
+            assert!(!expr.eval(&linux));
+            assert!(expr.eval(&windows));
+            assert!(expr.eval(&mac));
+
+            let features = CfgSet::new("custom").with_value("feature", "serde");
+            assert!(CfgExpr::parse(r#"cfg(feature = "serde")"#)?.eval(&features));
+            assert!(CfgSet::for_target("unknown-triple").is_none());
+            Ok(())
+        }
+    }
+
+    mod target_tables {
+        use super::*;
+
+        const MANIFEST: &str = r#"
+            [package]
+            name = "app"
+            version = "0.1.0"
+
+            [dependencies]
+            log = "0.4.0"
+
+            [target.'cfg(all(unix, not(target_arch = "wasm32")))'.dependencies]
+            libc = "0.2.0"
+
+            [target.x86_64-pc-windows-msvc.dependencies]
+            windows-sys = "0.52.0"
+
+            [target."cfg(target_family = \"wasm\")".dev-dependencies]
+            wasm-bindgen-test = "0.3.0"
+        "#;
+
+        #[test]
+        fn target_dependency_tables() -> Result<()> {
+            let manifest = Manifest::parse(MANIFEST)?;
+            let deps: Vec<&Dependency> = manifest.dependencies().collect();
+
+            assert_eq!(deps[0].target, None);
+            assert_eq!(
+                deps[2].target,
+                Some(Platform::Triple("x86_64-pc-windows-msvc".to_string()))
+            );
+            assert_eq!(deps[3].kind, DependencyKind::Dev);
+            assert!(matches!(deps[3].target, Some(Platform::Cfg(_))));
+            assert_eq!(
+                manifest.get_by_key("target.x86_64-pc-windows-msvc.dependencies", "windows-sys")?,
+                "0.52.0"
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn dependencies_per_platform() -> Result<()> {
+            let manifest = Manifest::parse(MANIFEST)?;
+            let names = |triple: &str| -> Vec<String> {
+                let cfg = CfgSet::for_target(triple).unwrap();
+                manifest
+                    .dependencies_for(&cfg)
+                    .map(|dependency| dependency.name.clone())
+                    .collect()
+            };
+
+            assert_eq!(names("x86_64-unknown-linux-gnu"), vec!["log", "libc"]);
+            assert_eq!(names("x86_64-pc-windows-msvc"), vec!["log", "windows-sys"]);
+            assert_eq!(
+                names("wasm32-unknown-unknown"),
+                vec!["log", "wasm-bindgen-test"]
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn invalid_cfg_in_table_name() {
+            let input = r#"
+                [package]
+                name = "app"
+                version = "0.1.0"
+
+                [target.'cfg(all(unix)'.dependencies]
+                libc = "0.2.0"
+            "#;
+
+            assert!(matches!(
+                Manifest::parse(input),
+                Err(ManifestError::ParseError(_))
+            ));
+        }
+    }
+}
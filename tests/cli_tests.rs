@@ -0,0 +1,235 @@
+use anyhow::Result;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"[package]
+name = "app"
+version = "0.1.0"
+
+[dependencies]
+serde = "1.0"
+"#;
+
+    /// Writes `MANIFEST` to a file unique to the calling test.
+    fn manifest_file(test: &str) -> Result<PathBuf> {
+        let path = std::env::temp_dir().join(format!(
+            "manifest_parser_rs-{}-{}.toml",
+            test,
+            std::process::id()
+        ));
+        std::fs::write(&path, MANIFEST)?;
+        Ok(path)
+    }
+
+    fn run(args: &[&str]) -> Result<Output> {
+        Ok(Command::new(env!("CARGO_BIN_EXE_manifest_parser_rs"))
+            .args(args)
+            .env("RUST_BACKTRACE", "0")
+            .output()?)
+    }
+
+    fn run_with_stdin(args: &[&str], stdin: &str) -> Result<Output> {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_manifest_parser_rs"))
+            .args(args)
+            .env("RUST_BACKTRACE", "0")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        child.stdin.take().unwrap().write_all(stdin.as_bytes())?;
+        Ok(child.wait_with_output()?)
+    }
+
+    #[test]
+    fn parse_formats() -> Result<()> {
+        let path = manifest_file("parse")?;
+        let file = path.to_str().unwrap();
+
+        let json = run(&["parse", file, "--format", "json"])?;
+        assert!(json.status.success());
+        let value: serde_json::Value = serde_json::from_slice(&json.stdout)?;
+        assert_eq!(value["package"]["name"], "app");
+        assert_eq!(value["dependencies"]["serde"]["version"], "1.0");
+
+        let typed = "[package]\nname = \"app\"\nversion = \"0.1.0\"\npublish = false\n\n[dependencies]\nalpha = { version = \"1\", features = [\"x\"] }\n\n[profile.release]\nlto = true\n";
+        let json = run_with_stdin(&["parse", "-", "--format", "json"], typed)?;
+        let value: serde_json::Value = serde_json::from_slice(&json.stdout)?;
+        assert_eq!(value["package"]["publish"], false);
+        assert_eq!(value["dependencies"]["alpha"]["features"][0], "x");
+        assert_eq!(value["profile"]["release"]["lto"], true);
+        let toml = run_with_stdin(&["parse", "-", "--format", "toml"], typed)?;
+        assert!(String::from_utf8(toml.stdout)?.contains("\n[profile.release]\nlto = true\n"));
+
+        let toml = run(&["get-by-key", file, "package", "version", "--format", "toml"])?;
+        assert_eq!(String::from_utf8(toml.stdout)?, "version = \"0.1.0\"\n");
+
+        let yaml = run(&["get-by-section", file, "dependencies", "--format", "yaml"])?;
+        assert_eq!(String::from_utf8(yaml.stdout)?, "serde: '1.0'\n");
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn query_paths() -> Result<()> {
+        let path = manifest_file("query")?;
+        let file = path.to_str().unwrap();
+
+        let value = run(&["query", file, "package.version"])?;
+        assert_eq!(String::from_utf8(value.stdout)?, "0.1.0\n");
+
+        let wildcard = run(&["query", file, "dependencies.*.version"])?;
+        assert_eq!(
+            String::from_utf8(wildcard.stdout)?,
+            "dependencies.serde.version = \"1.0\"\n"
+        );
+
+        let missing = run(&["query", file, "dependencies.*.git"])?;
+        assert!(!missing.status.success());
+        assert!(String::from_utf8(missing.stderr)?.starts_with("Error: No value matches"));
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn edit_in_place() -> Result<()> {
+        let path = manifest_file("edit")?;
+        let file = path.to_str().unwrap();
+
+        let dry_run = run(&["set", file, "package.version", "0.2.0", "--dry-run"])?;
+        assert!(String::from_utf8(dry_run.stdout)?
+            .contains("-version = \"0.1.0\"\n+version = \"0.2.0\"\n"));
+        assert_eq!(std::fs::read_to_string(&path)?, MANIFEST);
+
+        let set = |value: &str| -> Result<String> {
+            let args = ["set", "-", "package.rust-version", value];
+            Ok(String::from_utf8(run_with_stdin(&args, MANIFEST)?.stdout)?)
+        };
+        assert!(set("1.70")?.contains("rust-version = \"1.70\"\n"));
+        assert!(set("1.70.0")?.contains("rust-version = \"1.70.0\"\n"));
+        assert!(set("true")?.contains("rust-version = true\n"));
+
+        assert!(run(&["add", file, "log@0.4", "--dev"])?.status.success());
+        assert!(run(&["rm", file, "serde"])?.status.success());
+        assert!(std::fs::read_to_string(&path)?
+            .ends_with("[dependencies]\n\n[dev-dependencies]\nlog = \"0.4\"\n"));
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn stdin_and_directories() -> Result<()> {
+        let version = run_with_stdin(&["query", "-", "package.version"], MANIFEST)?;
+        assert_eq!(String::from_utf8(version.stdout)?, "0.1.0\n");
+
+        let root =
+            std::env::temp_dir().join(format!("manifest_parser_rs-tree-{}", std::process::id()));
+        for (dir, content) in [
+            ("app", MANIFEST),
+            ("crates/core", MANIFEST),
+            ("crates/broken", "[package]\nname = \"broken\"\n"),
+            ("target/debug", "not a manifest"),
+        ] {
+            std::fs::create_dir_all(root.join(dir))?;
+            std::fs::write(root.join(dir).join("Cargo.toml"), content)?;
+        }
+        let dir = root.to_str().unwrap();
+
+        let single = run(&["query", &format!("{}/app", dir), "package.name"])?;
+        assert_eq!(String::from_utf8(single.stdout)?, "app\n");
+
+        let all = run(&["query", "--recursive", dir, "package.name"])?;
+        let stdout = String::from_utf8(all.stdout)?;
+        assert_eq!(stdout.lines().count(), 2);
+        assert!(stdout.contains("core/Cargo.toml: app"));
+        assert!(!all.status.success());
+        let stderr = String::from_utf8(all.stderr)?;
+        assert!(stderr.contains("broken/Cargo.toml: Failed to parse manifest"));
+        assert!(stderr.ends_with("Error: 1 of 3 manifests failed\n"));
+
+        // Results keep the order the manifests are given in, and a repeated one appears once
+        let core = format!("{}/crates/core", dir);
+        let app = format!("{}/app", dir);
+        let json = run(&[
+            "get", &core, &app, &core, "package", "name", "--format", "json",
+        ])?;
+        let stdout = String::from_utf8(json.stdout)?;
+        assert_eq!(stdout.matches("core/Cargo.toml").count(), 1);
+        assert!(stdout.find("core/Cargo.toml") < stdout.find("app/Cargo.toml"));
+        let section = run_with_stdin(&["get-by-section", "-", &app, "package"], MANIFEST)?;
+        assert!(String::from_utf8(section.stdout)?
+            .starts_with("Values in section [package] of <stdin>:\nname = app\n"));
+        let flags = run(&["lint-flags", &app, &core])?;
+        assert_eq!(String::from_utf8(flags.stdout)?.lines().count(), 2);
+
+        std::fs::remove_dir_all(root)?;
+        Ok(())
+    }
+
+    #[test]
+    fn lookup_failures_exit_non_zero() -> Result<()> {
+        let path = manifest_file("lookup")?;
+        let file = path.to_str().unwrap();
+
+        for args in [
+            vec!["get-by-key", file, "package", "license"],
+            vec!["get-by-section", file, "features", "--format", "json"],
+        ] {
+            let output = run(&args)?;
+            assert!(!output.status.success());
+            assert!(output.stdout.is_empty());
+            assert!(String::from_utf8(output.stderr)?.starts_with("Error: Missing"));
+        }
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn huge_cache_size() -> Result<()> {
+        let path = manifest_file("cache-size")?;
+        let cache = std::env::temp_dir().join(format!(
+            "manifest_parser_rs-cache-size-{}",
+            std::process::id()
+        ));
+        let output = run(&[
+            "get-by-key",
+            path.to_str().unwrap(),
+            "package",
+            "name",
+            "--cache",
+            cache.to_str().unwrap(),
+            "--cache-size",
+            &u64::MAX.to_string(),
+        ])?;
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8(output.stdout)?, "name = app\n");
+
+        std::fs::remove_file(path)?;
+        std::fs::remove_dir_all(cache)?;
+        Ok(())
+    }
+
+    #[test]
+    fn diff_same_manifest() -> Result<()> {
+        let path = manifest_file("diff")?;
+        let file = path.to_str().unwrap();
+
+        let output = run(&["diff", file, file])?;
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8(output.stdout)?, "No changes\n");
+        let output = run_with_stdin(&["diff", file, "-"], MANIFEST)?;
+        assert_eq!(String::from_utf8(output.stdout)?, "No changes\n");
+        assert!(!run(&["diff", "-", "-"])?.status.success());
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+}
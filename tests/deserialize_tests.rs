@@ -0,0 +1,162 @@
+use anyhow::Result;
+use manifest_parser_rs::*;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"[package]
+name = "app"
+version = "0.1.0"
+edition = "2021"
+
+[package.metadata.mytool]
+level = 3
+targets = ["linux", "wasm"]
+mode = "strict"
+ratio = 0.5
+
+[package.metadata.mytool.limits]
+max-size = 1024
+
+[dependencies]
+serde = { version = "1.0", features = ["derive"] }
+anyhow = "1.0"
+
+[[bin]]
+name = "app"
+path = "src/main.rs"
+"#;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(rename_all = "kebab-case")]
+    enum Mode {
+        Strict,
+        Lenient,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(rename_all = "kebab-case")]
+    struct Limits {
+        max_size: u32,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct MyTool {
+        level: u8,
+        targets: Vec<String>,
+        mode: Mode,
+        ratio: f32,
+        limits: Limits,
+        #[serde(default)]
+        verbose: Option<bool>,
+    }
+
+    #[test]
+    fn metadata_section() -> Result<()> {
+        let manifest = Manifest::parse(MANIFEST)?;
+        let tool: MyTool = manifest.deserialize_section("package.metadata.mytool")?;
+        assert_eq!(
+            tool,
+            MyTool {
+                level: 3,
+                targets: vec!["linux".to_string(), "wasm".to_string()],
+                mode: Mode::Strict,
+                ratio: 0.5,
+                limits: Limits { max_size: 1024 },
+                verbose: None,
+            }
+        );
+
+        let version: String = manifest.deserialize_section("package.version")?;
+        assert_eq!(version, "0.1.0");
+        assert!(matches!(
+            manifest.deserialize_section::<MyTool>("package.metadata.other"),
+            Err(ManifestError::MissingSection(_))
+        ));
+        assert!(matches!(
+            manifest.deserialize_section::<MyTool>("package.metadata.*"),
+            Err(ManifestError::InvalidQuery(_, _))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn whole_manifest() -> Result<()> {
+        #[derive(Debug, Deserialize)]
+        struct Package {
+            name: String,
+            edition: String,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct DependencySpec {
+            version: String,
+            #[serde(default)]
+            features: Vec<String>,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct Target {
+            name: String,
+            path: String,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct CargoToml {
+            package: Package,
+            dependencies: BTreeMap<String, DependencySpec>,
+            bin: Vec<Target>,
+        }
+
+        let cargo: CargoToml = Manifest::parse(MANIFEST)?.deserialize()?;
+        assert_eq!(cargo.package.name, "app");
+        assert_eq!(cargo.package.edition, "2021");
+        assert_eq!(cargo.dependencies["anyhow"].version, "1.0");
+        assert!(cargo.dependencies["anyhow"].features.is_empty());
+        assert_eq!(cargo.dependencies["serde"].features, ["derive"]);
+        assert_eq!(cargo.bin[0].name, "app");
+        assert_eq!(cargo.bin[0].path, "src/main.rs");
+        Ok(())
+    }
+
+    #[test]
+    fn errors_point_at_source() -> Result<()> {
+        let source = MANIFEST.replace("level = 3", "level = 300");
+        let manifest = Manifest::parse(&source)?;
+        let error = manifest
+            .deserialize_section::<MyTool>("package.metadata.mytool")
+            .unwrap_err();
+        match &error {
+            ManifestError::Deserialize(path, message, Some(span)) => {
+                assert_eq!(path, "package.metadata.mytool.level");
+                assert!(message.contains("300"), "{}", message);
+                assert_eq!((span.line, span.column), (7, 1));
+            }
+            error => panic!("unexpected error {:?}", error),
+        }
+        assert!(error.to_string().ends_with("(line 7, column 1)"));
+
+        let source = MANIFEST.replace("mode = \"strict\"", "mode = \"loose\"");
+        let error = Manifest::parse(&source)?
+            .deserialize_section::<MyTool>("package.metadata.mytool")
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            ManifestError::Deserialize(path, _, Some(span)) if path == "package.metadata.mytool.mode" && span.line == 9
+        ));
+
+        let source = MANIFEST.replace("max-size = 1024", "max-items = 1024");
+        let error = Manifest::parse(&source)?
+            .deserialize_section::<MyTool>("package.metadata.mytool")
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            ManifestError::Deserialize(path, message, Some(span))
+                if path == "package.metadata.mytool.limits" && message.contains("max-size") && span.line == 12
+        ));
+        Ok(())
+    }
+}
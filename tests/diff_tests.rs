@@ -0,0 +1,316 @@
+use anyhow::Result;
+use manifest_parser_rs::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD: &str = r#"
+        [package]
+        name = "app"
+        version = "0.1.0"
+        edition = "2018"
+        description = "An app"
+
+        [dependencies]
+        serde = { version = "1.0", features = ["derive", "rc"] }
+        anyhow = "1"
+        log = "0.4"
+        libc = "0.2"
+
+        [features]
+        default = ["std"]
+        std = []
+        unstable = []
+    "#;
+
+    const NEW: &str = r#"
+        [package]
+        name = "app"
+        version = "0.2.0"
+        edition = "2021"
+        license = "MIT"
+
+        [features]
+        default = ["std", "serde"]
+        std = []
+        nightly = []
+
+        [dependencies]
+        anyhow = "1"
+        serde = { version = "1.1", features = ["derive", "alloc"] }
+        log = { git = "https://github.com/rust-lang/log", tag = "0.4.22" }
+        thiserror = "2"
+
+        [target.'cfg(unix)'.dependencies]
+        libc = "0.2"
+    "#;
+
+    #[test]
+    fn structured_changes() -> Result<()> {
+        let changes = Manifest::parse(OLD)?.diff(&Manifest::parse(NEW)?);
+
+        let expected = vec![
+            Change::KeyRemoved {
+                section: "package".to_string(),
+                key: "description".to_string(),
+                value: "An app".to_string(),
+            },
+            Change::KeyChanged {
+                section: "package".to_string(),
+                key: "edition".to_string(),
+                old: "2018".to_string(),
+                new: "2021".to_string(),
+            },
+            Change::KeyChanged {
+                section: "package".to_string(),
+                key: "version".to_string(),
+                old: "0.1.0".to_string(),
+                new: "0.2.0".to_string(),
+            },
+            Change::KeyAdded {
+                section: "package".to_string(),
+                key: "license".to_string(),
+                value: "MIT".to_string(),
+            },
+            Change::RequirementChanged {
+                table: "dependencies".to_string(),
+                name: "log".to_string(),
+                old: Some("0.4".to_string()),
+                new: None,
+            },
+            Change::SourceChanged {
+                table: "dependencies".to_string(),
+                name: "log".to_string(),
+                old: "crates.io".to_string(),
+                new: "git https://github.com/rust-lang/log (tag 0.4.22)".to_string(),
+            },
+            Change::RequirementChanged {
+                table: "dependencies".to_string(),
+                name: "serde".to_string(),
+                old: Some("1.0".to_string()),
+                new: Some("1.1".to_string()),
+            },
+            Change::DependencyFeaturesChanged {
+                table: "dependencies".to_string(),
+                name: "serde".to_string(),
+                added: vec!["alloc".to_string()],
+                removed: vec!["rc".to_string()],
+            },
+            Change::TargetChanged {
+                name: "libc".to_string(),
+                old: "dependencies".to_string(),
+                new: "target.'cfg(unix)'.dependencies".to_string(),
+            },
+            Change::DependencyAdded {
+                table: "dependencies".to_string(),
+                name: "thiserror".to_string(),
+                requirement: Some("2".to_string()),
+            },
+            Change::FeatureChanged {
+                name: "default".to_string(),
+                added: vec!["serde".to_string()],
+                removed: vec![],
+            },
+            Change::FeatureRemoved {
+                name: "unstable".to_string(),
+                enables: vec![],
+            },
+            Change::FeatureAdded {
+                name: "nightly".to_string(),
+                enables: vec![],
+            },
+        ];
+        assert_eq!(changes, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn formatting_and_order_are_ignored() -> Result<()> {
+        let formatted = format_manifest(NEW.trim_start())?;
+        assert!(Manifest::parse(NEW)?
+            .diff(&Manifest::parse(&formatted)?)
+            .is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn values_are_compared_parsed() -> Result<()> {
+        let old = Manifest::parse(
+            r#"[package]
+name = "app"
+version = "0.1.0"
+keywords = ["a","b"]
+
+[lints.rust]
+unsafe_code = { level = "forbid", priority = 1 }
+unused = "warn"
+
+[patch.crates-io]
+foo = { path = "../foo" }
+"#,
+        )?;
+        let new = Manifest::parse(
+            r#"[package]
+name = "app"
+version = "0.1.0"
+keywords = [ "a", "b" ]
+
+[lints.rust]
+unsafe_code = { priority = 1, level = "forbid" }
+unused = { level = "warn" }
+
+[patch.crates-io]
+foo = {path="../foo"}
+"#,
+        )?;
+        assert_eq!(old.diff(&new), vec![]);
+        Ok(())
+    }
+
+    #[test]
+    fn patch_replace_and_lint_changes() -> Result<()> {
+        let old = Manifest::parse(
+            r#"[package]
+name = "app"
+version = "0.1.0"
+
+[lints.rust]
+unused = "warn"
+
+[patch.crates-io]
+foo = { path = "../foo" }
+bar = { path = "../bar" }
+
+[replace]
+"baz:1.0.0" = { path = "../baz" }
+"#,
+        )?;
+        let new = Manifest::parse(
+            r#"[package]
+name = "app"
+version = "0.1.0"
+
+[lints.rust]
+unused = "deny"
+
+[patch.crates-io]
+foo = { git = "https://github.com/example/foo" }
+
+[replace]
+"baz:1.0.0" = { path = "../baz" }
+"#,
+        )?;
+
+        let changes: Vec<String> = old.diff(&new).iter().map(Change::to_string).collect();
+        assert_eq!(
+            changes,
+            vec![
+                "[lints.rust] unused: warn -> deny",
+                "[patch.crates-io] removed bar *",
+                "[patch.crates-io] foo source: path ../foo -> git https://github.com/example/foo",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn dependency_fields() -> Result<()> {
+        let old = Manifest::parse(
+            r#"[package]
+name = "app"
+version = "0.1.0"
+
+[dependencies]
+serde = { version = "1", optional = true }
+json = { version = "1", package = "serde_json" }
+"#,
+        )?;
+        let new = Manifest::parse(
+            r#"[package]
+name = "app"
+version = "0.1.0"
+
+[dependencies]
+serde = { version = "1", default-features = false }
+json = { version = "1", package = "simd-json" }
+"#,
+        )?;
+
+        let changes = old.diff(&new);
+        assert_eq!(
+            changes[0],
+            Change::DependencyFieldChanged {
+                table: "dependencies".to_string(),
+                name: "json".to_string(),
+                field: "package".to_string(),
+                old: "serde_json".to_string(),
+                new: "simd-json".to_string(),
+            }
+        );
+        let changes: Vec<String> = changes.iter().map(Change::to_string).collect();
+        assert_eq!(
+            changes[1..],
+            [
+                "[dependencies] serde optional: true -> false",
+                "[dependencies] serde default-features: true -> false",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn arrays_of_tables() -> Result<()> {
+        let source = r#"[package]
+name = "app"
+version = "0.1.0"
+
+[[bin]]
+name = "first"
+path = "src/first.rs"
+
+[[bin]]
+name = "second"
+path = "src/second.rs"
+"#;
+        let old = Manifest::parse(source)?;
+        let new = Manifest::parse(&source.replace("src/first.rs", "src/bin/first.rs"))?;
+
+        let changes: Vec<String> = old.diff(&new).iter().map(Change::to_string).collect();
+        assert_eq!(
+            changes,
+            vec!["[bin[0]] path: src/first.rs -> src/bin/first.rs"]
+        );
+
+        let third = format!("{}\n[[bin]]\nname = \"third\"\n", source);
+        let changes: Vec<String> = old
+            .diff(&Manifest::parse(&third)?)
+            .iter()
+            .map(Change::to_string)
+            .collect();
+        assert_eq!(changes, vec!["[bin[2]] added name = third"]);
+        assert!(old.diff(&Manifest::parse(source)?).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn text_and_json_output() -> Result<()> {
+        let changes = Manifest::parse(OLD)?.diff(&Manifest::parse(NEW)?);
+
+        assert_eq!(changes[1].to_string(), "[package] edition: 2018 -> 2021");
+        assert_eq!(
+            changes[7].to_string(),
+            "[dependencies] serde features: +alloc -rc"
+        );
+        assert_eq!(
+            changes[8].to_string(),
+            "libc moved from [dependencies] to [target.'cfg(unix)'.dependencies]"
+        );
+
+        let json = serde_json::to_value(&changes[6])?;
+        assert_eq!(json["change"], "requirement-changed");
+        assert_eq!(json["name"], "serde");
+        assert_eq!(json["new"], "1.1");
+        Ok(())
+    }
+}
@@ -0,0 +1,116 @@
+use anyhow::Result;
+use manifest_parser_rs::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"[package]
+name = "app"
+version = "0.1.0"   # bumped by CI
+edition = "2021"
+
+[dependencies]
+# Serialization
+anyhow = "1.0"
+serde = { version = "1.0", features = ["derive"] }
+
+[profile.release]
+lto = true
+"#;
+
+    #[test]
+    fn set_keeps_formatting() -> Result<()> {
+        let edited = set_value(
+            MANIFEST,
+            "package.version",
+            &Value::parse_toml("\"1.2.0\"").unwrap(),
+        )?;
+        assert_eq!(
+            edited,
+            MANIFEST.replace("\"0.1.0\"   # bumped", "\"1.2.0\"   # bumped")
+        );
+
+        let edited = set_value(
+            MANIFEST,
+            "package.license",
+            &Value::String("MIT".to_string()),
+        )?;
+        assert!(edited.contains("edition = \"2021\"\nlicense = \"MIT\"\n\n[dependencies]"));
+
+        let edited = set_value(MANIFEST, "profile.dev.opt-level", &Value::Integer(1))?;
+        assert!(edited.ends_with("lto = true\n\n[profile.dev]\nopt-level = 1\n"));
+        assert_eq!(
+            Manifest::parse(&edited)?.get_by_key("profile.dev", "opt-level")?,
+            "1"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn unset_entries_and_tables() -> Result<()> {
+        let edited = unset_value(MANIFEST, "package.edition")?;
+        assert_eq!(edited, MANIFEST.replace("edition = \"2021\"\n", ""));
+
+        let edited = unset_value(MANIFEST, "profile.release")?;
+        assert!(edited.ends_with("features = [\"derive\"] }\n"));
+
+        assert!(matches!(
+            unset_value(MANIFEST, "package.license"),
+            Err(ManifestError::MissingKey(_, _))
+        ));
+        assert!(matches!(
+            unset_value(MANIFEST, "dependencies.*"),
+            Err(ManifestError::InvalidQuery(_, _))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn add_and_remove_dependencies() -> Result<()> {
+        let mut log = Dependency::new("log", DependencyKind::Normal);
+        log.version = Some("0.4".to_string());
+        let edited = add_dependency(MANIFEST, &log)?;
+        assert!(edited.contains("anyhow = \"1.0\"\nlog = \"0.4\"\nserde = "));
+
+        let mut tokio = Dependency::new("tokio", DependencyKind::Dev);
+        tokio.version = Some("1".to_string());
+        tokio.features = vec!["rt".to_string(), "macros".to_string()];
+        tokio.optional = true;
+        let edited = add_dependency(&edited, &tokio)?;
+        assert!(edited.ends_with(
+            "[dev-dependencies]\ntokio = { version = \"1\", optional = true, features = [\"rt\", \"macros\"] }\n"
+        ));
+
+        let mut serde = Dependency::new("serde", DependencyKind::Normal);
+        serde.version = Some("1.0.200".to_string());
+        serde.features = vec!["rc".to_string()];
+        let source = MANIFEST.replace(
+            "features = [\"derive\"] }",
+            "features = [\"derive\"], default-features = false }",
+        );
+        let merged = add_dependency(&source, &serde)?;
+        assert!(merged.contains(
+            "serde = { version = \"1.0.200\", default-features = false, features = [\"derive\", \"rc\"] }\n"
+        ));
+
+        let edited = remove_dependency(&edited, "anyhow", "dependencies")?;
+        assert!(edited.contains("# Serialization\nlog = \"0.4\"\n"));
+        assert!(matches!(
+            remove_dependency(&edited, "anyhow", "dependencies"),
+            Err(ManifestError::MissingKey(_, _))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn diffs_of_edits() -> Result<()> {
+        let edited = unset_value(MANIFEST, "package.edition")?;
+        assert_eq!(
+            unified_diff("Cargo.toml", MANIFEST, &edited),
+            "--- Cargo.toml\n+++ Cargo.toml\n@@ -1,7 +1,6 @@\n [package]\n name = \"app\"\n version = \"0.1.0\"   # bumped by CI\n-edition = \"2021\"\n \n [dependencies]\n # Serialization\n"
+        );
+        assert!(unified_diff("Cargo.toml", MANIFEST, MANIFEST).is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+/* Reads a manifest through the C API; run by the `c_program` test in tests/ffi_tests.rs. */
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "manifest_parser.h"
+
+static char *read_file(const char *path, size_t *len) {
+    FILE *file = fopen(path, "rb");
+    if (file == NULL) {
+        return NULL;
+    }
+    fseek(file, 0, SEEK_END);
+    *len = (size_t)ftell(file);
+    fseek(file, 0, SEEK_SET);
+    char *content = malloc(*len);
+    if (content != NULL && fread(content, 1, *len, file) != *len) {
+        free(content);
+        content = NULL;
+    }
+    fclose(file);
+    return content;
+}
+
+int main(int argc, char **argv) {
+    size_t len = 0;
+    char *content = argc > 1 ? read_file(argv[1], &len) : NULL;
+    if (content == NULL) {
+        return 2;
+    }
+    MpManifest *manifest = mp_manifest_parse(content, len);
+    free(content);
+    if (manifest == NULL) {
+        fprintf(stderr, "%s\n", mp_last_error());
+        return 1;
+    }
+
+    char *name = mp_manifest_get(manifest, "package", "name");
+    printf("name = %s\n", name);
+    mp_string_free(name);
+    if (mp_manifest_get(manifest, "package", "nope") == NULL) {
+        printf("missing: %s\n", mp_last_error());
+    }
+
+    char *json = mp_manifest_to_json(manifest);
+    int dumped = json != NULL && strstr(json, "\"package\"") != NULL;
+    mp_string_free(json);
+    mp_manifest_free(manifest);
+
+    const char invalid[] = "[package]\nname = \"x\"\n";
+    if (mp_manifest_parse(invalid, strlen(invalid)) == NULL) {
+        printf("error: %.11s", mp_last_error());
+    }
+    return dumped ? 0 : 1;
+}
@@ -0,0 +1,171 @@
+use anyhow::Result;
+use manifest_parser_rs::ffi::*;
+use std::ffi::{c_char, CStr, CString};
+use std::path::Path;
+use std::process::Command;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FULL: &str = include_str!("corpus/full.toml");
+
+    /// Copies a string returned by the C API and releases it.
+    fn take(string: *mut c_char) -> Option<String> {
+        if string.is_null() {
+            return None;
+        }
+        let copy = unsafe { CStr::from_ptr(string) }
+            .to_str()
+            .unwrap()
+            .to_string();
+        unsafe { mp_string_free(string) };
+        Some(copy)
+    }
+
+    fn last_error() -> Option<String> {
+        let error = mp_last_error();
+        (!error.is_null()).then(|| {
+            unsafe { CStr::from_ptr(error) }
+                .to_string_lossy()
+                .into_owned()
+        })
+    }
+
+    #[test]
+    fn parse_get_and_dump() -> Result<()> {
+        let manifest = unsafe { mp_manifest_parse(FULL.as_ptr().cast(), FULL.len()) };
+        assert!(!manifest.is_null());
+        assert_eq!(last_error(), None);
+
+        let get = |section: &str, key: &str| {
+            let section = CString::new(section).unwrap();
+            let key = CString::new(key).unwrap();
+            take(unsafe { mp_manifest_get(manifest, section.as_ptr(), key.as_ptr()) })
+        };
+        assert_eq!(get("package", "name").as_deref(), Some("full"));
+        assert_eq!(get("profile.release", "lto").as_deref(), Some("true"));
+
+        let json = take(unsafe { mp_manifest_to_json(manifest) }).unwrap();
+        let expected = serde_json::to_value(manifest_parser_rs::Manifest::parse(FULL)?)?;
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&json)?, expected);
+        let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus/full.toml");
+        let cli = Command::new(env!("CARGO_BIN_EXE_manifest_parser_rs"))
+            .arg("parse")
+            .arg(corpus)
+            .args(["--format", "json"])
+            .output()?;
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&cli.stdout)?,
+            expected
+        );
+
+        unsafe { mp_manifest_free(manifest) };
+        let version = unsafe { CStr::from_ptr(mp_version()) };
+        assert_eq!(version.to_str()?, env!("CARGO_PKG_VERSION"));
+        Ok(())
+    }
+
+    #[test]
+    fn errors() -> Result<()> {
+        let invalid = "[package]\nname = \"x\"\n";
+        let manifest = unsafe { mp_manifest_parse(invalid.as_ptr().cast(), invalid.len()) };
+        assert!(manifest.is_null());
+        assert!(last_error().unwrap().starts_with("Parse error: "));
+
+        let bytes = b"[package]\xff";
+        assert!(unsafe { mp_manifest_parse(bytes.as_ptr().cast(), bytes.len()) }.is_null());
+        assert!(last_error().unwrap().contains("not UTF-8"));
+        assert!(unsafe { mp_manifest_parse(std::ptr::null(), 0) }.is_null());
+        assert_eq!(
+            last_error().as_deref(),
+            Some("Invalid argument: input is NULL")
+        );
+
+        // The input does not need a NUL terminator, only its length matters
+        let padded = format!("{}garbage", FULL);
+        let manifest = unsafe { mp_manifest_parse(padded.as_ptr().cast(), FULL.len()) };
+        assert!(!manifest.is_null());
+        assert_eq!(last_error(), None);
+
+        let section = CString::new("package")?;
+        let key = CString::new("nope")?;
+        assert_eq!(
+            take(unsafe { mp_manifest_get(manifest, section.as_ptr(), key.as_ptr()) }),
+            None
+        );
+        assert_eq!(
+            last_error().as_deref(),
+            Some("Missing key nope in section package")
+        );
+        assert!(unsafe { mp_manifest_get(manifest, section.as_ptr(), std::ptr::null()) }.is_null());
+        assert_eq!(
+            last_error().as_deref(),
+            Some("Invalid argument: key is NULL")
+        );
+        assert!(unsafe { mp_manifest_to_json(std::ptr::null()) }.is_null());
+
+        unsafe {
+            mp_manifest_free(manifest);
+            mp_manifest_free(std::ptr::null_mut());
+            mp_string_free(std::ptr::null_mut());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn header_is_up_to_date() -> Result<()> {
+        let root = env!("CARGO_MANIFEST_DIR");
+        let config = cbindgen::Config::from_file(Path::new(root).join("cbindgen.toml"))
+            .map_err(anyhow::Error::msg)?;
+        let mut generated = Vec::new();
+        cbindgen::generate_with_config(root, config)?.write(&mut generated);
+        let path = Path::new(root).join("include/manifest_parser.h");
+
+        if std::env::var_os("UPDATE_HEADER").is_some() {
+            std::fs::write(&path, &generated)?;
+        }
+        assert_eq!(
+            String::from_utf8(generated)?,
+            std::fs::read_to_string(path)?,
+            "include/manifest_parser.h is out of date, run `make header`"
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn c_program() -> Result<()> {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        // Test executables live in `target/<profile>/deps`, next to the libraries
+        let exe = std::env::current_exe()?;
+        let target = exe.parent().unwrap().parent().unwrap();
+        let library = target.join("libmanifest_parser_rs.a");
+        let program = target.join(format!("ffi-smoke-{}", std::process::id()));
+
+        let compiled = Command::new(std::env::var("CC").unwrap_or_else(|_| "cc".to_string()))
+            .arg("-I")
+            .arg(root.join("include"))
+            .arg(root.join("tests/ffi/smoke.c"))
+            .arg(&library)
+            .args(["-lpthread", "-ldl", "-lm", "-o"])
+            .arg(&program)
+            .output();
+        let Ok(compiled) = compiled else {
+            eprintln!("skipping: no C compiler");
+            return Ok(());
+        };
+        assert!(compiled.status.success(), "{:?}", compiled);
+
+        let output = Command::new(&program)
+            .arg(root.join("tests/corpus/full.toml"))
+            .output()?;
+        std::fs::remove_file(program)?;
+        assert!(output.status.success(), "{:?}", output);
+        assert_eq!(
+            String::from_utf8(output.stdout)?,
+            "name = full\nmissing: Missing key nope in section package\nerror: Parse error"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,181 @@
+use anyhow::Result;
+use manifest_parser_rs::*;
+use std::path::Path;
+use std::process::Command;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FULL: &str = include_str!("corpus/full.toml");
+
+    /// A `requirements.txt` of `name==version` lines, standing in for another ecosystem.
+    struct Requirements;
+
+    impl ManifestFormat for Requirements {
+        fn id(&self) -> &'static str {
+            "pip"
+        }
+
+        fn file_names(&self) -> &'static [&'static str] {
+            &["requirements.txt"]
+        }
+
+        fn parse(&self, input: &str) -> Result<Package, ManifestError> {
+            let dependencies = input
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| {
+                    let (name, version) = line.split_once("==").ok_or_else(|| {
+                        ManifestError::ParseError(format!("Expected name==version: {}", line))
+                    })?;
+                    Ok(PackageDependency {
+                        name: name.trim().to_string(),
+                        alias: None,
+                        requirement: Some(format!("=={}", version.trim())),
+                        kind: DependencyKind::Normal,
+                        source: DependencySource::Registry(None),
+                        target: None,
+                        optional: false,
+                    })
+                })
+                .collect::<Result<_, ManifestError>>()?;
+            Ok(Package {
+                format: self.id().to_string(),
+                name: None,
+                version: None,
+                dependencies,
+            })
+        }
+    }
+
+    #[test]
+    fn cargo_package() -> Result<()> {
+        let package = CargoFormat.parse(FULL)?;
+        assert_eq!(package, Package::from(&Manifest::parse(FULL)?));
+        assert_eq!(package.format, "cargo");
+        assert_eq!(package.name.as_deref(), Some("full"));
+        assert_eq!(package.version.as_deref(), Some("1.2.3-beta.1+build.5"));
+        assert_eq!(package.dependencies.len(), 16);
+
+        let find = |name: &str| {
+            package
+                .dependencies
+                .iter()
+                .find(|dependency| dependency.name == name)
+                .unwrap()
+        };
+        let renamed = find("original");
+        assert_eq!(renamed.alias.as_deref(), Some("renamed"));
+        assert_eq!(renamed.requirement.as_deref(), Some("^0.3.1-alpha.2"));
+        assert_eq!(
+            find("pinned").source,
+            DependencySource::Git {
+                url: "https://github.com/example/pinned".to_string(),
+                reference: Some("abc123".to_string()),
+            }
+        );
+        assert_eq!(find("shared").source, DependencySource::Workspace);
+        assert_eq!(
+            find("private").source,
+            DependencySource::Registry(Some("internal".to_string()))
+        );
+        let winapi = find("winapi");
+        assert_eq!(winapi.kind, DependencyKind::Dev);
+        assert_eq!(
+            winapi.target.as_deref(),
+            Some("cfg(all(windows, target_arch = \"x86_64\"))")
+        );
+        assert!(find("tokio").optional);
+        assert_eq!(find("local").to_string(), "local (path ../local)");
+
+        Ok(())
+    }
+
+    #[test]
+    fn pluggable_formats() -> Result<()> {
+        let mut formats = Formats::new();
+        formats.add_format(Box::new(Requirements));
+        let ids: Vec<&str> = formats.formats().map(|format| format.id()).collect();
+        assert_eq!(ids, ["cargo", "pip"]);
+        assert_eq!(formats.get("pip")?.file_names(), ["requirements.txt"]);
+        assert!(matches!(
+            formats.get("npm"),
+            Err(ManifestError::UnknownFormat(id)) if id == "npm"
+        ));
+
+        let detected = |path: &str| formats.detect(Path::new(path)).map(|format| format.id());
+        assert_eq!(detected("crates/app/Cargo.toml"), Some("cargo"));
+        assert_eq!(detected("service/requirements.txt"), Some("pip"));
+        assert_eq!(detected("web/package.json"), None);
+
+        // The same listing works for every format
+        let packages = [
+            formats.parse(Path::new("Cargo.toml"), FULL)?,
+            formats.parse(
+                Path::new("requirements.txt"),
+                "requests==2.31.0\nflask==3.0\n",
+            )?,
+        ];
+        let listing: Vec<String> = packages
+            .iter()
+            .flat_map(|package| &package.dependencies)
+            .filter(|dependency| dependency.kind == DependencyKind::Build)
+            .chain(&packages[1].dependencies)
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            listing,
+            [
+                "cc 1.0 (build-dependencies)",
+                "pkg-config 0.3 (build-dependencies, target x86_64-unknown-linux-gnu)",
+                "requests ==2.31.0",
+                "flask ==3.0"
+            ]
+        );
+
+        assert!(formats
+            .parse(Path::new("requirements.txt"), "requests\n")
+            .is_err());
+        assert_eq!(
+            formats
+                .parse(Path::new("package.json"), "{}")
+                .unwrap_err()
+                .to_string(),
+            "Unknown manifest format: package.json"
+        );
+        assert_eq!(Formats::empty().formats().count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn cli_deps() -> Result<()> {
+        let run = |args: &[&str]| {
+            Command::new(env!("CARGO_BIN_EXE_manifest_parser_rs"))
+                .args(args)
+                .output()
+        };
+        let basic = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/corpus/basic.toml");
+        let full = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/corpus/full.toml");
+
+        let output = run(&["deps", full])?;
+        assert!(output.status.success());
+        let text = String::from_utf8(output.stdout)?;
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 16);
+        assert_eq!(lines[0], "serde 1.0");
+        assert!(lines.contains(&"original ^0.3.1-alpha.2 (as renamed)"));
+
+        let output = run(&["deps", "--format", "json", basic, full])?;
+        assert!(output.status.success());
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+        assert_eq!(json[full]["name"], "full");
+        assert_eq!(json[full]["dependencies"][3]["source"]["path"], "../local");
+        assert_eq!(json[basic]["format"], "cargo");
+
+        let output = run(&["deps", "--manifest-format", "npm", full])?;
+        assert!(!output.status.success());
+        assert!(String::from_utf8(output.stderr)?.contains("Unknown manifest format: npm"));
+        Ok(())
+    }
+}
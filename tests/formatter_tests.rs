@@ -0,0 +1,132 @@
+use anyhow::Result;
+use manifest_parser_rs::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MESSY: &str = r#"# Top comment
+[package]
+name="app"
+version =   "0.1.0"
+  edition = "2021"   # edition
+
+[dependencies]
+# Serialization
+serde   = {version="1",features=["derive","rc"],default_features=false}
+anyhow="1"
+
+[target.'cfg(unix)'.dependencies]
+libc = "0.2"
+
+[lints.rust]
+unexpected_cfgs = {level="warn",check-cfg=['cfg(tarpaulin)']}
+
+[features]
+default = ["std","rc",]
+
+[lib]
+path = "src/lib.rs"
+
+[dev-dependencies]
+tempfile = "3"
+# trailing comment
+"#;
+
+    const FORMATTED: &str = r#"# Top comment
+[package]
+name = "app"
+version = "0.1.0"
+edition = "2021" # edition
+
+[lib]
+path = "src/lib.rs"
+
+[features]
+default = ["std", "rc"]
+
+[dependencies]
+anyhow = "1"
+# Serialization
+serde = { version = "1", features = ["derive", "rc"], default-features = false }
+
+[dev-dependencies]
+tempfile = "3"
+
+[target."cfg(unix)".dependencies]
+libc = "0.2"
+
+[lints.rust]
+unexpected_cfgs = { level = "warn", check-cfg = ["cfg(tarpaulin)"] }
+
+# trailing comment
+"#;
+
+    #[test]
+    fn canonical_layout() -> Result<()> {
+        assert_eq!(format_manifest(MESSY)?, FORMATTED);
+        assert!(!is_formatted(MESSY)?);
+        assert!(is_formatted(FORMATTED)?);
+        Ok(())
+    }
+
+    #[test]
+    fn formatting_preserves_contents() -> Result<()> {
+        let before = Manifest::parse(MESSY)?;
+        let after = Manifest::parse(&format_manifest(MESSY)?)?;
+
+        assert_eq!(before.get_by_key("package", "edition")?, "2021");
+        assert_eq!(after.get_by_key("package", "edition")?, "2021");
+        let mut names: Vec<&str> = after.dependencies().map(|d| d.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, vec!["anyhow", "libc", "serde", "tempfile"]);
+        assert_eq!(
+            before.dependencies().find(|d| d.name == "serde"),
+            after.dependencies().find(|d| d.name == "serde")
+        );
+        assert_eq!(
+            before.lints().collect::<Vec<_>>(),
+            after.lints().collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn inline_tables_are_aligned() -> Result<()> {
+        let source = "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[dependencies]\nanyhow = \"1\"\ntokio-util = {version=\"0.7\"}\nserde = {version=\"1\"}\ntoml = \"0.8\"\n\n[lints.rust]\nunsafe_code = {level=\"forbid\"}\nunused = {level=\"warn\",priority=-1}\n";
+        let formatted = r#"[package]
+name = "app"
+version = "0.1.0"
+
+[dependencies]
+anyhow = "1"
+serde      = { version = "1" }
+tokio-util = { version = "0.7" }
+toml = "0.8"
+
+[lints.rust]
+unsafe_code = { level = "forbid" }
+unused      = { level = "warn", priority = -1 }
+"#;
+
+        assert_eq!(format_manifest(source)?, formatted);
+        assert!(is_formatted(formatted)?);
+        Ok(())
+    }
+
+    #[test]
+    fn comments_inside_entries_are_kept() -> Result<()> {
+        let source = "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[dependencies]\nserde = {\n    version = \"1\", # pinned by the registry\n}\n";
+
+        assert_eq!(format_manifest(source)?, source);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_manifests_are_rejected() {
+        assert!(matches!(
+            format_manifest("[dependencies]\nserde = \"1\"\n"),
+            Err(ManifestError::ParseError(_))
+        ));
+    }
+}
@@ -0,0 +1,104 @@
+use anyhow::Result;
+use manifest_parser_rs::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(name: &str, dependencies: &str) -> Manifest {
+        Manifest::parse(&format!(
+            r#"
+            [package]
+            name = "{}"
+            version = "0.1.0"
+            {}
+        "#,
+            name, dependencies
+        ))
+        .unwrap()
+    }
+
+    fn workspace() -> Vec<Manifest> {
+        vec![
+            member(
+                "cli",
+                r#"[dependencies]
+                core = { path = "../core" }
+                macros = { workspace = true }
+                serde = "1.0.0""#,
+            ),
+            member(
+                "macros",
+                r#"[build-dependencies]
+                codegen = { path = "../codegen" }"#,
+            ),
+            member(
+                "core",
+                r#"[dev-dependencies]
+                cli = { path = "../cli" }"#,
+            ),
+            member("codegen", ""),
+        ]
+    }
+
+    #[test]
+    fn topological_order() -> Result<()> {
+        let manifests = workspace();
+        let graph = DependencyGraph::new(&manifests);
+
+        assert_eq!(
+            graph.topological_order()?,
+            vec!["core", "codegen", "macros", "cli"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn edges_and_reverse_dependencies() -> Result<()> {
+        let manifests = workspace();
+        let graph = DependencyGraph::new(&manifests);
+
+        assert_eq!(
+            graph.dependencies_of("cli")?,
+            vec![
+                ("core", DependencyKind::Normal),
+                ("macros", DependencyKind::Normal)
+            ]
+        );
+        assert_eq!(
+            graph.dependents_of("cli")?,
+            vec![("core", DependencyKind::Dev)]
+        );
+        assert_eq!(
+            graph.transitive_dependents_of("codegen")?,
+            vec!["cli", "macros"]
+        );
+        assert!(matches!(
+            graph.dependents_of("serde"),
+            Err(ManifestError::MissingMember(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn cycles_are_reported() {
+        let manifests = vec![
+            member(
+                "a",
+                r#"[dependencies]
+                b = { path = "../b" }"#,
+            ),
+            member(
+                "b",
+                r#"[build-dependencies]
+                a = { path = "../a" }"#,
+            ),
+        ];
+        let graph = DependencyGraph::new(&manifests);
+
+        match graph.topological_order() {
+            Err(ManifestError::DependencyCycle(cycle)) => assert_eq!(cycle, vec!["a", "b"]),
+            other => panic!("Expected a cycle, got {:?}", other),
+        }
+    }
+}
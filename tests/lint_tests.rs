@@ -0,0 +1,180 @@
+use anyhow::Result;
+use manifest_parser_rs::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"[package]
+name = "app"
+version = "0.1.0"
+license = "MIT"
+description = "An app"
+repository = "https://github.com/example/app"
+
+[dependencies]
+serde = "1"
+anyhow = "*"
+local = { path = "../local" }
+remote = { git = "https://github.com/example/remote", rev = "abc123" }
+
+[dev-dependencies]
+helper = { path = "../helper" }
+"#;
+
+    fn rules(diagnostics: &[Diagnostic]) -> Vec<&str> {
+        diagnostics.iter().map(|d| d.rule.as_str()).collect()
+    }
+
+    #[test]
+    fn builtin_rules() -> Result<()> {
+        let manifest = Manifest::parse(MANIFEST)?;
+        let diagnostics = Linter::new().check(&manifest, MANIFEST);
+
+        assert_eq!(
+            rules(&diagnostics),
+            vec![
+                "unsorted-dependencies",
+                "wildcard-requirement",
+                "path-without-version"
+            ]
+        );
+        assert!(diagnostics[1].is_error());
+        assert_eq!(diagnostics[1].span.map(|span| span.line), Some(10));
+        assert!(!diagnostics[0].is_error());
+        Ok(())
+    }
+
+    #[test]
+    fn rules_have_own_descriptions() {
+        let linter = Linter::new();
+        let mut descriptions: Vec<&str> = linter.rules().map(|rule| rule.description()).collect();
+        let count = descriptions.len();
+        descriptions.sort_unstable();
+        descriptions.dedup();
+        assert_eq!(descriptions.len(), count);
+    }
+
+    #[test]
+    fn configured_levels() -> Result<()> {
+        let manifest = Manifest::parse(MANIFEST)?;
+        let mut linter = Linter::new();
+        linter.set_level("path-without-version", LintLevel::Allow)?;
+        linter.set_level("unsorted-dependencies", LintLevel::Deny)?;
+        let diagnostics = linter.check(&manifest, MANIFEST);
+
+        assert_eq!(
+            rules(&diagnostics),
+            vec!["unsorted-dependencies", "wildcard-requirement"]
+        );
+        assert!(diagnostics.iter().all(Diagnostic::is_error));
+        assert!(matches!(
+            linter.set_level("no-such-rule", LintLevel::Warn),
+            Err(ManifestError::UnknownRule(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn missing_metadata_and_unpublished_crates() -> Result<()> {
+        let source = r#"[package]
+name = "internal"
+version = "0.1.0"
+publish = false
+
+[dependencies]
+local = { path = "../local" }
+pinned = { git = "https://github.com/example/pinned" }
+"#;
+        let manifest = Manifest::parse(source)?;
+        let diagnostics = Linter::new().check(&manifest, source);
+
+        assert_eq!(
+            rules(&diagnostics),
+            vec![
+                "missing-license",
+                "missing-description",
+                "missing-repository",
+                "git-without-rev"
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn autofixes() -> Result<()> {
+        let source = r#"[package]
+name = "app"
+version = "0.1.0"
+license = "MIT"
+description = "An app"
+repository = "https://github.com/example/app"
+
+[dependencies]
+toml = "0.8"
+serde = { version = "1", default_features = false }
+anyhow = "1"
+
+[lib]
+proc_macro = true
+"#;
+        let manifest = Manifest::parse(source)?;
+        let diagnostics = Linter::new().check(&manifest, source);
+        assert_eq!(
+            rules(&diagnostics),
+            vec![
+                "unsorted-dependencies",
+                "deprecated-keys",
+                "deprecated-keys"
+            ]
+        );
+
+        // The rename inside the table overlaps the sort and waits for a second pass
+        let fixed = apply_fixes(source, &diagnostics);
+        assert!(fixed.contains(
+            "anyhow = \"1\"\nserde = { version = \"1\", default_features = false }\ntoml = \"0.8\"\n"
+        ));
+        assert!(fixed.contains("proc-macro = true"));
+
+        let diagnostics = Linter::new().check(&Manifest::parse(&fixed)?, &fixed);
+        assert_eq!(rules(&diagnostics), vec!["deprecated-keys"]);
+        let fixed = apply_fixes(&fixed, &diagnostics);
+        assert!(fixed.contains("default-features = false"));
+        assert!(Linter::new()
+            .check(&Manifest::parse(&fixed)?, &fixed)
+            .is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn default_features_only_as_a_field() -> Result<()> {
+        let source = r#"[package]
+name = "app"
+version = "0.1.0"
+license = "MIT"
+description = "An app"
+repository = "https://github.com/example/app"
+
+[dependencies]
+my_default_features = "1"
+other = { version = "1", features = ["default_features"] }
+"#;
+        let manifest = Manifest::parse(source)?;
+        assert!(Linter::new().check(&manifest, source).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn comments_block_sorting_fix() -> Result<()> {
+        let source = "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[dependencies]\nserde = \"1\"\n# error handling\nanyhow = \"1\"\n";
+        let manifest = Manifest::parse(source)?;
+        let diagnostics = Linter::new().check(&manifest, source);
+        let unsorted = diagnostics
+            .iter()
+            .find(|d| d.rule == "unsorted-dependencies")
+            .unwrap();
+
+        assert!(unsorted.fix.is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+use anyhow::Result;
+use manifest_parser_rs::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+        [package]
+        name = "app"
+        version = "0.1.0"
+
+        [lints.rust]
+        unsafe_code = "forbid"
+        unexpected_cfgs = { level = "warn", check-cfg = ['cfg(tarpaulin)'] }
+
+        [lints.clippy]
+        all = { level = "deny", priority = -1 }
+        module_name_repetitions = "allow"
+
+        [lints.rustdoc]
+        broken_intra_doc_links = { priority = 1, level = "deny" }
+
+        [workspace.lints.clippy]
+        pedantic = "warn"
+    "#;
+
+    #[test]
+    fn short_and_table_forms() -> Result<()> {
+        let manifest = Manifest::parse(MANIFEST)?;
+        let lints: Vec<&Lint> = manifest.lints().collect();
+
+        assert_eq!(lints.len(), 5);
+        assert_eq!(lints[0].tool, "rust");
+        assert_eq!(lints[0].level, LintLevel::Forbid);
+        assert_eq!(lints[1].check_cfg, vec!["cfg(tarpaulin)"]);
+        assert_eq!(lints[2].qualified_name(), "clippy::all");
+        assert_eq!(lints[2].priority, -1);
+        assert_eq!(lints[4].level, LintLevel::Deny);
+
+        let workspace: Vec<&Lint> = manifest.workspace_lints().collect();
+        assert_eq!(workspace.len(), 1);
+        assert_eq!(workspace[0].name, "pedantic");
+
+        assert_eq!(manifest.get_by_key("lints.rust", "unsafe_code")?, "forbid");
+        assert!(!manifest.inherits_workspace_lints());
+        Ok(())
+    }
+
+    #[test]
+    fn flag_export_sorted_by_priority() -> Result<()> {
+        let manifest = Manifest::parse(MANIFEST)?;
+
+        assert_eq!(
+            lint_args(manifest.lints()),
+            vec![
+                "-D",
+                "clippy::all",
+                "-A",
+                "clippy::module_name_repetitions",
+                "-W",
+                "unexpected_cfgs",
+                "--check-cfg",
+                "cfg(tarpaulin)",
+                "-F",
+                "unsafe_code",
+                "-D",
+                "rustdoc::broken_intra_doc_links",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn inherited_lints() -> Result<()> {
+        let manifest = Manifest::parse(
+            r#"
+            [package]
+            name = "member"
+            version = "0.1.0"
+
+            [lints]
+            workspace = true
+        "#,
+        )?;
+
+        assert!(manifest.inherits_workspace_lints());
+        assert_eq!(manifest.lints().count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_levels() {
+        let inputs = [
+            r#"unsafe_code = "forbidden""#,
+            r#"all = { priority = -1 }"#,
+            r#"all = { level = "loud", priority = 1 }"#,
+        ];
+
+        for input in inputs {
+            let manifest = format!(
+                "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[lints.clippy]\n{}\n",
+                input
+            );
+            assert!(
+                matches!(
+                    Manifest::parse(&manifest),
+                    Err(ManifestError::InvalidValue(_, _, _))
+                ),
+                "Should fail: {}",
+                input
+            );
+        }
+    }
+}
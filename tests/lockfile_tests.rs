@@ -0,0 +1,227 @@
+use anyhow::Result;
+use manifest_parser_rs::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOCK: &str = r#"
+        # This file is automatically @generated by Cargo.
+        version = 3
+
+        [[package]]
+        name = "app"
+        version = "0.1.0"
+        dependencies = [
+         "helper",
+         "serde",
+        ]
+
+        [[package]]
+        name = "helper"
+        version = "0.1.0"
+
+        [[package]]
+        name = "serde"
+        version = "1.0.200"
+        source = "registry+https://github.com/rust-lang/crates.io-index"
+        checksum = "abc"
+        dependencies = [
+         "serde_derive",
+        ]
+
+        [[package]]
+        name = "serde_derive"
+        version = "1.0.200"
+        source = "registry+https://github.com/rust-lang/crates.io-index"
+    "#;
+
+    mod dependencies {
+        use super::*;
+
+        #[test]
+        fn typed_dependency_tables() -> Result<()> {
+            let manifest = Manifest::parse(
+                r#"
+                [package]
+                name = "app"
+                version = "0.1.0"
+
+                [dependencies]
+                serde = "^1.0"
+                helper = { path = "../helper", optional = true }
+
+                [dev-dependencies]
+                tokio = { version = "1.0.0", features = ["full"], default-features = false }
+
+                [build-dependencies]
+                cc = { git = "https://github.com/rust-lang/cc-rs", rev = "abc123" }
+            "#,
+            )?;
+
+            let deps: Vec<&Dependency> = manifest.dependencies().collect();
+            assert_eq!(deps.len(), 4);
+            assert_eq!(deps[0].version.as_deref(), Some("^1.0"));
+            assert_eq!(deps[1].path.as_deref(), Some("../helper"));
+            assert!(deps[1].optional);
+            assert_eq!(deps[2].kind, DependencyKind::Dev);
+            assert!(!deps[2].default_features);
+            assert_eq!(deps[2].features, vec!["full"]);
+            assert_eq!(deps[3].kind, DependencyKind::Build);
+            assert_eq!(deps[3].rev.as_deref(), Some("abc123"));
+
+            assert!(manifest.get_by_key("dev-dependencies", "tokio").is_ok());
+            assert!(manifest.get_by_key("build-dependencies", "cc").is_ok());
+            Ok(())
+        }
+    }
+
+    mod parsing {
+        use super::*;
+
+        #[test]
+        fn lockfile_packages() -> Result<()> {
+            let lock = Lockfile::parse(LOCK)?;
+            assert_eq!(lock.version(), Some(3));
+
+            let packages: Vec<&LockedPackage> = lock.packages().collect();
+            assert_eq!(packages.len(), 4);
+            assert_eq!(packages[0].dependencies, vec!["helper", "serde"]);
+            assert_eq!(packages[1].source, None);
+            assert_eq!(packages[2].checksum.as_deref(), Some("abc"));
+            Ok(())
+        }
+
+        #[test]
+        fn unknown_tables_are_ignored() -> Result<()> {
+            let lock = Lockfile::parse(&format!(
+                "{}\n[[patch.unused]]\nname = \"foo\"\nversion = \"0.1.0\"\n\n[future]\nflag = true\ncount = 2\n",
+                LOCK
+            ))?;
+            assert_eq!(lock.packages().count(), 4);
+            Ok(())
+        }
+
+        #[test]
+        fn invalid_lockfile() {
+            assert!(Lockfile::parse("[[package]]\nversion = \"1.0.0\"").is_err());
+            assert!(Lockfile::parse("[package]\nname = \"a\"").is_err());
+        }
+    }
+
+    mod check {
+        use super::*;
+
+        fn app(dependencies: &str) -> Manifest {
+            Manifest::parse(&format!(
+                r#"
+                [package]
+                name = "app"
+                version = "0.1.0"
+
+                [dependencies]
+                {}
+            "#,
+                dependencies
+            ))
+            .unwrap()
+        }
+
+        #[test]
+        fn up_to_date() -> Result<()> {
+            let lock = Lockfile::parse(LOCK)?;
+            let manifest = app(r#"serde = "1.0.100"
+                helper = { path = "../helper" }"#);
+
+            assert_eq!(lock.check(&[manifest]), vec![]);
+            Ok(())
+        }
+
+        #[test]
+        fn unsatisfied_and_missing() -> Result<()> {
+            let lock = Lockfile::parse(LOCK)?;
+            let manifest = app(r#"serde = "1.0.300"
+                helper = { path = "../helper" }
+                rand = "0.8.0""#);
+
+            let issues = lock.check(&[manifest]);
+            assert!(issues.contains(&LockIssue::Unsatisfied {
+                package: "app".to_string(),
+                dependency: "serde".to_string(),
+                requirement: "1.0.300".to_string(),
+                locked: vec!["1.0.200".to_string()],
+            }));
+            assert!(issues.contains(&LockIssue::MissingFromLock {
+                package: "app".to_string(),
+                dependency: "rand".to_string(),
+            }));
+            Ok(())
+        }
+
+        #[test]
+        fn stale_entries() -> Result<()> {
+            let lock = Lockfile::parse(LOCK)?;
+            let manifest = app(r#"helper = { path = "../helper" }"#);
+
+            let issues = lock.check(&[manifest]);
+            assert_eq!(
+                issues,
+                vec![
+                    LockIssue::StaleEntry {
+                        name: "serde".to_string(),
+                        version: "1.0.200".to_string(),
+                    },
+                    LockIssue::StaleEntry {
+                        name: "serde_derive".to_string(),
+                        version: "1.0.200".to_string(),
+                    },
+                ]
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn source_mismatch() -> Result<()> {
+            let lock = Lockfile::parse(LOCK)?;
+            let manifest = app(r#"serde = { path = "../serde" }
+                helper = { git = "https://github.com/example/helper" }"#);
+
+            let issues = lock.check(&[manifest]);
+            let mismatched: Vec<&str> = issues
+                .iter()
+                .filter_map(|issue| match issue {
+                    LockIssue::SourceMismatch { dependency, .. } => Some(dependency.as_str()),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(mismatched, vec!["serde", "helper"]);
+            Ok(())
+        }
+
+        #[test]
+        fn git_references() -> Result<()> {
+            let lock = Lockfile::parse(
+                r#"
+                [[package]]
+                name = "app"
+                version = "0.1.0"
+
+                [[package]]
+                name = "helper"
+                version = "0.2.0"
+                source = "git+https://github.com/example/helper?branch=dev#0123456789abcdef"
+            "#,
+            )?;
+
+            let matching = app(
+                r#"helper = { git = "https://github.com/example/helper.git", branch = "dev" }"#,
+            );
+            assert_eq!(lock.check(&[matching]), vec![]);
+
+            let other_branch =
+                app(r#"helper = { git = "https://github.com/example/helper", branch = "main" }"#);
+            assert_eq!(lock.check(&[other_branch]).len(), 1);
+            Ok(())
+        }
+    }
+}
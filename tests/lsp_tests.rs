@@ -0,0 +1,268 @@
+use anyhow::Result;
+use lsp_server::{Connection, Message, Notification, Request, RequestId};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidOpenTextDocument, Exit, Initialized, Notification as _,
+    PublishDiagnostics,
+};
+use lsp_types::request::{
+    Completion, Formatting, GotoDefinition, HoverRequest, Initialize, Shutdown,
+};
+use lsp_types::*;
+use manifest_parser_rs::serve;
+use std::path::Path;
+use std::str::FromStr;
+use std::thread::JoinHandle;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"[package]
+name = "app"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+serde = { version = "1.0", features = ["derive"] }
+"#;
+
+    /// A client talking to a server running on another thread.
+    struct Client {
+        connection: Connection,
+        server: JoinHandle<Result<(), manifest_parser_rs::ManifestError>>,
+        next_id: i32,
+    }
+
+    impl Client {
+        fn start() -> Result<Self> {
+            let (connection, server) = Connection::memory();
+            let server = std::thread::spawn(move || serve(&server));
+            let mut client = Client {
+                connection,
+                server,
+                next_id: 0,
+            };
+            client.request::<Initialize>(InitializeParams::default())?;
+            client.notify::<Initialized>(InitializedParams {})?;
+            Ok(client)
+        }
+
+        fn request<R: request::Request>(&mut self, params: R::Params) -> Result<R::Result> {
+            self.next_id += 1;
+            let id = RequestId::from(self.next_id);
+            let request = Request::new(id.clone(), R::METHOD.to_string(), params);
+            self.connection.sender.send(Message::Request(request))?;
+            loop {
+                if let Message::Response(response) = self.connection.receiver.recv()? {
+                    assert_eq!(response.id, id);
+                    assert!(response.error.is_none(), "{:?}", response.error);
+                    return Ok(serde_json::from_value(response.result.unwrap_or_default())?);
+                }
+            }
+        }
+
+        fn notify<N: notification::Notification>(&self, params: N::Params) -> Result<()> {
+            let notification = Notification::new(N::METHOD.to_string(), params);
+            self.connection
+                .sender
+                .send(Message::Notification(notification))?;
+            Ok(())
+        }
+
+        /// Opens a document and waits for its diagnostics.
+        fn open(&self, uri: &Uri, text: &str) -> Result<Vec<lsp_types::Diagnostic>> {
+            self.notify::<DidOpenTextDocument>(DidOpenTextDocumentParams {
+                text_document: TextDocumentItem::new(
+                    uri.clone(),
+                    "toml".to_string(),
+                    1,
+                    text.to_string(),
+                ),
+            })?;
+            self.diagnostics()
+        }
+
+        fn diagnostics(&self) -> Result<Vec<lsp_types::Diagnostic>> {
+            match self.connection.receiver.recv()? {
+                Message::Notification(notification)
+                    if notification.method == PublishDiagnostics::METHOD =>
+                {
+                    let params: PublishDiagnosticsParams =
+                        serde_json::from_value(notification.params)?;
+                    Ok(params.diagnostics)
+                }
+                message => anyhow::bail!("unexpected message {:?}", message),
+            }
+        }
+
+        fn stop(mut self) -> Result<()> {
+            self.request::<Shutdown>(())?;
+            self.notify::<Exit>(())?;
+            self.server.join().unwrap()?;
+            Ok(())
+        }
+    }
+
+    fn uri(path: &Path) -> Result<Uri> {
+        Ok(Uri::from_str(&format!("file://{}", path.display()))?)
+    }
+
+    fn at(uri: &Uri, line: u32, character: u32) -> TextDocumentPositionParams {
+        TextDocumentPositionParams::new(
+            TextDocumentIdentifier::new(uri.clone()),
+            Position::new(line, character),
+        )
+    }
+
+    #[test]
+    fn diagnostics_follow_edits() -> Result<()> {
+        let client = Client::start()?;
+        let uri = uri(Path::new("/project/Cargo.toml"))?;
+
+        let diagnostics = client.open(&uri, "[package]\nname = \"app\"\nversion = \"1\"\n")?;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::ERROR));
+        assert_eq!(diagnostics[0].range.start, Position::new(2, 10));
+
+        client.notify::<DidChangeTextDocument>(DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier::new(uri.clone(), 2),
+            content_changes: vec![TextDocumentContentChangeEvent {
+                range: None,
+                range_length: None,
+                text: MANIFEST.to_string(),
+            }],
+        })?;
+        let diagnostics = client.diagnostics()?;
+        let codes: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.severity, d.code.clone()))
+            .collect();
+        assert!(codes.contains(&(
+            Some(DiagnosticSeverity::WARNING),
+            Some(NumberOrString::String("missing-license".to_string()))
+        )));
+        assert!(diagnostics
+            .iter()
+            .all(|d| d.severity == Some(DiagnosticSeverity::WARNING)));
+        client.stop()
+    }
+
+    #[test]
+    fn completion_and_hover() -> Result<()> {
+        let mut client = Client::start()?;
+        let uri = uri(Path::new("/project/Cargo.toml"))?;
+        let text = format!("{}\n[\n[[\n[profile.release]\nl\n", MANIFEST);
+        client.open(&uri, &text)?;
+
+        let labels = |response: Option<CompletionResponse>| -> Vec<String> {
+            match response {
+                Some(CompletionResponse::Array(items)) => {
+                    items.into_iter().map(|item| item.label).collect()
+                }
+                _ => Vec::new(),
+            }
+        };
+        let complete = |client: &mut Client, line, character| {
+            client.request::<Completion>(CompletionParams {
+                text_document_position: at(&uri, line, character),
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+                context: None,
+            })
+        };
+
+        let tables = labels(complete(&mut client, 8, 1)?);
+        assert!(tables.contains(&"workspace.dependencies".to_string()));
+        assert!(tables.contains(&"profile.release".to_string()));
+        assert_eq!(
+            labels(complete(&mut client, 9, 2)?),
+            ["bin", "example", "test", "bench"]
+        );
+        assert!(labels(complete(&mut client, 11, 1)?).contains(&"lto".to_string()));
+        assert!(labels(complete(&mut client, 1, 0)?).contains(&"rust-version".to_string()));
+        assert!(labels(complete(&mut client, 6, 28)?).contains(&"default-features".to_string()));
+        assert!(labels(complete(&mut client, 6, 0)?).is_empty());
+
+        let hover = |client: &mut Client, line, character| -> Result<String> {
+            let hover = client.request::<HoverRequest>(HoverParams {
+                text_document_position_params: at(&uri, line, character),
+                work_done_progress_params: Default::default(),
+            })?;
+            Ok(match hover.map(|hover| hover.contents) {
+                Some(HoverContents::Markup(content)) => content.value,
+                _ => String::new(),
+            })
+        };
+        assert!(hover(&mut client, 3, 2)?.contains("Rust edition"));
+        assert!(hover(&mut client, 5, 3)?.starts_with("**[dependencies]**"));
+        assert!(hover(&mut client, 6, 30)?.contains("Features of the dependency"));
+        assert!(hover(&mut client, 3, 12)?.is_empty());
+        client.stop()
+    }
+
+    #[test]
+    fn definitions_in_workspace() -> Result<()> {
+        let root =
+            std::env::temp_dir().join(format!("manifest_parser_rs-lsp-{}", std::process::id()));
+        std::fs::create_dir_all(&root)?;
+        let root = root.canonicalize()?;
+        std::fs::create_dir_all(root.join("app"))?;
+        std::fs::create_dir_all(root.join("core"))?;
+        std::fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"app\", \"core\"]\n\n[workspace.package]\nedition = \"2021\"\n\n[workspace.dependencies]\nserde = { version = \"1.0\" }\n\n[workspace.lints.rust]\nunsafe_code = \"forbid\"\n",
+        )?;
+        std::fs::write(root.join("core/Cargo.toml"), MANIFEST)?;
+        let app = "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition.workspace = true\n\n[dependencies]\nserde = { workspace = true }\ncore = { path = \"../core\" }\n\n[lints]\nworkspace = true\n";
+
+        let root_uri = uri(&root.join("Cargo.toml"))?;
+        let core_uri = uri(&root.join("core/Cargo.toml"))?;
+        let mut client = Client::start()?;
+        let uri = uri(&root.join("app/Cargo.toml"))?;
+        client.open(&uri, app)?;
+        let mut define = |line, character| {
+            client.request::<GotoDefinition>(GotoDefinitionParams {
+                text_document_position_params: at(&uri, line, character),
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            })
+        };
+        let location = |uri: Uri, line, start, end| {
+            Some(GotoDefinitionResponse::Scalar(Location::new(
+                uri,
+                Range::new(Position::new(line, start), Position::new(line, end)),
+            )))
+        };
+
+        assert_eq!(define(6, 2)?, location(root_uri.clone(), 7, 0, 5));
+        assert_eq!(define(3, 1)?, location(root_uri.clone(), 4, 0, 7));
+        assert_eq!(define(10, 1)?, location(root_uri, 9, 0, 22));
+        assert_eq!(define(7, 20)?, location(core_uri, 0, 0, 0));
+        assert_eq!(define(1, 1)?, None);
+        client.stop()
+    }
+
+    #[test]
+    fn formatting() -> Result<()> {
+        let mut client = Client::start()?;
+        let uri = uri(Path::new("/project/Cargo.toml"))?;
+        let text = "[package]\nname = \"app\"\nversion = \"0.1.0\"\n[dependencies]\nserde=\"1\"\nanyhow = \"1\"\n";
+        client.open(&uri, text)?;
+
+        let format = |client: &mut Client| {
+            client.request::<Formatting>(DocumentFormattingParams {
+                text_document: TextDocumentIdentifier::new(uri.clone()),
+                options: FormattingOptions::default(),
+                work_done_progress_params: Default::default(),
+            })
+        };
+        let edits = format(&mut client)?.unwrap();
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].range.end, Position::new(6, 0));
+        assert_eq!(
+            edits[0].new_text,
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[dependencies]\nanyhow = \"1\"\nserde = \"1\"\n"
+        );
+        client.stop()
+    }
+}
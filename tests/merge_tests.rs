@@ -0,0 +1,164 @@
+use anyhow::Result;
+use manifest_parser_rs::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = r#"[package]
+name = "app"
+version = "0.1.0"
+
+[dependencies]
+anyhow = "1"
+serde = "1.0"
+"#;
+
+    #[test]
+    fn independent_changes_merge_cleanly() -> Result<()> {
+        let ours = r#"[package]
+name = "app"
+version = "0.2.0"
+
+[dependencies]
+anyhow = "1"
+serde = "1.0"
+# Errors
+thiserror = "2"
+"#;
+        let theirs = r#"[package]
+name = "app"
+version = "0.1.0"
+license = "MIT"
+
+[dependencies]
+serde = "1.0"
+log = "0.4"
+
+[dev-dependencies]
+tempfile = "3"
+"#;
+        let merge = merge_manifests(BASE, ours, theirs)?;
+
+        assert!(merge.is_clean());
+        assert_eq!(
+            merge.manifest,
+            r#"[package]
+name = "app"
+version = "0.2.0"
+license = "MIT"
+
+[dependencies]
+serde = "1.0"
+# Errors
+thiserror = "2"
+log = "0.4"
+
+[dev-dependencies]
+tempfile = "3"
+"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn our_layout_is_kept() -> Result<()> {
+        let base = r#"[package]
+name = "app"
+version = "0.1.0"
+
+[dependencies]
+toml = "0.8"
+anyhow = "1"
+
+[features]
+default = []
+"#;
+        let ours = r#"[package]
+name="app"
+version = "0.1.0"
+
+[features]
+default = []   # nothing yet
+
+[dependencies]
+toml    = "0.8"
+anyhow  = "1"
+serde   = {version="1"}
+"#;
+        let theirs = base.replace("anyhow = \"1\"", "anyhow = \"1.0.90\"");
+        let merge = merge_manifests(base, ours, &theirs)?;
+
+        assert!(merge.is_clean());
+        assert_eq!(
+            merge.manifest,
+            ours.replace("anyhow  = \"1\"", "anyhow = \"1.0.90\"")
+        );
+        assert_eq!(merge_manifests(base, ours, base)?.manifest, ours);
+        Ok(())
+    }
+
+    #[test]
+    fn removed_entries_and_tables() -> Result<()> {
+        let ours = format!(
+            "{}\n# Testing\n[dev-dependencies]\ntempfile = \"3\"\n",
+            BASE
+        );
+        let base = ours.clone();
+        let theirs = BASE.replace("anyhow = \"1\"\n", "");
+        let merge = merge_manifests(&base, &ours, &theirs)?;
+
+        assert!(merge.is_clean());
+        assert_eq!(merge.manifest, theirs);
+        Ok(())
+    }
+
+    #[test]
+    fn same_change_on_both_sides() -> Result<()> {
+        let bumped = BASE.replace("serde = \"1.0\"", "serde = \"1.1\"");
+        let merge = merge_manifests(BASE, &bumped, &bumped)?;
+
+        assert!(merge.is_clean());
+        assert_eq!(merge.manifest, bumped);
+        Ok(())
+    }
+
+    #[test]
+    fn conflicting_bumps() -> Result<()> {
+        let ours = BASE.replace("serde = \"1.0\"", "serde = \"1.1\"");
+        let theirs = BASE.replace(
+            "serde = \"1.0\"",
+            "serde = { version = \"1.2\", features = [\"derive\"] }",
+        );
+        let merge = merge_manifests(BASE, &ours, &theirs)?;
+
+        assert_eq!(
+            merge.conflicts,
+            vec![Conflict {
+                table: "[dependencies]".to_string(),
+                key: "serde".to_string(),
+                base: Some("serde = \"1.0\"".to_string()),
+                ours: Some("serde = \"1.1\"".to_string()),
+                theirs: Some("serde = { version = \"1.2\", features = [\"derive\"] }".to_string()),
+            }]
+        );
+        assert!(merge.manifest.ends_with(
+            "anyhow = \"1\"\n<<<<<<< ours\nserde = \"1.1\"\n=======\nserde = { version = \"1.2\", features = [\"derive\"] }\n>>>>>>> theirs\n"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn removal_against_change_conflicts() -> Result<()> {
+        let ours = BASE.replace("serde = \"1.0\"\n", "");
+        let theirs = BASE.replace("serde = \"1.0\"", "serde = \"1.1\"");
+        let merge = merge_manifests(BASE, &ours, &theirs)?;
+
+        assert_eq!(merge.conflicts.len(), 1);
+        assert_eq!(
+            merge.conflicts[0].to_string(),
+            "[dependencies] serde: ours removed, theirs serde = \"1.1\""
+        );
+        Ok(())
+    }
+}
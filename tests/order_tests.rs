@@ -0,0 +1,135 @@
+use anyhow::Result;
+use manifest_parser_rs::*;
+use std::process::Command;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"[package]
+name = "app"
+version = "0.1.0"
+edition = "2021"
+authors = ["Jane Doe"]
+
+[[bin]]
+name = "first"
+
+[dependencies]
+zstd = "0.13"
+serde = { version = "1.0", features = ["derive"] }
+anyhow = "1.0"
+
+[profile.release.package.zstd]
+opt-level = 3
+
+[profile.release]
+lto = true
+codegen-units = 1
+
+[[bin]]
+name = "second"
+path = "src/second.rs"
+
+[features]
+std = []
+default = ["std"]
+"#;
+
+    const SECTIONS: [&str; 6] = [
+        "package",
+        "bin",
+        "dependencies",
+        "profile.release.package.zstd",
+        "profile.release",
+        "features",
+    ];
+
+    #[test]
+    fn sections_and_keys_in_document_order() -> Result<()> {
+        for backend in [Backend::Pest, Backend::Handwritten] {
+            let manifest = Manifest::parse_with(MANIFEST, backend)?;
+            assert_eq!(manifest.sections().collect::<Vec<_>>(), SECTIONS);
+            assert_eq!(
+                manifest.entries("package")?.collect::<Vec<_>>(),
+                [
+                    ("name", "app"),
+                    ("version", "0.1.0"),
+                    ("edition", "2021"),
+                    ("authors", "[\"Jane Doe\"]")
+                ]
+            );
+            let keys: Vec<&String> = manifest.get_by_section("dependencies")?.keys().collect();
+            assert_eq!(keys, ["zstd", "serde", "anyhow"]);
+
+            // A repeated `[[bin]]` replaces the first one but keeps its place
+            let (section, entries) = manifest.iter().nth(1).unwrap();
+            assert_eq!(section, "bin");
+            assert_eq!(entries.keys().collect::<Vec<_>>(), ["name", "path"]);
+            assert_eq!(entries["name"], "second");
+        }
+
+        let borrowed = BorrowedManifest::parse(MANIFEST)?;
+        assert_eq!(borrowed.sections().collect::<Vec<_>>(), SECTIONS);
+        let (_, features) = borrowed.iter().last().unwrap();
+        assert_eq!(features.keys().collect::<Vec<_>>(), [&"std", &"default"]);
+        Ok(())
+    }
+
+    #[test]
+    fn value_tree_in_document_order() -> Result<()> {
+        let manifest = Manifest::parse(MANIFEST)?;
+        let json = serde_json::to_string(&manifest)?;
+        let position = |text: &str| json.find(text).unwrap();
+        assert!(position("\"package\"") < position("\"bin\""));
+        assert!(position("\"zstd\"") < position("\"serde\""));
+        assert!(position("\"serde\"") < position("\"anyhow\""));
+        assert!(position("\"std\"") < position("\"default\""));
+
+        // `[profile.release]` comes after its subtable but keeps the subtable's place
+        let Value::Table(release) = &manifest.query("profile.release")?[0].value else {
+            panic!("profile.release is not a table");
+        };
+        let keys: Vec<&String> = release.keys().collect();
+        assert_eq!(keys, ["lto", "codegen-units", "package"]);
+
+        let features = manifest.query("features.*")?;
+        let paths: Vec<&str> = features.iter().map(|found| found.path.as_str()).collect();
+        assert_eq!(paths, ["features.std", "features.default"]);
+        Ok(())
+    }
+
+    #[test]
+    fn cli_output_in_document_order() -> Result<()> {
+        let path = std::env::temp_dir().join(format!(
+            "manifest_parser_rs-order-{}.toml",
+            std::process::id()
+        ));
+        std::fs::write(&path, MANIFEST)?;
+        let file = path.to_str().unwrap();
+        let run = |args: &[&str]| -> Result<String> {
+            let output = Command::new(env!("CARGO_BIN_EXE_manifest_parser_rs"))
+                .args(args)
+                .output()?;
+            assert!(output.status.success());
+            Ok(String::from_utf8(output.stdout)?)
+        };
+
+        let text = run(&["get-by-section", file, "dependencies"])?;
+        assert_eq!(
+            text,
+            "Values in section [dependencies]:\nzstd = 0.13\nserde = { version = \"1.0\", features = [\"derive\"] }\nanyhow = 1.0\n"
+        );
+        let json = run(&["get-by-section", file, "dependencies", "--format", "json"])?;
+        assert!(json.find("zstd").unwrap() < json.find("anyhow").unwrap());
+
+        let sections = run(&["parse", file])?;
+        let listed: Vec<&str> = sections
+            .lines()
+            .filter_map(|line| line.strip_prefix("- "))
+            .collect();
+        assert_eq!(listed, SECTIONS);
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+}
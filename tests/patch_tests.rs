@@ -0,0 +1,154 @@
+use anyhow::Result;
+use manifest_parser_rs::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+        [package]
+        name = "app"
+        version = "0.1.0"
+
+        [dependencies]
+        serde = "1.0.0"
+        rand = "0.8.5"
+        helper = { git = "https://github.com/example/helper" }
+        internal = { version = "2.0.0", registry = "company" }
+
+        [patch.crates-io]
+        serde = { path = "../serde" }
+        tokio = { git = "https://github.com/tokio-rs/tokio", branch = "master" }
+
+        [patch.'https://github.com/example/helper.git']
+        helper = { path = "../helper" }
+
+        [replace]
+        "rand:0.8.5" = { git = "https://github.com/rust-random/rand", rev = "abc123" }
+        "internal:1.0.0" = { path = "../internal" }
+    "#;
+
+    #[test]
+    fn patch_and_replace_tables() -> Result<()> {
+        let manifest = Manifest::parse(MANIFEST)?;
+
+        let patches: Vec<&PatchEntry> = manifest.patches().collect();
+        assert_eq!(patches.len(), 3);
+        assert_eq!(patches[0].source, "crates-io");
+        assert_eq!(patches[0].replacement.path.as_deref(), Some("../serde"));
+        assert_eq!(patches[1].replacement.branch.as_deref(), Some("master"));
+        assert_eq!(patches[2].source, "https://github.com/example/helper.git");
+
+        let replacements: Vec<&ReplaceEntry> = manifest.replacements().collect();
+        assert_eq!(replacements[0].package, "rand");
+        assert_eq!(replacements[0].version, "0.8.5");
+        assert_eq!(replacements[0].replacement.rev.as_deref(), Some("abc123"));
+
+        assert_eq!(
+            manifest.get_by_key("patch.crates-io", "serde")?,
+            r#"{ path = "../serde" }"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn apply_to_dependencies() -> Result<()> {
+        let manifest = Manifest::parse(MANIFEST)?;
+        let patches: Vec<PatchEntry> = manifest.patches().cloned().collect();
+        let replacements: Vec<ReplaceEntry> = manifest.replacements().cloned().collect();
+
+        let report = apply_patches(manifest.dependencies(), &patches, &replacements);
+        let overrides: Vec<Option<Override>> = report
+            .dependencies
+            .iter()
+            .map(|patched| patched.overridden_by)
+            .collect();
+
+        assert_eq!(
+            overrides,
+            vec![
+                Some(Override::Patch(0)),
+                Some(Override::Replace(0)),
+                Some(Override::Patch(2)),
+                None,
+            ]
+        );
+
+        let serde = &report.dependencies[0].dependency;
+        assert_eq!(serde.path.as_deref(), Some("../serde"));
+        assert_eq!(serde.version.as_deref(), Some("1.0.0"));
+
+        let helper = &report.dependencies[2].dependency;
+        assert_eq!(helper.git, None);
+        assert_eq!(helper.path.as_deref(), Some("../helper"));
+
+        assert_eq!(report.unused_patches, vec![1]);
+        assert_eq!(report.unused_replacements, vec![1]);
+        Ok(())
+    }
+
+    #[test]
+    fn patch_subtables() -> Result<()> {
+        let source = r#"[package]
+name = "app"
+version = "0.1.0"
+
+[dependencies]
+foo = "1.0"
+bar = "0.2"
+
+[patch.crates-io.foo]
+git = "https://github.com/example/foo"
+branch = "main"
+
+[patch.crates-io]
+bar = { path = "../bar" }
+"#;
+        for backend in [Backend::Pest, Backend::Handwritten] {
+            let manifest = Manifest::parse_with(source, backend)?;
+            let patches: Vec<PatchEntry> = manifest.patches().cloned().collect();
+            assert_eq!(patches.len(), 2);
+            assert_eq!(patches[0].source, "crates-io");
+            assert_eq!(patches[0].replacement.name, "foo");
+            assert_eq!(
+                patches[0].replacement.git.as_deref(),
+                Some("https://github.com/example/foo")
+            );
+            assert_eq!(patches[0].replacement.branch.as_deref(), Some("main"));
+            assert_eq!(patches[1].replacement.name, "bar");
+
+            let report = apply_patches(manifest.dependencies(), &patches, &[]);
+            assert_eq!(
+                report.dependencies[0].overridden_by,
+                Some(Override::Patch(0))
+            );
+            assert!(report.unused_patches.is_empty());
+        }
+
+        let invalid = source.replace("branch = \"main\"", "colour = \"blue\"");
+        assert!(Manifest::parse(&invalid).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_replace_entries() {
+        let inputs = [
+            r#"[package]
+            name = "app"
+            version = "0.1.0"
+
+            [replace]
+            "rand" = { path = "../rand" }"#,
+            r#"[package]
+            name = "app"
+            version = "0.1.0"
+
+            [replace]
+            rand = { path = "../rand" }"#,
+        ];
+
+        for input in inputs {
+            assert!(Manifest::parse(input).is_err(), "Should fail: {}", input);
+        }
+    }
+}
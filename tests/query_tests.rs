@@ -0,0 +1,174 @@
+use anyhow::Result;
+use manifest_parser_rs::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+        [package]
+        name = "app"
+        version = "0.1.0"
+        edition = "2021"
+
+        [package.metadata.docs.rs]
+        features = ["full", "docs"]
+        all-features = false
+
+        [[bin]]
+        name = "app"
+        path = "src/main.rs"
+
+        [dependencies]
+        serde = { version = "1.0", features = ["derive"] }
+        anyhow = "1.0"
+
+        [target.'cfg(unix)'.dependencies]
+        openssl = "0.10"
+
+        [target.'cfg(windows)'.dependencies]
+        winapi = { version = "0.3", optional = true }
+
+        [profile.release]
+        lto = true
+        opt-level = 3
+    "#;
+
+    fn paths(matches: &[QueryMatch]) -> Vec<&str> {
+        matches.iter().map(|found| found.path.as_str()).collect()
+    }
+
+    #[test]
+    fn nested_tables() -> Result<()> {
+        let manifest = Manifest::parse(MANIFEST)?;
+
+        let features = manifest.query("package.metadata.docs.rs.features")?;
+        assert_eq!(
+            features[0].value,
+            Value::Array(vec![
+                Value::String("full".to_string()),
+                Value::String("docs".to_string())
+            ])
+        );
+        assert_eq!(
+            manifest.query("package.metadata.docs.rs.all-features")?[0].value,
+            Value::Boolean(false)
+        );
+        assert_eq!(
+            manifest.query("profile.release.opt-level")?[0].value,
+            Value::Integer(3)
+        );
+        assert_eq!(
+            manifest.query("package.name")?[0].value.as_str(),
+            Some("app")
+        );
+        assert!(manifest.query("package.license")?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn wildcards() -> Result<()> {
+        let manifest = Manifest::parse(MANIFEST)?;
+
+        let versions = manifest.query("dependencies.*.version")?;
+        assert_eq!(
+            paths(&versions),
+            ["dependencies.serde.version", "dependencies.anyhow.version"]
+        );
+        assert_eq!(versions[0].value.as_str(), Some("1.0"));
+
+        let openssl = manifest.query("target.*.dependencies.openssl")?;
+        assert_eq!(paths(&openssl), ["target.'cfg(unix)'.dependencies.openssl"]);
+
+        let optional = manifest.query("target.'cfg(windows)'.dependencies.*.optional")?;
+        assert_eq!(optional[0].value, Value::Boolean(true));
+        Ok(())
+    }
+
+    #[test]
+    fn array_indexes() -> Result<()> {
+        let manifest = Manifest::parse(MANIFEST)?;
+
+        assert_eq!(
+            manifest.query("package.metadata.docs.rs.features[1]")?[0].value,
+            Value::String("docs".to_string())
+        );
+        assert_eq!(
+            paths(&manifest.query("package.metadata.docs.rs.features[*]")?),
+            [
+                "package.metadata.docs.rs.features[0]",
+                "package.metadata.docs.rs.features[1]"
+            ]
+        );
+        assert_eq!(
+            manifest.query("bin[0].path")?[0].value.as_str(),
+            Some("src/main.rs")
+        );
+        assert!(manifest.query("bin[1].path")?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn nested_tables_are_not_expanded() -> Result<()> {
+        let manifest = Manifest::parse(
+            r#"[package]
+name = "app"
+version = "0.1.0"
+
+[package.metadata.dependencies]
+tool = "1"
+
+[patch.crates-io.foo]
+git = "https://github.com/example/foo"
+
+[workspace.dependencies]
+serde = "1.0"
+"#,
+        )?;
+        let value = serde_json::to_value(&manifest)?;
+        assert_eq!(value["package"]["metadata"]["dependencies"]["tool"], "1");
+        assert_eq!(
+            value["patch"]["crates-io"]["foo"]["git"],
+            "https://github.com/example/foo"
+        );
+        assert_eq!(
+            value["workspace"]["dependencies"]["serde"]["version"],
+            "1.0"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn arrays_of_tables() -> Result<()> {
+        let source = "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[[bin]]\nname = \"a\"\npath = \"src/a.rs\"\n\n[[bin]]\nname = \"b\"\n";
+        for backend in [Backend::Pest, Backend::Handwritten] {
+            let manifest = Manifest::parse_with(source, backend)?;
+
+            let names: Vec<String> = manifest
+                .query("bin[*].name")?
+                .iter()
+                .map(|found| format!("{} = {}", found.path, found.value))
+                .collect();
+            assert_eq!(names, ["bin[0].name = \"a\"", "bin[1].name = \"b\""]);
+            assert!(manifest.query("bin[1].path")?.is_empty());
+            assert_eq!(
+                serde_json::to_value(&manifest)?["bin"][0]["path"],
+                "src/a.rs"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_queries() -> Result<()> {
+        let manifest = Manifest::parse(MANIFEST)?;
+
+        for query in ["", "package..name", "target.'cfg(unix)", "bin[x]", "bin[0"] {
+            assert!(matches!(
+                manifest.query(query),
+                Err(ManifestError::InvalidQuery(_, _))
+            ));
+        }
+        Ok(())
+    }
+}
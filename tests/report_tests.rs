@@ -0,0 +1,74 @@
+use anyhow::Result;
+use manifest_parser_rs::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "[package]\nname = \"app\"\nversion = \"0.1.0\"\nlicense = \"MIT\"\ndescription = \"An app\"\nrepository = \"https://github.com/example/app\"\n\n[dependencies]\nserde = \"1\"\nanyhow = \"*\"\n";
+
+    fn diagnostics() -> Result<Vec<Diagnostic>> {
+        let manifest = Manifest::parse(SOURCE)?;
+        Ok(Linter::new().check(&manifest, SOURCE))
+    }
+
+    #[test]
+    fn sarif_log() -> Result<()> {
+        let diagnostics = diagnostics()?;
+        let log: serde_json::Value =
+            serde_json::from_str(&to_sarif([("Cargo.toml", &diagnostics[..])]))?;
+
+        assert_eq!(log["version"], "2.1.0");
+        let run = &log["runs"][0];
+        assert_eq!(
+            run["tool"]["driver"]["rules"][1]["id"],
+            "wildcard-requirement"
+        );
+
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["level"], "warning");
+        assert_eq!(
+            results[0]["fixes"][0]["description"]["text"],
+            "sort the table"
+        );
+        assert_eq!(results[1]["ruleIndex"], 1);
+        assert_eq!(results[1]["level"], "error");
+        let location = &results[1]["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "Cargo.toml");
+        assert_eq!(location["region"]["startLine"], 10);
+        assert_eq!(location["region"]["startColumn"], 1);
+        Ok(())
+    }
+
+    #[test]
+    fn github_annotations() -> Result<()> {
+        let diagnostics = diagnostics()?;
+
+        assert_eq!(
+            to_github_annotations("crates/app/Cargo.toml", &diagnostics),
+            "::warning file=crates/app/Cargo.toml,line=8,col=1,title=unsorted-dependencies::[dependencies] is not sorted alphabetically\n\
+             ::error file=crates/app/Cargo.toml,line=10,col=1,title=wildcard-requirement::anyhow uses a wildcard version requirement\n"
+        );
+
+        let multiline = Diagnostic::new("custom", LintLevel::Warn, "100%\nsure");
+        assert_eq!(
+            to_github_annotations("a,b.toml", &[multiline]),
+            "::warning file=a%2Cb.toml,title=custom::100%25%0Asure\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_errors_are_located() {
+        let source = "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[dependencies]\nserde = { version = 1 }\n";
+        let error = Manifest::parse(source).unwrap_err();
+        let diagnostic = Diagnostic::from_error(&error, source);
+
+        assert_eq!(diagnostic.rule, "parse-error");
+        assert!(diagnostic.is_error());
+        let span = diagnostic.span.unwrap();
+        assert_eq!((span.line, span.column), (6, 21));
+        assert_eq!(&source[span.start..], "1 }\n");
+    }
+}
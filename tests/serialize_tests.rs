@@ -0,0 +1,109 @@
+use anyhow::Result;
+use manifest_parser_rs::*;
+use serde_json::json;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"[package]
+name = "app"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+serde = { version = "1.0", features = ["derive"] }
+anyhow = "1.0"
+
+[target.'cfg(unix)'.dependencies]
+libc = { version = "0.2", optional = true, default-features = false }
+
+[features]
+default = ["std"]
+std = []
+
+[[bin]]
+name = "app"
+path = "src/main.rs"
+
+[profile.release]
+lto = true
+opt-level = 3
+
+[lints.clippy]
+all = { level = "deny", priority = -1 }
+"#;
+
+    #[test]
+    fn manifest_as_tree() -> Result<()> {
+        let manifest = Manifest::parse(MANIFEST)?;
+        let value = serde_json::to_value(&manifest)?;
+        assert_eq!(value["package"]["name"], "app");
+        assert_eq!(value["dependencies"]["anyhow"], json!({ "version": "1.0" }));
+        assert_eq!(
+            value["dependencies"]["serde"]["features"],
+            json!(["derive"])
+        );
+        assert_eq!(
+            value["target"]["cfg(unix)"]["dependencies"]["libc"]["optional"],
+            true
+        );
+        assert_eq!(value["features"], json!({ "default": ["std"], "std": [] }));
+        assert_eq!(
+            value["bin"],
+            json!([{ "name": "app", "path": "src/main.rs" }])
+        );
+        assert_eq!(
+            value["profile"]["release"],
+            json!({ "lto": true, "opt-level": 3 })
+        );
+
+        let yaml = serde_yaml::to_string(&manifest)?;
+        assert!(yaml.contains("opt-level: 3"), "{}", yaml);
+        Ok(())
+    }
+
+    #[test]
+    fn typed_dependencies() -> Result<()> {
+        let manifest = Manifest::parse(MANIFEST)?;
+        let libc = manifest.dependencies().find(|d| d.name == "libc").unwrap();
+        let value = serde_json::to_value(libc)?;
+        assert_eq!(value["kind"], "normal");
+        assert_eq!(value["target"], "cfg(unix)");
+        assert_eq!(value["version"], "0.2");
+        assert_eq!(value["optional"], true);
+        assert_eq!(value["default-features"], false);
+        assert_eq!(value["git"], json!(null));
+
+        let kinds = serde_json::to_value([DependencyKind::Dev, DependencyKind::Build])?;
+        assert_eq!(kinds, json!(["dev", "build"]));
+        let platform = Platform::parse("cfg(all(unix, target_os = \"linux\"))")?;
+        assert_eq!(
+            serde_json::to_value(&platform)?,
+            "cfg(all(unix, target_os = \"linux\"))"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn lints_and_spans() -> Result<()> {
+        let manifest = Manifest::parse(MANIFEST)?;
+        let lints: Vec<&Lint> = manifest.lints().collect();
+        assert_eq!(
+            serde_json::to_value(&lints)?,
+            json!([{
+                "tool": "clippy",
+                "name": "all",
+                "level": "deny",
+                "priority": -1,
+                "check-cfg": []
+            }])
+        );
+
+        let span = manifest.section_span("profile.release").unwrap();
+        let value = serde_json::to_value(span)?;
+        assert_eq!(value["line"], 21);
+        assert_eq!(value["column"], 1);
+        Ok(())
+    }
+}
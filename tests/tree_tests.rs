@@ -0,0 +1,156 @@
+use anyhow::Result;
+use manifest_parser_rs::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const APP: &str = r#"
+        [package]
+        name = "app"
+        version = "0.1.0"
+
+        [dependencies]
+        core = { path = "../core" }
+        log = "0.4"
+
+        [target.'cfg(windows)'.dependencies]
+        winapi = "0.3"
+
+        [dev-dependencies]
+        pretty = "1"
+    "#;
+
+    const CORE: &str = r#"
+        [package]
+        name = "core"
+        version = "0.2.0"
+
+        [dependencies]
+        log = "0.3"
+    "#;
+
+    const LOCK: &str = r#"
+version = 3
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = ["core", "log 0.4.20", "pretty", "winapi"]
+
+[[package]]
+name = "core"
+version = "0.2.0"
+dependencies = ["log 0.3.9"]
+
+[[package]]
+name = "log"
+version = "0.3.9"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = ["log 0.4.20"]
+
+[[package]]
+name = "log"
+version = "0.4.20"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "pretty"
+version = "1.2.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "winapi"
+version = "0.3.9"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+
+    fn workspace() -> Result<(Vec<Manifest>, Lockfile)> {
+        Ok((
+            vec![Manifest::parse(APP)?, Manifest::parse(CORE)?],
+            Lockfile::parse(LOCK)?,
+        ))
+    }
+
+    #[test]
+    fn workspace_tree() -> Result<()> {
+        let (manifests, lock) = workspace()?;
+        let tree = render_tree(&manifests, Some(&lock), &TreeOptions::default())?;
+        assert_eq!(
+            tree,
+            "\
+app v0.1.0
+├── core v0.2.0
+│   └── log v0.3.9
+│       └── log v0.4.20
+├── log v0.4.20
+└── winapi v0.3.9
+[dev-dependencies]
+└── pretty v1.2.0
+
+core v0.2.0
+└── log v0.3.9
+    └── log v0.4.20
+"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn filters() -> Result<()> {
+        let (manifests, lock) = workspace()?;
+        let options = TreeOptions {
+            kinds: vec![DependencyKind::Normal],
+            depth: Some(1),
+            target: CfgSet::for_target("x86_64-unknown-linux-gnu"),
+            package: Some("app".to_string()),
+            ..TreeOptions::default()
+        };
+        assert_eq!(
+            render_tree(&manifests, Some(&lock), &options)?,
+            "app v0.1.0\n├── core v0.2.0\n└── log v0.4.20\n"
+        );
+
+        let unlocked = render_tree(&manifests[1..], None, &TreeOptions::default())?;
+        assert_eq!(unlocked, "core v0.2.0\n└── log 0.3\n");
+        Ok(())
+    }
+
+    #[test]
+    fn inverted_and_duplicates() -> Result<()> {
+        let (manifests, lock) = workspace()?;
+        let options = TreeOptions {
+            invert: Some("core".to_string()),
+            ..TreeOptions::default()
+        };
+        assert_eq!(
+            render_tree(&manifests, Some(&lock), &options)?,
+            "core v0.2.0\n└── app v0.1.0\n"
+        );
+
+        let options = TreeOptions {
+            duplicates: true,
+            depth: Some(1),
+            ..TreeOptions::default()
+        };
+        assert_eq!(
+            render_tree(&manifests, Some(&lock), &options)?,
+            "log v0.3.9\n└── core v0.2.0\n\nlog v0.4.20\n├── app v0.1.0\n└── log v0.3.9\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_package() -> Result<()> {
+        let (manifests, lock) = workspace()?;
+        let options = TreeOptions {
+            invert: Some("serde".to_string()),
+            ..TreeOptions::default()
+        };
+        assert!(matches!(
+            render_tree(&manifests, Some(&lock), &options),
+            Err(ManifestError::UnknownPackage(name)) if name == "serde"
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+use anyhow::Result;
+use manifest_parser_rs::*;
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates an empty directory unique to the calling test.
+    fn scratch(test: &str) -> Result<PathBuf> {
+        let dir = std::env::temp_dir().join(format!(
+            "manifest_parser_rs-watch-{}-{}",
+            test,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    fn package(name: &str) -> String {
+        format!("[package]\nname = \"{}\"\nversion = \"0.1.0\"\n", name)
+    }
+
+    #[test]
+    fn members_of_workspace() -> Result<()> {
+        let root = scratch("members")?;
+        for member in ["app", "crates/core", "crates/old", "crates/empty"] {
+            std::fs::create_dir_all(root.join(member))?;
+        }
+        for member in ["app", "crates/core", "crates/old"] {
+            std::fs::write(root.join(member).join("Cargo.toml"), package("member"))?;
+        }
+        let manifest = Manifest::parse(&format!(
+            "{}\n[workspace]\nmembers = [\"app\", \"crates/*\"]\nexclude = [\"crates/o?d\"]\n",
+            package("root")
+        ))?;
+
+        assert_eq!(
+            workspace_members(&manifest, &root),
+            vec![
+                root.join("app/Cargo.toml"),
+                root.join("crates/core/Cargo.toml"),
+            ]
+        );
+        assert!(workspace_members(&Manifest::parse(&package("app"))?, &root).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn polls_changes() -> Result<()> {
+        let root = scratch("poll")?;
+        let (first, second) = (root.join("a.toml"), root.join("b.toml"));
+        std::fs::write(&first, package("a"))?;
+
+        let mut watcher = Watcher::new();
+        assert!(watcher.watch(first.clone()));
+        assert!(watcher.watch(second.clone()));
+        assert!(!watcher.watch(first.clone()));
+        assert!(watcher.poll().is_empty());
+
+        std::fs::write(&first, package("ab"))?;
+        std::fs::write(&second, package("b"))?;
+        assert_eq!(watcher.poll(), vec![first.clone(), second.clone()]);
+        assert!(watcher.poll().is_empty());
+
+        std::fs::remove_file(&first)?;
+        assert_eq!(watcher.poll(), vec![first]);
+        Ok(())
+    }
+
+    #[test]
+    fn debounces_bursts() -> Result<()> {
+        let root = scratch("debounce")?;
+        let file = root.join("Cargo.toml");
+        std::fs::write(&file, package("a"))?;
+
+        let mut watcher =
+            Watcher::new().with_timing(Duration::from_millis(10), Duration::from_millis(300));
+        watcher.watch(file.clone());
+        let writer = std::thread::spawn({
+            let file = file.clone();
+            move || -> std::io::Result<()> {
+                for name in ["ab", "abc", "abcd"] {
+                    std::thread::sleep(Duration::from_millis(50));
+                    std::fs::write(&file, package(name))?;
+                }
+                Ok(())
+            }
+        });
+
+        assert_eq!(watcher.wait(), vec![file]);
+        writer.join().unwrap()?;
+        assert!(watcher.poll().is_empty());
+        Ok(())
+    }
+}